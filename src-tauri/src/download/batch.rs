@@ -0,0 +1,403 @@
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use url::Url;
+use uuid::Uuid;
+
+use crate::download::error::DownloadError;
+use crate::download::manager::try_mirrors;
+use crate::network::{ClientSnapshot, SharedClient};
+
+/// 同时下载的文件数
+const BATCH_CONCURRENCY: usize = 16;
+/// 计算SHA-1时每次读取的大小
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 批量下载中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    /// 下载地址，启用镜像时会按镜像规则改写
+    pub url: String,
+    /// 保存路径
+    pub path: PathBuf,
+    /// 文件大小
+    pub size: Option<u64>,
+    /// SHA-1
+    pub sha1: Option<String>,
+}
+
+/// 批量下载的进度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BatchProgress {
+    /// 文件总数
+    pub total: usize,
+    /// 已完成的文件数，包括跳过的文件
+    pub completed: usize,
+    /// 已存在且校验通过而跳过的文件数
+    pub skipped: usize,
+    /// 失败的文件数
+    pub failed: usize,
+    /// 已知大小的文件的总字节数
+    pub total_bytes: u64,
+    /// 已完成的文件的字节数
+    pub completed_bytes: u64,
+}
+
+/// 批量下载小文件
+///
+/// 不创建下载任务，也不分段，适合资源文件、依赖库等数量多、体积小的文件。
+/// 已存在且大小和SHA-1一致的文件会被跳过；下载后校验失败的文件不会保留。
+/// 连接错误、超时和5xx响应按网络配置中的重试设置退避重试，其他错误不重试；
+/// 单个文件失败时继续下载其他文件，全部结束后返回[`DownloadError::BatchFailed`]。
+pub async fn download_batch(
+    http: &SharedClient,
+    items: Vec<BatchItem>,
+    on_progress: impl Fn(BatchProgress),
+) -> Result<BatchProgress, DownloadError> {
+    let snapshot = http.snapshot();
+    let mut progress = BatchProgress {
+        total: items.len(),
+        total_bytes: items.iter().filter_map(|item| item.size).sum(),
+        ..BatchProgress::default()
+    };
+    let mut first_error = None;
+
+    let mut results = stream::iter(items)
+        .map(|item| {
+            let snapshot = snapshot.clone();
+            async move {
+                let result = fetch_item(&snapshot, &item).await;
+                (item, result)
+            }
+        })
+        .buffer_unordered(BATCH_CONCURRENCY);
+
+    while let Some((item, result)) = results.next().await {
+        match result {
+            Ok(downloaded) => {
+                progress.completed += 1;
+                progress.completed_bytes += item.size.unwrap_or(0);
+                if !downloaded {
+                    progress.skipped += 1;
+                }
+            }
+            Err(e) => {
+                progress.failed += 1;
+                first_error.get_or_insert_with(|| format!("{}: {}", item.path.display(), e));
+            }
+        }
+        on_progress(progress);
+    }
+
+    match first_error {
+        Some(reason) => Err(DownloadError::BatchFailed {
+            failed: progress.failed,
+            reason,
+        }),
+        None => Ok(progress),
+    }
+}
+
+/// 检查文件是否已存在且与预期的大小和SHA-1一致，没有SHA-1时只比较大小
+pub async fn verify_file(
+    path: &Path,
+    size: Option<u64>,
+    sha1: Option<&str>,
+) -> Result<bool, DownloadError> {
+    let metadata = match tokio::fs::metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.is_file() || size.is_some_and(|size| size != metadata.len()) {
+        return Ok(false);
+    }
+    match sha1 {
+        Some(expected) => Ok(file_sha1(path).await?.eq_ignore_ascii_case(expected)),
+        None => Ok(true),
+    }
+}
+
+/// 计算文件的SHA-1，在阻塞线程中读取
+pub async fn file_sha1(path: &Path) -> Result<String, DownloadError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha1::new();
+        let mut buf = vec![0; HASH_CHUNK_SIZE];
+        loop {
+            let len = file.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            hasher.update(&buf[..len]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await
+    .map_err(|e| DownloadError::Other(e.to_string()))?
+}
+
+/// 下载单个文件，返回是否实际进行了下载
+async fn fetch_item(http: &ClientSnapshot, item: &BatchItem) -> Result<bool, DownloadError> {
+    if verify_file(&item.path, item.size, item.sha1.as_deref()).await? {
+        return Ok(false);
+    }
+    if let Some(parent) = item.path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let retry = &http.config.retry;
+    let mut retries = 0;
+    loop {
+        match try_mirrors(http, &item.url, |candidate| {
+            download_file(http, candidate, item)
+        })
+        .await
+        {
+            Ok(_) => return Ok(true),
+            Err(e) if e.is_retryable() && retries < retry.max_retries => {
+                tokio::time::sleep(retry.backoff(retries)).await;
+                retries += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 下载文件，失败时删除本次尝试的临时文件
+async fn download_file(
+    http: &ClientSnapshot,
+    url: Url,
+    item: &BatchItem,
+) -> Result<(), DownloadError> {
+    let read_timeout = http.config.read_timeout();
+    let response = tokio::time::timeout(read_timeout, http.send(http.client.get(url)))
+        .await
+        .map_err(|_| DownloadError::ReadTimeout)??;
+    if !response.status().is_success() {
+        return Err(DownloadError::HttpStatus(response.status().as_u16()));
+    }
+
+    // 每次尝试使用不同的临时文件，同时下载同一文件时不会写入同一个临时文件
+    let mut temp_path = item.path.clone().into_os_string();
+    temp_path.push(format!(".part-{}", Uuid::new_v4()));
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = save_response(response, &temp_path, item, read_timeout).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
+
+/// 将响应写入临时文件，校验通过后重命名为目标文件
+async fn save_response(
+    response: reqwest::Response,
+    temp_path: &Path,
+    item: &BatchItem,
+    read_timeout: std::time::Duration,
+) -> Result<(), DownloadError> {
+    let mut file = tokio::fs::File::create(temp_path).await?;
+    let mut hasher = Sha1::new();
+    let mut len = 0u64;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = tokio::time::timeout(read_timeout, stream.next())
+        .await
+        .map_err(|_| DownloadError::ReadTimeout)?
+    {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        hasher.update(&chunk);
+        len += chunk.len() as u64;
+    }
+    file.sync_data().await?;
+    drop(file);
+
+    let sha1 = format!("{:x}", hasher.finalize());
+    let size_matches = item.size.is_none_or(|size| size == len);
+    let sha1_matches = item
+        .sha1
+        .as_deref()
+        .is_none_or(|expected| sha1.eq_ignore_ascii_case(expected));
+    if !size_matches || !sha1_matches {
+        return Err(DownloadError::ChecksumMismatch(
+            item.path.display().to_string(),
+        ));
+    }
+
+    tokio::fs::rename(temp_path, &item.path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{NetworkError, ProxyConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// 不经过系统代理、重试等待很短的客户端
+    fn client(max_retries: u32) -> SharedClient {
+        let shared = SharedClient::new();
+        // 代理模式没有从网络模块导出，按配置文件中的格式指定
+        let proxy: ProxyConfig = serde_json::from_str(r#"{ "mode": "direct" }"#).unwrap();
+        shared.set_proxy(proxy).unwrap();
+        let mut config = shared.config();
+        config.retry.max_retries = max_retries;
+        config.retry.initial_backoff_ms = 1;
+        config.retry.max_backoff_ms = 1;
+        shared.set_config(config).unwrap();
+        shared
+    }
+
+    /// 本地HTTP服务，每个连接只处理一个请求，返回地址和收到的请求数
+    async fn serve(status: &'static str, body: &'static [u8]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(len) if len > 0 => request.extend_from_slice(&buf[..len]),
+                        _ => break,
+                    }
+                }
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body).await;
+            }
+        });
+        (format!("http://{}/file", addr), requests)
+    }
+
+    fn item(url: &str, dir: &Path) -> BatchItem {
+        BatchItem {
+            url: url.to_string(),
+            path: dir.join("file"),
+            size: None,
+            sha1: None,
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novacl-batch-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn classifies_retryable_errors() {
+        assert!(DownloadError::ReadTimeout.is_retryable());
+        assert!(DownloadError::HttpStatus(503).is_retryable());
+        assert!(DownloadError::Network(NetworkError::Timeout).is_retryable());
+        assert!(
+            DownloadError::Network(NetworkError::ConnectionError(String::new())).is_retryable()
+        );
+        assert!(!DownloadError::HttpStatus(404).is_retryable());
+        assert!(!DownloadError::ChecksumMismatch(String::new()).is_retryable());
+        assert!(!DownloadError::IoError(io::Error::other("disk full")).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (url, requests) = serve("503 Service Unavailable", b"").await;
+        let dir = temp_dir();
+
+        let result = download_batch(&client(2), vec![item(&url, &dir)], |_| {}).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(matches!(
+            result,
+            Err(DownloadError::BatchFailed { failed: 1, .. })
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, requests) = serve("404 Not Found", b"").await;
+        let dir = temp_dir();
+
+        let result = download_batch(&client(2), vec![item(&url, &dir)], |_| {}).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(matches!(
+            result,
+            Err(DownloadError::BatchFailed { failed: 1, .. })
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_checksum_mismatch() {
+        let (url, requests) = serve("200 OK", b"content").await;
+        let dir = temp_dir();
+        let mut mismatched = item(&url, &dir);
+        mismatched.sha1 = Some("0".repeat(40));
+
+        let result = download_batch(&client(2), vec![mismatched], |_| {}).await;
+        assert!(matches!(
+            result,
+            Err(DownloadError::BatchFailed { failed: 1, .. })
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(!dir.join("file").exists());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn downloads_same_file_concurrently() {
+        let (url, _) = serve("200 OK", b"content").await;
+        let dir = temp_dir();
+        let mut file = item(&url, &dir);
+        file.sha1 = Some("040f06fd774092478d450774f5ba30c5da78acc8".to_string());
+        let http = client(0);
+
+        let (first, second) = tokio::join!(
+            download_batch(&http, vec![file.clone()], |_| {}),
+            download_batch(&http, vec![file.clone()], |_| {}),
+        );
+        let content = std::fs::read(&file.path).unwrap();
+        let entries = std::fs::read_dir(&dir).unwrap().count();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert_eq!(content, b"content");
+        // 只剩下目标文件，没有残留的临时文件
+        assert_eq!(entries, 1);
+    }
+
+    #[tokio::test]
+    async fn downloads_and_skips_verified_files() {
+        let (url, requests) = serve("200 OK", b"content").await;
+        let dir = temp_dir();
+        let mut file = item(&url, &dir);
+        file.size = Some(7);
+        file.sha1 = Some("040f06fd774092478d450774f5ba30c5da78acc8".to_string());
+        let http = client(2);
+
+        let progress = download_batch(&http, vec![file.clone()], |_| {})
+            .await
+            .unwrap();
+        assert_eq!((progress.completed, progress.skipped), (1, 0));
+        assert_eq!(std::fs::read(&file.path).unwrap(), b"content");
+
+        let progress = download_batch(&http, vec![file], |_| {}).await.unwrap();
+        assert_eq!((progress.completed, progress.skipped), (1, 1));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
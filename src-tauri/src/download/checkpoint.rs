@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::download::error::DownloadError;
+use crate::download::task::{DownloadStatus, DownloadTask, SegmentProgress};
+
+/// 下载任务的断点信息，用于在下次启动时继续下载
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCheckpoint {
+    /// 任务ID
+    pub id: String,
+    /// 下载URL
+    pub url: String,
+    /// 跟随重定向后的最终URL
+    pub resolved_url: String,
+    /// 保存路径
+    pub save_path: PathBuf,
+    /// 文件名
+    pub filename: String,
+    /// 分段数量
+    pub segments: usize,
+    /// 文件总大小
+    pub total: u64,
+    /// 各分段的下载进度
+    pub segment_progress: Vec<SegmentProgress>,
+}
+
+impl TaskCheckpoint {
+    /// 从下载任务生成断点信息
+    pub fn from_task(task: &DownloadTask) -> Self {
+        Self {
+            id: task.id.clone(),
+            url: task.url.clone(),
+            resolved_url: task.resolved_url.clone(),
+            save_path: task.save_path.clone(),
+            filename: task.filename.clone(),
+            segments: task.segments,
+            total: task.get_progress().total,
+            segment_progress: task.get_segments(),
+        }
+    }
+
+    /// 根据断点信息恢复下载任务，恢复后的任务处于暂停状态
+    ///
+    /// 如果临时文件已丢失，则丢弃分段进度，恢复时从头开始下载。
+    pub fn into_task(self) -> DownloadTask {
+        let task = DownloadTask::new(
+            self.id,
+            self.url,
+            self.resolved_url,
+            self.save_path,
+            self.filename,
+            self.segments,
+        );
+
+        if task.temp_path().exists() {
+            task.set_total_size(self.total);
+            task.set_segments(self.segment_progress);
+            task.update_progress(task.downloaded_bytes());
+        }
+        task.set_status(DownloadStatus::Paused);
+
+        task
+    }
+}
+
+/// 从文件中读取所有断点信息，文件不存在时返回空列表
+pub fn load_checkpoints(path: &Path) -> Result<Vec<TaskCheckpoint>, DownloadError> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| DownloadError::Other(format!("解析断点文件失败: {}", e)))
+}
+
+/// 将断点信息写入文件
+///
+/// 先写入临时文件再重命名，避免写入中途退出导致断点文件损坏。
+pub fn save_checkpoints(path: &Path, checkpoints: &[TaskCheckpoint]) -> Result<(), DownloadError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(checkpoints)
+        .map_err(|e| DownloadError::Other(format!("序列化断点信息失败: {}", e)))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novacl-checkpoint-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn checkpoint(save_path: &Path) -> TaskCheckpoint {
+        let mut segments = SegmentProgress::split(100, 2);
+        segments[0].downloaded = 30;
+        TaskCheckpoint {
+            id: "task".to_string(),
+            url: "https://example.com/file.bin".to_string(),
+            resolved_url: "https://cdn.example.com/file.bin".to_string(),
+            save_path: save_path.to_path_buf(),
+            filename: "file.bin".to_string(),
+            segments: 2,
+            total: 100,
+            segment_progress: segments,
+        }
+    }
+
+    #[test]
+    fn round_trips_checkpoints_through_file() {
+        let dir = temp_dir();
+        let path = dir.join("checkpoints.json");
+
+        save_checkpoints(&path, &[checkpoint(&dir)]).unwrap();
+        let loaded = load_checkpoints(&path).unwrap();
+        let temp_left = path.with_extension("tmp").exists();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(!temp_left);
+        assert_eq!(loaded.len(), 1);
+        let loaded = &loaded[0];
+        assert_eq!(loaded.id, "task");
+        assert_eq!(loaded.resolved_url, "https://cdn.example.com/file.bin");
+        assert_eq!(loaded.save_path, dir);
+        assert_eq!(loaded.total, 100);
+        assert_eq!(loaded.segment_progress.len(), 2);
+        assert_eq!(loaded.segment_progress[0].downloaded, 30);
+    }
+
+    #[test]
+    fn loads_nothing_without_file() {
+        let path = temp_dir().join("checkpoints.json");
+        assert!(load_checkpoints(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn keeps_progress_when_temp_file_exists() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file.bin.part"), [0u8; 100]).unwrap();
+
+        let task = checkpoint(&dir).into_task();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(task.status(), DownloadStatus::Paused);
+        assert_eq!(task.get_segments().len(), 2);
+        let progress = task.get_progress();
+        assert_eq!(progress.total, 100);
+        assert_eq!(progress.downloaded, 30);
+    }
+
+    #[test]
+    fn discards_progress_when_temp_file_is_missing() {
+        let dir = temp_dir();
+
+        let task = checkpoint(&dir).into_task();
+
+        assert_eq!(task.status(), DownloadStatus::Paused);
+        assert!(task.get_segments().is_empty());
+        let progress = task.get_progress();
+        assert_eq!(progress.total, 0);
+        assert_eq!(progress.downloaded, 0);
+    }
+}
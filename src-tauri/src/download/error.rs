@@ -1,6 +1,8 @@
 use std::fmt;
 use std::io;
 
+use crate::network::NetworkError;
+
 /// 下载错误类型
 #[derive(Debug)]
 pub enum DownloadError {
@@ -8,6 +10,10 @@ pub enum DownloadError {
     IoError(io::Error),
     /// HTTP请求错误
     HttpError(String),
+    /// 发送请求或读取响应时的网络错误
+    Network(NetworkError),
+    /// 服务器返回了非2xx状态码
+    HttpStatus(u16),
     /// 无效的URL
     InvalidUrl(String),
     /// 无法获取文件大小
@@ -18,10 +24,27 @@ pub enum DownloadError {
     TaskAlreadyExists(String),
     /// 任务不存在
     TaskNotFound(String),
-    /// 锁定错误
-    LockError,
+    /// 任务当前的状态不允许该操作
+    InvalidState(String),
+    /// 下载管理器已停止运行
+    ManagerStopped,
     /// 文件写入错误
     WriteError(String),
+    /// 下载管理器正在关闭
+    ShuttingDown,
+    /// 等待服务器数据超时
+    ReadTimeout,
+    /// 批量下载作业已取消
+    Cancelled,
+    /// 下载的文件与预期的大小或SHA-1不一致
+    ChecksumMismatch(String),
+    /// 批量下载中有文件失败
+    BatchFailed {
+        /// 失败的文件数
+        failed: usize,
+        /// 第一个失败的文件及原因
+        reason: String,
+    },
     /// 其他错误
     Other(String),
 }
@@ -31,19 +54,49 @@ impl fmt::Display for DownloadError {
         match self {
             Self::IoError(err) => write!(f, "IO错误: {}", err),
             Self::HttpError(err) => write!(f, "HTTP请求错误: {}", err),
+            Self::Network(err) => write!(f, "{}", err),
+            Self::HttpStatus(status) => write!(f, "HTTP错误: {}", status),
             Self::InvalidUrl(url) => write!(f, "无效的URL: {}", url),
             Self::ContentLengthError => write!(f, "无法获取文件大小"),
             Self::RangeNotSupported => write!(f, "服务器不支持断点续传"),
             Self::TaskAlreadyExists(id) => write!(f, "任务已存在: {}", id),
             Self::TaskNotFound(id) => write!(f, "任务不存在: {}", id),
-            Self::LockError => write!(f, "锁定错误"),
+            Self::InvalidState(err) => write!(f, "任务状态无效: {}", err),
+            Self::ManagerStopped => write!(f, "下载管理器已停止运行"),
             Self::WriteError(err) => write!(f, "文件写入错误: {}", err),
+            Self::ShuttingDown => write!(f, "下载管理器正在关闭"),
+            Self::ReadTimeout => write!(f, "等待服务器数据超时"),
+            Self::Cancelled => write!(f, "下载已取消"),
+            Self::ChecksumMismatch(path) => write!(f, "文件校验失败: {}", path),
+            Self::BatchFailed { failed, reason } => {
+                write!(f, "{}个文件下载失败，第一个错误: {}", failed, reason)
+            }
             Self::Other(err) => write!(f, "其他错误: {}", err),
         }
     }
 }
 
-impl std::error::Error for DownloadError {}
+impl DownloadError {
+    /// 是否为可以重试的错误（连接错误、超时和5xx响应）
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(err) => err.is_retryable(),
+            Self::ReadTimeout => true,
+            Self::HttpStatus(status) => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for DownloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            Self::Network(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for DownloadError {
     fn from(err: io::Error) -> Self {
@@ -53,7 +106,7 @@ impl From<io::Error> for DownloadError {
 
 impl From<reqwest::Error> for DownloadError {
     fn from(err: reqwest::Error) -> Self {
-        Self::HttpError(err.to_string())
+        Self::Network(NetworkError::from(err))
     }
 }
 
@@ -65,6 +118,6 @@ impl From<url::ParseError> for DownloadError {
 
 impl From<reqwest::header::InvalidHeaderValue> for DownloadError {
     fn from(err: reqwest::header::InvalidHeaderValue) -> Self {
-        Self::HttpError(err.to_string())
+        Self::Network(NetworkError::from(err))
     }
 }
@@ -0,0 +1,327 @@
+use percent_encoding::percent_decode_str;
+use reqwest::header::{HeaderMap, CONTENT_DISPOSITION};
+use url::Url;
+
+/// 无法解析出文件名时使用的默认名称
+const FALLBACK_FILENAME: &str = "download";
+
+/// 文件名的最大字节数（大多数文件系统的限制）
+const MAX_FILENAME_BYTES: usize = 255;
+
+/// Windows保留的设备名
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 根据响应头和最终URL解析文件名
+///
+/// 优先级：`filename*` > `filename` > 最终URL的路径 > 默认名称
+pub fn resolve_filename(final_url: &Url, headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_disposition)
+        .or_else(|| filename_from_url(final_url))
+        .unwrap_or_else(|| FALLBACK_FILENAME.to_string())
+}
+
+/// 从Content-Disposition头中解析文件名，支持RFC 5987的`filename*`参数
+pub fn parse_content_disposition(value: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+
+    for param in split_params(value).into_iter().skip(1) {
+        let Some((key, val)) = param.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let val = val.trim();
+
+        match key.as_str() {
+            "filename*" => extended = decode_ext_value(val),
+            "filename" => plain = Some(decode_plain_value(val)),
+            _ => {}
+        }
+    }
+
+    extended
+        .and_then(|name| sanitize_filename(&name))
+        .or_else(|| plain.and_then(|name| sanitize_filename(&name)))
+}
+
+/// 从URL路径的最后一个非空段中提取文件名
+pub fn filename_from_url(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.rfind(|segment| !segment.is_empty())?;
+    let decoded = percent_decode_str(segment).decode_utf8_lossy();
+    sanitize_filename(&decoded)
+}
+
+/// 清理文件名，使其可以安全地用于本地文件系统
+///
+/// 去除路径部分和非法字符，处理Windows保留名称，并限制长度。
+/// 清理后为空时返回`None`。
+pub fn sanitize_filename(name: &str) -> Option<String> {
+    // 只保留最后一个路径分量，防止路径穿越
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+
+    let mut cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows不允许文件名以点或空格结尾
+    let trimmed_len = cleaned.trim_end_matches(['.', ' ']).len();
+    cleaned.truncate(trimmed_len);
+    let cleaned = cleaned.trim_start().to_string();
+
+    if cleaned.is_empty() || cleaned.chars().all(|c| c == '.') {
+        return None;
+    }
+
+    let stem = cleaned.split('.').next().unwrap_or("");
+    let mut cleaned = if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        format!("_{}", cleaned)
+    } else {
+        cleaned
+    };
+
+    if cleaned.len() > MAX_FILENAME_BYTES {
+        cleaned = truncate_preserving_extension(&cleaned, MAX_FILENAME_BYTES);
+    }
+
+    Some(cleaned)
+}
+
+/// 按分号拆分头部参数，忽略引号内的分号
+fn split_params(value: &str) -> Vec<String> {
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        if escaped {
+            current.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_quotes => {
+                current.push(c);
+                escaped = true;
+            }
+            '"' => {
+                current.push(c);
+                in_quotes = !in_quotes;
+            }
+            ';' if !in_quotes => params.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    params.push(current);
+
+    params
+}
+
+/// 解码普通的`filename`参数值（可能带引号，部分服务器还会进行百分号编码）
+fn decode_plain_value(value: &str) -> String {
+    let unquoted = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut result = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    if let Some(next) = chars.next() {
+                        result.push(next);
+                    }
+                } else {
+                    result.push(c);
+                }
+            }
+            result
+        }
+        None => value.to_string(),
+    };
+
+    match percent_decode_str(&unquoted).decode_utf8() {
+        Ok(decoded) => decoded.into_owned(),
+        Err(_) => unquoted,
+    }
+}
+
+/// 解码RFC 5987格式的扩展参数值：`charset'language'percent-encoded-value`
+fn decode_ext_value(value: &str) -> Option<String> {
+    let value = value.trim_matches('"');
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+
+    let bytes: Vec<u8> = percent_decode_str(encoded).collect();
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(bytes.into_iter().map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+/// 截断过长的文件名，尽量保留扩展名
+fn truncate_preserving_extension(name: &str, max_bytes: usize) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(idx) if idx > 0 && name.len() - idx <= 16 => name.split_at(idx),
+        _ => (name, ""),
+    };
+
+    let mut budget = max_bytes.saturating_sub(ext.len());
+    while !stem.is_char_boundary(budget) {
+        budget -= 1;
+    }
+
+    format!("{}{}", &stem[..budget], ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_and_unquoted_filename() {
+        assert_eq!(
+            parse_content_disposition("attachment; filename=plain.txt").as_deref(),
+            Some("plain.txt")
+        );
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"a;b.jar\"").as_deref(),
+            Some("a;b.jar")
+        );
+        assert_eq!(
+            parse_content_disposition(r#"attachment; filename="a\"b.txt""#).as_deref(),
+            Some("a_b.txt")
+        );
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"my%20file.txt\"").as_deref(),
+            Some("my file.txt")
+        );
+    }
+
+    #[test]
+    fn prefers_extended_filename() {
+        let value =
+            "attachment; filename=\"fallback.zip\"; filename*=UTF-8''%E4%B8%AD%E6%96%87.zip";
+        assert_eq!(
+            parse_content_disposition(value).as_deref(),
+            Some("中文.zip")
+        );
+
+        let value = "attachment; FILENAME*=iso-8859-1'en'%E9t%E9.txt";
+        assert_eq!(parse_content_disposition(value).as_deref(), Some("été.txt"));
+    }
+
+    #[test]
+    fn falls_back_when_extended_filename_is_invalid() {
+        let value = "attachment; filename*=gbk''%D6%D0.txt; filename=\"fallback.txt\"";
+        assert_eq!(
+            parse_content_disposition(value).as_deref(),
+            Some("fallback.txt")
+        );
+        let value = "attachment; filename*=UTF-8''%FF%FE.txt; filename=fallback.txt";
+        assert_eq!(
+            parse_content_disposition(value).as_deref(),
+            Some("fallback.txt")
+        );
+        assert_eq!(parse_content_disposition("inline"), None);
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"..\""),
+            None
+        );
+    }
+
+    #[test]
+    fn strips_path_components() {
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"../../etc/passwd\"").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(
+            sanitize_filename("C:\\Windows\\evil.dll").as_deref(),
+            Some("evil.dll")
+        );
+    }
+
+    #[test]
+    fn replaces_illegal_characters_and_trailing_dots() {
+        assert_eq!(
+            sanitize_filename("a<b>:c?.txt").as_deref(),
+            Some("a_b__c_.txt")
+        );
+        assert_eq!(
+            sanitize_filename("tab\there.txt").as_deref(),
+            Some("tab_here.txt")
+        );
+        assert_eq!(sanitize_filename("  name. .").as_deref(), Some("name"));
+        assert_eq!(sanitize_filename("..."), None);
+        assert_eq!(sanitize_filename(""), None);
+        assert_eq!(sanitize_filename("dir/"), None);
+    }
+
+    #[test]
+    fn prefixes_windows_reserved_names() {
+        assert_eq!(sanitize_filename("CON").as_deref(), Some("_CON"));
+        assert_eq!(sanitize_filename("con.txt").as_deref(), Some("_con.txt"));
+        assert_eq!(
+            sanitize_filename("lpt9.tar.gz").as_deref(),
+            Some("_lpt9.tar.gz")
+        );
+        assert_eq!(
+            sanitize_filename("CONSOLE.txt").as_deref(),
+            Some("CONSOLE.txt")
+        );
+        assert_eq!(sanitize_filename("COM10").as_deref(), Some("COM10"));
+    }
+
+    #[test]
+    fn truncates_long_names_preserving_extension() {
+        let name = format!("{}.jar", "a".repeat(300));
+        let cleaned = sanitize_filename(&name).unwrap();
+        assert_eq!(cleaned.len(), MAX_FILENAME_BYTES);
+        assert!(cleaned.ends_with(".jar"));
+
+        // 截断位置不能落在多字节字符中间
+        let name = format!("{}.txt", "中".repeat(100));
+        let cleaned = sanitize_filename(&name).unwrap();
+        assert!(cleaned.len() <= MAX_FILENAME_BYTES);
+        assert!(cleaned.ends_with(".txt"));
+        assert!(cleaned.trim_end_matches(".txt").chars().all(|c| c == '中'));
+
+        // 扩展名过长时不当作扩展名
+        let name = format!("a.{}", "b".repeat(300));
+        assert_eq!(sanitize_filename(&name).unwrap().len(), MAX_FILENAME_BYTES);
+    }
+
+    #[test]
+    fn resolves_filename_from_url() {
+        let url = Url::parse("https://example.com/a/file%20name.jar/").unwrap();
+        assert_eq!(filename_from_url(&url).as_deref(), Some("file name.jar"));
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(filename_from_url(&url), None);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(resolve_filename(&url, &headers), FALLBACK_FILENAME);
+        headers.insert(
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"server.zip\"".parse().unwrap(),
+        );
+        let url = Url::parse("https://example.com/download?id=1").unwrap();
+        assert_eq!(resolve_filename(&url, &headers), "server.zip");
+    }
+}
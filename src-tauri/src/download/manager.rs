@@ -1,19 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, File};
+use std::future::Future;
 use std::io::{Seek, SeekFrom, Write};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-// use futures::stream::FuturesUnordered;
-use futures::StreamExt;
+use futures::future::{join_all, AbortHandle, Abortable, BoxFuture, Shared};
+use futures::{FutureExt, StreamExt};
 use reqwest::header::{HeaderMap, HeaderValue, RANGE};
-use tauri::async_runtime::{spawn, JoinHandle};
-use tokio::sync::mpsc;
+use reqwest::StatusCode;
+use tauri::async_runtime::spawn;
+use tokio::sync::{mpsc, oneshot, watch};
 use url::Url;
 use uuid::Uuid;
 
+use crate::download::batch::{download_batch, BatchItem, BatchProgress};
+use crate::download::checkpoint::{load_checkpoints, save_checkpoints, TaskCheckpoint};
 use crate::download::error::DownloadError;
-use crate::download::task::{DownloadProgress, DownloadStatus, DownloadTask};
+use crate::download::filename::resolve_filename;
+use crate::download::task::{DownloadProgress, DownloadStatus, DownloadTask, SegmentProgress};
+use crate::network::{ClientSnapshot, SharedClient};
 
 /// 下载事件类型
 #[derive(Debug, Clone)]
@@ -36,45 +42,171 @@ pub enum DownloadEvent {
     // 如果确实不需要，可以考虑将字段类型改为()
 }
 
+/// 命令的回复通道
+type Reply<T> = oneshot::Sender<Result<T, DownloadError>>;
+
+/// 一次下载运行结束的信号，可以被多处同时等待
+type RunDone = Shared<BoxFuture<'static, ()>>;
+
+/// 发送给下载管理器后台任务的命令
+enum Command {
+    /// 添加下载任务
+    AddTask {
+        url: String,
+        save_path: PathBuf,
+        filename: Option<String>,
+        segments: usize,
+        reply: Reply<String>,
+    },
+    /// 登记已解析完成的下载任务（内部使用）
+    InsertTask {
+        task: Arc<DownloadTask>,
+        reply: Reply<String>,
+    },
+    /// 开始下载任务
+    StartTask { task_id: String, reply: Reply<()> },
+    /// 暂停下载任务
+    PauseTask { task_id: String, reply: Reply<()> },
+    /// 恢复下载任务
+    ResumeTask { task_id: String, reply: Reply<()> },
+    /// 取消下载任务
+    CancelTask { task_id: String, reply: Reply<()> },
+    /// 获取任务列表
+    GetTasks {
+        reply: Reply<Vec<(String, DownloadProgress)>>,
+    },
+    /// 获取任务进度
+    GetTaskProgress {
+        task_id: String,
+        reply: Reply<DownloadProgress>,
+    },
+    /// 获取事件接收器
+    TakeEventReceiver {
+        reply: Reply<Option<mpsc::Receiver<DownloadEvent>>>,
+    },
+    /// 设置断点文件并恢复任务
+    Restore {
+        checkpoint_path: PathBuf,
+        reply: Reply<usize>,
+    },
+    /// 保存断点信息
+    SaveCheckpoints { reply: Reply<()> },
+    /// 关闭下载管理器
+    Shutdown { reply: Reply<()> },
+    /// 某次下载运行已结束（内部使用）
+    RunFinished { task_id: String, run_id: u64 },
+    /// 登记批量下载作业（内部使用）
+    RegisterJob {
+        job_id: String,
+        job: ActiveJob,
+        reply: Reply<()>,
+    },
+    /// 取消批量下载作业
+    CancelJob { job_id: String, reply: Reply<bool> },
+    /// 批量下载作业已结束（内部使用）
+    JobFinished { job_id: String },
+}
+
 /// 下载管理器
-#[derive(Debug)]
+///
+/// 这是一个可以廉价克隆的句柄，所有操作都以命令的形式发送给后台任务处理。
+/// 后台任务只维护状态，网络请求和等待分段退出等耗时操作都在单独的任务中进行，
+/// 因此一个任务的慢请求不会阻塞其他任务的暂停、取消和进度查询。
+#[derive(Debug, Clone)]
 pub struct DownloadManager {
-    /// 下载任务列表
-    tasks: Arc<Mutex<HashMap<String, Arc<DownloadTask>>>>,
-    /// 活跃任务的句柄
-    active_tasks: Arc<Mutex<HashMap<String, Vec<JoinHandle<()>>>>>,
-    /// 事件发送器
-    event_sender: mpsc::Sender<DownloadEvent>,
-    /// 事件接收器
-    event_receiver: Arc<Mutex<Option<mpsc::Receiver<DownloadEvent>>>>,
-    /// HTTP客户端
-    client: reqwest::Client,
-    /// 最大并发下载数
-    max_concurrent_downloads: usize,
+    /// 命令发送器
+    commands: mpsc::Sender<Command>,
+    /// 共享的HTTP客户端，批量下载直接使用
+    http: SharedClient,
 }
 
 impl DownloadManager {
-    /// 创建一个新的下载管理器
-    pub fn new(max_concurrent_downloads: usize) -> Self {
-        let (tx, rx) = mpsc::channel(100);
+    /// 创建一个新的下载管理器，并启动后台任务
+    ///
+    /// 下载使用共享客户端的连接池和配置，修改配置后只影响之后开始的下载。
+    pub fn new(max_concurrent_downloads: usize, http: SharedClient) -> Self {
+        let (command_tx, command_rx) = mpsc::channel(100);
+        let actor = ManagerActor::new(
+            max_concurrent_downloads,
+            http.clone(),
+            command_tx.downgrade(),
+        );
+        spawn(actor.run(command_rx));
 
         Self {
-            tasks: Arc::new(Mutex::new(HashMap::new())),
-            active_tasks: Arc::new(Mutex::new(HashMap::new())),
-            event_sender: tx,
-            event_receiver: Arc::new(Mutex::new(Some(rx))),
-            client: reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_default(),
-            max_concurrent_downloads,
+            commands: command_tx,
+            http,
         }
     }
 
+    /// 以作业的形式运行由多次批量下载组成的操作，如安装版本
+    ///
+    /// 作业可以通过[`DownloadManager::cancel_job`]取消，被取消时返回[`DownloadError::Cancelled`]；
+    /// 关闭下载管理器时会取消所有作业并等待其结束。作业ID与下载任务ID相互独立，
+    /// 同一ID的作业仍在进行时返回[`DownloadError::TaskAlreadyExists`]。
+    pub async fn run_job<T, E, F>(&self, job_id: &str, job: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<DownloadError>,
+    {
+        let (abort, registration) = AbortHandle::new_pair();
+        let (done_tx, done_rx) = oneshot::channel::<()>();
+        let job_active = ActiveJob {
+            abort,
+            done: done_rx.map(|_| ()).boxed().shared(),
+        };
+        self.request(|reply| Command::RegisterJob {
+            job_id: job_id.to_string(),
+            job: job_active,
+            reply,
+        })
+        .await?;
+
+        let _guard = JobGuard {
+            commands: self.commands.clone(),
+            job_id: job_id.to_string(),
+            _done: done_tx,
+        };
+        Abortable::new(job, registration)
+            .await
+            .unwrap_or_else(|_| Err(DownloadError::Cancelled.into()))
+    }
+
+    /// 取消批量下载作业，作业不存在或已经结束时返回`false`
+    pub async fn cancel_job(&self, job_id: &str) -> Result<bool, DownloadError> {
+        let job_id = job_id.to_string();
+        self.request(|reply| Command::CancelJob { job_id, reply })
+            .await
+    }
+
+    /// 批量下载小文件，不创建下载任务，已存在且校验通过的文件会被跳过
+    pub async fn download_batch(
+        &self,
+        items: Vec<BatchItem>,
+        on_progress: impl Fn(BatchProgress),
+    ) -> Result<BatchProgress, DownloadError> {
+        download_batch(&self.http, items, on_progress).await
+    }
+
+    /// 发送命令并等待回复
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(Reply<T>) -> Command,
+    ) -> Result<T, DownloadError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.commands
+            .send(command(reply_tx))
+            .await
+            .map_err(|_| DownloadError::ManagerStopped)?;
+        reply_rx.await.map_err(|_| DownloadError::ManagerStopped)?
+    }
+
     /// 获取事件接收器
-    pub fn take_event_receiver(&self) -> Option<mpsc::Receiver<DownloadEvent>> {
-        let mut receiver = self.event_receiver.lock().unwrap();
-        receiver.take()
+    pub async fn take_event_receiver(
+        &self,
+    ) -> Result<Option<mpsc::Receiver<DownloadEvent>>, DownloadError> {
+        self.request(|reply| Command::TakeEventReceiver { reply })
+            .await
     }
 
     /// 添加下载任务
@@ -85,470 +217,1392 @@ impl DownloadManager {
         filename: Option<String>,
         segments: usize,
     ) -> Result<String, DownloadError> {
-        // 解析URL
-        let parsed_url = Url::parse(url)?;
+        self.request(|reply| Command::AddTask {
+            url: url.to_string(),
+            save_path,
+            filename,
+            segments,
+            reply,
+        })
+        .await
+    }
 
-        // 如果没有提供文件名，从URL中提取
-        let filename = match filename {
-            Some(name) => name,
-            None => {
-                let path = parsed_url.path();
-                let segments: Vec<&str> = path.split('/').collect();
-                let last_segment = segments.last().unwrap_or(&"");
-                if last_segment.is_empty() {
-                    return Err(DownloadError::Other("无法从URL中提取文件名".to_string()));
+    /// 开始下载任务
+    ///
+    /// 如果任务有可用的断点信息（分段进度和临时文件），则从断点处继续下载。
+    /// 达到最大并发数时任务进入等待队列，有空闲位置后自动开始。
+    pub async fn start_task(&self, task_id: &str) -> Result<(), DownloadError> {
+        let task_id = task_id.to_string();
+        self.request(|reply| Command::StartTask { task_id, reply })
+            .await
+    }
+
+    /// 暂停下载任务
+    ///
+    /// 等待所有分段将已接收的数据写入磁盘后返回，并保存断点信息。
+    pub async fn pause_task(&self, task_id: &str) -> Result<(), DownloadError> {
+        let task_id = task_id.to_string();
+        self.request(|reply| Command::PauseTask { task_id, reply })
+            .await
+    }
+
+    /// 恢复下载任务
+    pub async fn resume_task(&self, task_id: &str) -> Result<(), DownloadError> {
+        let task_id = task_id.to_string();
+        self.request(|reply| Command::ResumeTask { task_id, reply })
+            .await
+    }
+
+    /// 取消下载任务
+    pub async fn cancel_task(&self, task_id: &str) -> Result<(), DownloadError> {
+        let task_id = task_id.to_string();
+        self.request(|reply| Command::CancelTask { task_id, reply })
+            .await
+    }
+
+    /// 获取任务列表
+    pub async fn get_tasks(&self) -> Result<Vec<(String, DownloadProgress)>, DownloadError> {
+        self.request(|reply| Command::GetTasks { reply }).await
+    }
+
+    /// 获取任务进度
+    pub async fn get_task_progress(
+        &self,
+        task_id: &str,
+    ) -> Result<DownloadProgress, DownloadError> {
+        let task_id = task_id.to_string();
+        self.request(|reply| Command::GetTaskProgress { task_id, reply })
+            .await
+    }
+
+    /// 关闭下载管理器
+    ///
+    /// 停止接受新任务，暂停所有正在进行的下载并取消批量下载作业，等待分段数据刷新到磁盘后保存断点信息，
+    /// 下次启动时可以通过[`DownloadManager::restore`]从断点处继续。
+    pub async fn shutdown(&self) -> Result<(), DownloadError> {
+        self.request(|reply| Command::Shutdown { reply }).await
+    }
+
+    /// 设置断点文件路径，并恢复其中记录的未完成任务
+    ///
+    /// 恢复的任务处于暂停状态，返回恢复的任务数量。
+    pub async fn restore(&self, checkpoint_path: PathBuf) -> Result<usize, DownloadError> {
+        self.request(|reply| Command::Restore {
+            checkpoint_path,
+            reply,
+        })
+        .await
+    }
+
+    /// 保存所有未完成任务的断点信息，未设置断点文件路径时不做任何事
+    pub async fn save_checkpoints(&self) -> Result<(), DownloadError> {
+        self.request(|reply| Command::SaveCheckpoints { reply })
+            .await
+    }
+}
+
+/// 正在进行的下载
+struct ActiveDownload {
+    /// 本次运行的编号，用于区分同一任务的多次运行
+    run_id: u64,
+    /// 停止信号
+    stop_tx: watch::Sender<bool>,
+    /// 运行结束信号，在所有分段退出后完成
+    done: RunDone,
+    /// 是否已发出停止信号，停止中的运行仍占用并发位置直到真正结束
+    stopping: bool,
+}
+
+/// 正在进行的批量下载作业
+struct ActiveJob {
+    /// 取消句柄
+    abort: AbortHandle,
+    /// 作业结束信号
+    done: RunDone,
+}
+
+/// 作业结束或被丢弃时通知后台任务移除登记，同时完成结束信号
+struct JobGuard {
+    /// 命令发送器
+    commands: mpsc::Sender<Command>,
+    /// 作业ID
+    job_id: String,
+    /// 释放时完成结束信号
+    _done: oneshot::Sender<()>,
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let command = Command::JobFinished {
+            job_id: std::mem::take(&mut self.job_id),
+        };
+        // 优先直接发送，保证在调用方之后发送的命令之前处理；通道已满时改为异步发送
+        if let Err(mpsc::error::TrySendError::Full(command)) = self.commands.try_send(command) {
+            let commands = self.commands.clone();
+            spawn(async move {
+                let _ = commands.send(command).await;
+            });
+        }
+    }
+}
+
+/// 下载管理器的后台任务，独占所有管理状态
+struct ManagerActor {
+    /// 下载任务列表
+    tasks: HashMap<String, Arc<DownloadTask>>,
+    /// 活跃任务
+    active_tasks: HashMap<String, ActiveDownload>,
+    /// 等待空闲位置的任务
+    pending: VecDeque<String>,
+    /// 正在进行的批量下载作业
+    jobs: HashMap<String, ActiveJob>,
+    /// 用于从子任务向后台任务回送命令，使用弱引用以便所有句柄释放后后台任务退出
+    commands: mpsc::WeakSender<Command>,
+    /// 事件发送器
+    event_sender: mpsc::Sender<DownloadEvent>,
+    /// 事件接收器
+    event_receiver: Option<mpsc::Receiver<DownloadEvent>>,
+    /// 共享的HTTP客户端
+    http: SharedClient,
+    /// 最大并发下载数
+    max_concurrent_downloads: usize,
+    /// 是否接受新任务，关闭后为false
+    accepting: bool,
+    /// 断点文件路径
+    checkpoint_path: Option<PathBuf>,
+    /// 下一次运行的编号
+    next_run_id: u64,
+}
+
+impl ManagerActor {
+    /// 创建后台任务的状态
+    fn new(
+        max_concurrent_downloads: usize,
+        http: SharedClient,
+        commands: mpsc::WeakSender<Command>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(100);
+
+        Self {
+            tasks: HashMap::new(),
+            active_tasks: HashMap::new(),
+            pending: VecDeque::new(),
+            jobs: HashMap::new(),
+            commands,
+            event_sender: tx,
+            event_receiver: Some(rx),
+            http,
+            max_concurrent_downloads,
+            accepting: true,
+            checkpoint_path: None,
+            next_run_id: 0,
+        }
+    }
+
+    /// 逐条处理命令，直到所有句柄都被释放
+    async fn run(mut self, mut command_rx: mpsc::Receiver<Command>) {
+        while let Some(command) = command_rx.recv().await {
+            self.handle(command);
+        }
+    }
+
+    /// 处理单条命令，不能在这里等待任何耗时操作
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::AddTask {
+                url,
+                save_path,
+                filename,
+                segments,
+                reply,
+            } => self.add_task(url, save_path, filename, segments, reply),
+            Command::InsertTask { task, reply } => {
+                let _ = reply.send(self.insert_task(task));
+            }
+            Command::StartTask { task_id, reply } => self.start_task(&task_id, Some(reply)),
+            Command::PauseTask { task_id, reply } => self.pause_task(&task_id, reply),
+            Command::ResumeTask { task_id, reply } => self.resume_task(&task_id, reply),
+            Command::CancelTask { task_id, reply } => self.cancel_task(&task_id, reply),
+            Command::GetTasks { reply } => {
+                let tasks = self
+                    .tasks
+                    .iter()
+                    .map(|(id, task)| (id.clone(), task.get_progress()))
+                    .collect();
+                let _ = reply.send(Ok(tasks));
+            }
+            Command::GetTaskProgress { task_id, reply } => {
+                let _ = reply.send(self.get_task(&task_id).map(|task| task.get_progress()));
+            }
+            Command::TakeEventReceiver { reply } => {
+                let _ = reply.send(Ok(self.event_receiver.take()));
+            }
+            Command::Restore {
+                checkpoint_path,
+                reply,
+            } => {
+                let _ = reply.send(self.restore(checkpoint_path));
+            }
+            Command::SaveCheckpoints { reply } => {
+                let _ = reply.send(self.save_checkpoints());
+            }
+            Command::Shutdown { reply } => self.shutdown(reply),
+            Command::RunFinished { task_id, run_id } => {
+                if self
+                    .active_tasks
+                    .get(&task_id)
+                    .is_some_and(|active| active.run_id == run_id)
+                {
+                    self.active_tasks.remove(&task_id);
                 }
-                last_segment.to_string()
+                self.start_pending();
             }
-        };
+            Command::RegisterJob { job_id, job, reply } => {
+                let _ = reply.send(self.register_job(job_id, job));
+            }
+            Command::CancelJob { job_id, reply } => {
+                // 登记在作业真正结束时才移除，关闭时仍会等待被取消的作业
+                let job = self.jobs.get(&job_id);
+                if let Some(job) = job {
+                    job.abort.abort();
+                }
+                let _ = reply.send(Ok(job.is_some()));
+            }
+            Command::JobFinished { job_id } => {
+                self.jobs.remove(&job_id);
+            }
+        }
+    }
 
-        // 创建保存目录
-        if !save_path.exists() {
-            fs::create_dir_all(&save_path)?;
+    /// 登记批量下载作业
+    fn register_job(&mut self, job_id: String, job: ActiveJob) -> Result<(), DownloadError> {
+        self.ensure_accepting()?;
+        if self.jobs.contains_key(&job_id) {
+            return Err(DownloadError::TaskAlreadyExists(job_id));
         }
+        self.jobs.insert(job_id, job);
+        Ok(())
+    }
 
-        // 生成任务ID
-        let task_id = Uuid::new_v4().to_string();
+    /// 添加下载任务，URL探测和目录创建在单独的任务中进行
+    fn add_task(
+        &mut self,
+        url: String,
+        save_path: PathBuf,
+        filename: Option<String>,
+        segments: usize,
+        reply: Reply<String>,
+    ) {
+        if let Err(e) = self.ensure_accepting() {
+            let _ = reply.send(Err(e));
+            return;
+        }
 
-        // 创建下载任务
-        let task = Arc::new(DownloadTask::new(
-            task_id.clone(),
-            url.to_string(),
-            save_path,
-            filename,
-            segments,
-        ));
+        let http = self.http.snapshot();
+        let commands = self.commands.clone();
 
-        // 添加到任务列表
-        {
-            let mut tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
-            if tasks.contains_key(&task_id) {
-                return Err(DownloadError::TaskAlreadyExists(task_id));
+        spawn(async move {
+            let task = match prepare_task(&http, &url, save_path, filename, segments).await {
+                Ok(task) => task,
+                Err(e) => {
+                    let _ = reply.send(Err(e));
+                    return;
+                }
+            };
+
+            // 回到后台任务中登记，后台任务已退出时回复通道随命令丢弃，调用方会收到ManagerStopped
+            if let Some(commands) = commands.upgrade() {
+                let _ = commands
+                    .send(Command::InsertTask {
+                        task: Arc::new(task),
+                        reply,
+                    })
+                    .await;
             }
-            tasks.insert(task_id.clone(), task.clone());
+        });
+    }
+
+    /// 登记下载任务
+    fn insert_task(&mut self, task: Arc<DownloadTask>) -> Result<String, DownloadError> {
+        self.ensure_accepting()?;
+
+        let task_id = task.id.clone();
+        if self.tasks.contains_key(&task_id) {
+            return Err(DownloadError::TaskAlreadyExists(task_id));
         }
+        self.tasks.insert(task_id.clone(), task);
 
         Ok(task_id)
     }
 
     /// 开始下载任务
-    pub async fn start_task(&self, task_id: &str) -> Result<(), DownloadError> {
-        let task = {
-            let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
-            tasks
-                .get(task_id)
-                .cloned()
-                .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))?
+    ///
+    /// `reply`在下载真正开始（获取文件大小并创建分段）后回复；
+    /// 从等待队列自动开始的任务没有回复通道。
+    fn start_task(&mut self, task_id: &str, reply: Option<Reply<()>>) {
+        let reply_with = |reply: Option<Reply<()>>, result| {
+            if let Some(reply) = reply {
+                let _ = reply.send(result);
+            }
+        };
+
+        let task = match self.ensure_accepting().and_then(|_| self.get_task(task_id)) {
+            Ok(task) => task,
+            Err(e) => return reply_with(reply, Err(e)),
         };
 
         // 检查当前活跃任务数量
-        {
-            let active_tasks = self
-                .active_tasks
-                .lock()
-                .map_err(|_| DownloadError::LockError)?;
-            if active_tasks.len() >= self.max_concurrent_downloads {
-                // 设置为等待状态
-                task.set_status(DownloadStatus::Pending)?;
-                return Ok(());
+        let stopping = match self.active_tasks.get(task_id) {
+            Some(active) if !active.stopping => return reply_with(reply, Ok(())),
+            Some(_) => true,
+            None => false,
+        };
+        if stopping || self.active_tasks.len() >= self.max_concurrent_downloads {
+            // 设置为等待状态；上一次运行仍在停止时也需要等待，避免两次运行同时写入同一文件
+            task.set_status(DownloadStatus::Pending);
+            if !self.pending.iter().any(|id| id == task_id) {
+                self.pending.push_back(task_id.to_string());
+            }
+            return reply_with(reply, Ok(()));
+        }
+
+        let run_id = self.next_run_id;
+        self.next_run_id += 1;
+
+        // 创建停止信号，暂停、取消和关闭时通知所有分段停止写入
+        let (stop_tx, stop_rx) = watch::channel(false);
+
+        let run = TaskRun {
+            task,
+            http: self.http.snapshot(),
+            event_sender: self.event_sender.clone(),
+            stop_rx,
+        };
+        let commands = self.commands.clone();
+        let task_id_clone = task_id.to_string();
+
+        let handle = spawn(async move {
+            run.run(reply).await;
+
+            if let Some(commands) = commands.upgrade() {
+                let _ = commands
+                    .send(Command::RunFinished {
+                        task_id: task_id_clone,
+                        run_id,
+                    })
+                    .await;
+            }
+        });
+
+        self.active_tasks.insert(
+            task_id.to_string(),
+            ActiveDownload {
+                run_id,
+                stop_tx,
+                done: async move {
+                    let _ = handle.await;
+                }
+                .boxed()
+                .shared(),
+                stopping: false,
+            },
+        );
+    }
+
+    /// 在有空闲位置时启动等待中的任务
+    fn start_pending(&mut self) {
+        let mut deferred = VecDeque::new();
+
+        while self.accepting && self.active_tasks.len() < self.max_concurrent_downloads {
+            let Some(task_id) = self.pending.pop_front() else {
+                break;
+            };
+            let still_pending = self
+                .tasks
+                .get(&task_id)
+                .is_some_and(|task| task.status() == DownloadStatus::Pending);
+            if !still_pending {
+                continue;
+            }
+
+            // 上一次运行仍在停止的任务留在队列中，等它结束后再开始
+            if self.active_tasks.contains_key(&task_id) {
+                deferred.push_back(task_id);
+                continue;
+            }
+            self.start_task(&task_id, None);
+        }
+
+        deferred.append(&mut self.pending);
+        self.pending = deferred;
+    }
+
+    /// 暂停下载任务
+    fn pause_task(&mut self, task_id: &str, reply: Reply<()>) {
+        let task = match self.get_task(task_id) {
+            Ok(task) => task,
+            Err(e) => {
+                let _ = reply.send(Err(e));
+                return;
+            }
+        };
+
+        // 只有下载中或等待中的任务可以暂停
+        let current_status = task.status();
+        if !matches!(
+            current_status,
+            DownloadStatus::Downloading | DownloadStatus::Pending
+        ) {
+            let _ = reply.send(Err(DownloadError::InvalidState(format!(
+                "只能暂停下载中或等待中的任务: {:?}",
+                current_status
+            ))));
+            return;
+        }
+
+        // 设置状态为已暂停
+        task.set_status(DownloadStatus::Paused);
+        self.pending.retain(|id| id != task_id);
+
+        let done = self.stop_active_task(task_id);
+        let event = DownloadEvent::Paused(task_id.to_string());
+        self.after_stopped(done, || Ok(()), event, reply);
+    }
+
+    /// 恢复下载任务
+    fn resume_task(&mut self, task_id: &str, reply: Reply<()>) {
+        let task = match self.get_task(task_id) {
+            Ok(task) => task,
+            Err(e) => {
+                let _ = reply.send(Err(e));
+                return;
+            }
+        };
+
+        // 检查当前状态
+        let current_status = task.status();
+        if current_status != DownloadStatus::Paused {
+            let _ = reply.send(Err(DownloadError::InvalidState(format!(
+                "任务状态不是已暂停: {:?}",
+                current_status
+            ))));
+            return;
+        }
+
+        // 发送恢复事件
+        self.emit(DownloadEvent::Resumed(task_id.to_string()));
+
+        // 重新开始任务
+        self.start_task(task_id, Some(reply));
+    }
+
+    /// 取消下载任务
+    fn cancel_task(&mut self, task_id: &str, reply: Reply<()>) {
+        let task = match self.get_task(task_id) {
+            Ok(task) => task,
+            Err(e) => {
+                let _ = reply.send(Err(e));
+                return;
+            }
+        };
+
+        // 设置状态为已取消
+        task.set_status(DownloadStatus::Cancelled);
+        self.pending.retain(|id| id != task_id);
+
+        let done = self.stop_active_task(task_id);
+        let event = DownloadEvent::Cancelled(task_id.to_string());
+        self.after_stopped(
+            done,
+            move || {
+                // 删除临时文件
+                let temp_path = task.temp_path();
+                if temp_path.exists() {
+                    let _ = fs::remove_file(temp_path);
+                }
+                task.set_segments(Vec::new());
+                Ok(())
+            },
+            event,
+            reply,
+        );
+    }
+
+    /// 关闭下载管理器
+    fn shutdown(&mut self, reply: Reply<()>) {
+        self.accepting = false;
+
+        // 先通知所有任务停止，再统一等待，避免逐个等待拖长退出时间
+        let mut runs = Vec::new();
+        for (task_id, download) in self.active_tasks.iter_mut() {
+            if let Some(task) = self.tasks.get(task_id) {
+                if task.status() == DownloadStatus::Downloading {
+                    task.set_status(DownloadStatus::Paused);
+                }
+            }
+            download.stopping = true;
+            let _ = download.stop_tx.send(true);
+            runs.push(download.done.clone());
+        }
+        for job in self.jobs.values() {
+            job.abort.abort();
+            runs.push(job.done.clone());
+        }
+
+        let commands = self.commands.clone();
+        spawn(async move {
+            join_all(runs).await;
+            let result = match commands.upgrade() {
+                Some(commands) => save_checkpoints_via(&commands).await,
+                None => Err(DownloadError::ManagerStopped),
+            };
+            let _ = reply.send(result);
+        });
+    }
+
+    /// 设置断点文件路径，并恢复其中记录的未完成任务
+    fn restore(&mut self, checkpoint_path: PathBuf) -> Result<usize, DownloadError> {
+        let checkpoints = load_checkpoints(&checkpoint_path)?;
+        self.checkpoint_path = Some(checkpoint_path);
+
+        let mut restored = 0;
+        for checkpoint in checkpoints {
+            if self.tasks.contains_key(&checkpoint.id) {
+                continue;
+            }
+            let task = checkpoint.into_task();
+            self.tasks.insert(task.id.clone(), Arc::new(task));
+            restored += 1;
+        }
+
+        Ok(restored)
+    }
+
+    /// 保存所有未完成任务的断点信息
+    fn save_checkpoints(&self) -> Result<(), DownloadError> {
+        let Some(path) = &self.checkpoint_path else {
+            return Ok(());
+        };
+
+        let checkpoints: Vec<TaskCheckpoint> = self
+            .tasks
+            .values()
+            .filter(|task| {
+                matches!(
+                    task.status(),
+                    DownloadStatus::Pending | DownloadStatus::Downloading | DownloadStatus::Paused
+                )
+            })
+            .map(|task| TaskCheckpoint::from_task(task))
+            .collect();
+
+        save_checkpoints(path, &checkpoints)
+    }
+
+    /// 获取任务
+    fn get_task(&self, task_id: &str) -> Result<Arc<DownloadTask>, DownloadError> {
+        self.tasks
+            .get(task_id)
+            .cloned()
+            .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))
+    }
+
+    /// 检查管理器是否仍在接受新任务
+    fn ensure_accepting(&self) -> Result<(), DownloadError> {
+        if self.accepting {
+            Ok(())
+        } else {
+            Err(DownloadError::ShuttingDown)
+        }
+    }
+
+    /// 发送下载事件，通道已满时丢弃事件，避免阻塞下载流程
+    fn emit(&self, event: DownloadEvent) {
+        let _ = self.event_sender.try_send(event);
+    }
+
+    /// 向活跃任务发送停止信号，返回运行结束信号
+    ///
+    /// 任务在真正结束前仍保留在活跃列表中，结束时由`RunFinished`移除。
+    fn stop_active_task(&mut self, task_id: &str) -> Option<RunDone> {
+        let download = self.active_tasks.get_mut(task_id)?;
+        download.stopping = true;
+        let _ = download.stop_tx.send(true);
+        Some(download.done.clone())
+    }
+
+    /// 在单独的任务中等待下载停止，然后执行清理、保存断点、发送事件并回复
+    fn after_stopped(
+        &self,
+        done: Option<RunDone>,
+        cleanup: impl FnOnce() -> Result<(), DownloadError> + Send + 'static,
+        event: DownloadEvent,
+        reply: Reply<()>,
+    ) {
+        let commands = self.commands.clone();
+        let event_sender = self.event_sender.clone();
+
+        spawn(async move {
+            if let Some(done) = done {
+                done.await;
             }
+
+            let result = cleanup();
+            if let Some(commands) = commands.upgrade() {
+                if let Err(e) = save_checkpoints_via(&commands).await {
+                    eprintln!("保存断点信息失败: {}", e);
+                }
+            }
+
+            let _ = event_sender.try_send(event);
+            let _ = reply.send(result);
+        });
+    }
+}
+
+/// 通过命令通道请求后台任务保存断点信息
+async fn save_checkpoints_via(commands: &mpsc::Sender<Command>) -> Result<(), DownloadError> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    commands
+        .send(Command::SaveCheckpoints { reply: reply_tx })
+        .await
+        .map_err(|_| DownloadError::ManagerStopped)?;
+    reply_rx.await.map_err(|_| DownloadError::ManagerStopped)?
+}
+
+/// 解析URL和文件名，创建保存目录，生成下载任务
+async fn prepare_task(
+    http: &ClientSnapshot,
+    url: &str,
+    save_path: PathBuf,
+    filename: Option<String>,
+    segments: usize,
+) -> Result<DownloadTask, DownloadError> {
+    // 解析URL
+    let parsed_url = Url::parse(url)?;
+
+    // 如果没有提供文件名，跟随重定向后从响应头和最终URL中解析
+    let (filename, resolved_url) = match filename {
+        Some(name) => (name, parsed_url.to_string()),
+        None => {
+            let (_, (final_url, headers)) =
+                try_mirrors(http, url, |candidate| probe_url(http, candidate)).await?;
+            (
+                resolve_filename(&final_url, &headers),
+                final_url.to_string(),
+            )
         }
+    };
 
-        // 获取文件大小
-        let file_size = self.get_file_size(&task.url).await?;
-        task.set_total_size(file_size)?;
+    // 创建保存目录
+    if !save_path.exists() {
+        tokio::fs::create_dir_all(&save_path).await?;
+    }
+
+    // 生成任务ID
+    let task_id = Uuid::new_v4().to_string();
+
+    Ok(DownloadTask::new(
+        task_id,
+        url.to_string(),
+        resolved_url,
+        save_path,
+        filename,
+        segments,
+    ))
+}
+
+/// 按镜像配置依次尝试各个候选地址，返回第一个成功的地址和结果
+pub async fn try_mirrors<T, F, Fut>(
+    http: &ClientSnapshot,
+    url: &str,
+    mut attempt: F,
+) -> Result<(Url, T), DownloadError>
+where
+    F: FnMut(Url) -> Fut,
+    Fut: Future<Output = Result<T, DownloadError>>,
+{
+    let mut last_error = None;
+    for candidate in http.mirrors.candidates(&Url::parse(url)?) {
+        match attempt(candidate.clone()).await {
+            Ok(value) => return Ok((candidate, value)),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| DownloadError::InvalidUrl(url.to_string())))
+}
+
+/// 探测URL，返回跟随重定向后的最终URL和响应头
+///
+/// 部分服务器不支持HEAD请求，此时改用只请求第一个字节的GET请求。
+async fn probe_url(http: &ClientSnapshot, url: Url) -> Result<(Url, HeaderMap), DownloadError> {
+    let timeout = http.config.request_timeout();
+    let response = http
+        .send(http.client.head(url.clone()).timeout(timeout))
+        .await?;
+    let response = if response.status().is_success() {
+        response
+    } else {
+        http.send(
+            http.client
+                .get(url)
+                .header(RANGE, HeaderValue::from_static("bytes=0-0"))
+                .timeout(timeout),
+        )
+        .await?
+    };
+
+    if !response.status().is_success() {
+        return Err(DownloadError::HttpStatus(response.status().as_u16()));
+    }
+
+    Ok((response.url().clone(), response.headers().clone()))
+}
+
+/// 获取文件大小
+async fn get_file_size(http: &ClientSnapshot, url: Url) -> Result<u64, DownloadError> {
+    let response = http
+        .send(http.client.head(url).timeout(http.config.request_timeout()))
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(DownloadError::HttpStatus(response.status().as_u16()));
+    }
+
+    let content_length = response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .ok_or(DownloadError::ContentLengthError)?;
+
+    Ok(content_length)
+}
+
+/// 一次下载运行，从获取文件大小到所有分段退出
+struct TaskRun {
+    /// 下载任务
+    task: Arc<DownloadTask>,
+    /// 本次运行使用的客户端
+    http: Arc<ClientSnapshot>,
+    /// 事件发送器
+    event_sender: mpsc::Sender<DownloadEvent>,
+    /// 停止信号
+    stop_rx: watch::Receiver<bool>,
+}
+
+impl TaskRun {
+    /// 执行下载，准备完成后通过`reply`回复调用方
+    async fn run(self, reply: Option<Reply<()>>) {
+        let mut stop_rx = self.stop_rx.clone();
+        let prepared = tokio::select! {
+            biased;
+            _ = stop_rx.changed() => None,
+            result = self.prepare() => Some(result),
+        };
+
+        let (source, segments) = match prepared {
+            Some(Ok(prepared)) => prepared,
+            Some(Err(e)) => {
+                if let Some(reply) = reply {
+                    let _ = reply.send(Err(e));
+                }
+                return;
+            }
+            // 准备期间被暂停或取消，不再开始下载
+            None => {
+                if let Some(reply) = reply {
+                    let _ = reply.send(Ok(()));
+                }
+                return;
+            }
+        };
+
+        if let Some(reply) = reply {
+            let _ = reply.send(Ok(()));
+        }
+        self.download(source, segments).await;
+    }
+
+    /// 选择下载地址，获取文件大小并准备临时文件和分段
+    async fn prepare(&self) -> Result<(Url, Vec<SegmentProgress>), DownloadError> {
+        let task = &self.task;
+        let http = &self.http;
+
+        // 按镜像配置选择可用的下载地址，本次运行的所有分段都使用该地址
+        let (source, file_size) =
+            try_mirrors(http, &task.url, |candidate| get_file_size(http, candidate)).await?;
+
+        // 文件大小未变化且临时文件完好时，沿用之前的分段进度
+        let temp_path = task.temp_path();
+        let previous_segments = task.get_segments();
+        let can_resume = !previous_segments.is_empty()
+            && task.get_progress().total == file_size
+            && fs::metadata(&temp_path)
+                .map(|metadata| metadata.len() == file_size)
+                .unwrap_or(false);
+
+        let segments = if can_resume {
+            previous_segments
+        } else {
+            // 创建临时文件
+            let file = File::create(&temp_path)?;
+            file.set_len(file_size)?;
+            SegmentProgress::split(file_size, task.segments)
+        };
+        task.set_segments(segments.clone());
+        task.set_total_size(file_size);
 
         // 设置开始时间
-        // 使用方法设置开始时间，而不是直接修改Arc中的数据
         task.set_start_time()?;
 
         // 设置状态为下载中
-        task.set_status(DownloadStatus::Downloading)?;
+        task.set_status(DownloadStatus::Downloading);
 
         // 发送开始事件
         let _ = self
             .event_sender
-            .send(DownloadEvent::Started(task_id.to_string()))
-            .await;
+            .try_send(DownloadEvent::Started(task.id.clone()));
 
-        // 计算每个分段的大小
-        let segment_size = file_size / task.segments as u64;
-        let mut handles = Vec::new();
+        Ok((source, segments))
+    }
 
-        // 创建临时文件
-        let temp_path = task.full_path().with_extension("part");
-        {
-            let file = File::create(&temp_path)?;
-            file.set_len(file_size)?;
-        }
+    /// 启动所有未完成的分段，等待它们退出后处理下载结果
+    async fn download(self, source: Url, segments: Vec<SegmentProgress>) {
+        let task = self.task;
+        let event_sender = self.event_sender;
+        let temp_path = task.temp_path();
+        let mut handles = Vec::new();
 
         // 创建进度更新通道
-        let (progress_tx, mut progress_rx) = mpsc::channel(100);
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<u64>();
 
         // 启动进度更新任务
         let task_clone = task.clone();
-        let task_id_clone = task_id.to_string();
-        let event_sender = self.event_sender.clone();
-        let progress_handle = spawn(async move {
-            let mut total_downloaded = 0;
-
-            while let Some((_segment_id, bytes)) = progress_rx.recv().await {
-                total_downloaded += bytes;
-
-                // 更新总进度
-                if let Err(e) = task_clone.update_progress(total_downloaded) {
-                    eprintln!("更新进度失败: {}", e);
-                    continue;
-                }
+        let progress_sender = event_sender.clone();
+        handles.push(spawn(async move {
+            while progress_rx.recv().await.is_some() {
+                // 以分段记录的字节数为准，断点续传时包含之前已下载的部分
+                task_clone.update_progress(task_clone.downloaded_bytes());
 
                 // 获取当前进度并发送事件
-                if let Ok(progress) = task_clone.get_progress() {
-                    let _ = event_sender
-                        .send(DownloadEvent::ProgressUpdated(
-                            task_id_clone.clone(),
-                            progress,
-                        ))
-                        .await;
-                }
+                let _ = progress_sender.try_send(DownloadEvent::ProgressUpdated(
+                    task_clone.id.clone(),
+                    task_clone.get_progress(),
+                ));
             }
-        });
-
-        handles.push(progress_handle);
+        }));
 
-        // 启动分段下载任务
-        for i in 0..task.segments {
-            let start = i as u64 * segment_size;
-            let end = if i == task.segments - 1 {
-                file_size - 1
-            } else {
-                (i as u64 + 1) * segment_size - 1
-            };
+        // 启动分段下载任务，跳过已完成的分段
+        for (segment_id, segment) in segments.iter().enumerate() {
+            if segment.is_complete() {
+                continue;
+            }
 
-            let client = self.client.clone();
-            let url = task.url.clone();
+            let http = self.http.clone();
+            let source = source.clone();
             let temp_path = temp_path.clone();
+            let task = task.clone();
             let progress_tx = progress_tx.clone();
-            let segment_id = i;
-
-            let handle = spawn(async move {
-                let mut headers = HeaderMap::new();
-                headers.insert(
-                    RANGE,
-                    HeaderValue::from_str(&format!("bytes={}-{}", start, end)).unwrap(),
-                );
+            let mut stop_rx = self.stop_rx.clone();
 
+            handles.push(spawn(async move {
                 let mut retry_count = 0;
-                let max_retries = 3;
 
                 loop {
-                    match Self::download_segment(
-                        client.clone(),
-                        &url,
+                    match download_segment(
+                        &http,
+                        &source,
                         &temp_path,
-                        start,
-                        end,
+                        &task,
                         segment_id,
-                        progress_tx.clone(),
+                        &progress_tx,
+                        &mut stop_rx,
                     )
                     .await
                     {
                         Ok(_) => break,
                         Err(e) => {
+                            if *stop_rx.borrow() {
+                                break;
+                            }
+
                             retry_count += 1;
-                            if retry_count >= max_retries {
+                            if retry_count >= task.max_retries {
                                 eprintln!("分段 {} 下载失败: {}", segment_id, e);
                                 break;
                             }
 
-                            // 等待一段时间后重试
-                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                            // 等待一段时间后从已下载的位置重试
+                            tokio::select! {
+                                _ = stop_rx.changed() => break,
+                                _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
+                            }
                         }
                     }
                 }
-            });
-
-            handles.push(handle);
+            }));
         }
-
-        // 添加完成处理任务
-        let task_clone = task.clone();
-        let task_id_clone = task_id.to_string();
-        let event_sender = self.event_sender.clone();
-        let temp_path_clone = temp_path.clone();
-        let active_tasks = self.active_tasks.clone();
-
-        let completion_handle = spawn(async move {
-            // 等待所有分段下载完成
-            for handle in handles {
-                let _ = handle.await;
-            }
-
-            // 检查下载状态
-            let status = match task_clone.get_progress() {
-                Ok(progress) => progress.status,
-                Err(_) => DownloadStatus::Failed,
-            };
-
-            match status {
-                DownloadStatus::Downloading => {
+        drop(progress_tx);
+
+        // 等待所有分段下载完成
+        join_all(handles).await;
+
+        // 检查下载状态
+        match task.status() {
+            DownloadStatus::Downloading => {
+                let all_complete = task.get_segments().iter().all(SegmentProgress::is_complete);
+
+                if !all_complete {
+                    // 部分分段重试后仍然失败
+                    task.set_status(DownloadStatus::Failed);
+                    let _ = fs::remove_file(&temp_path);
+                    let _ = event_sender.try_send(DownloadEvent::Failed(
+                        task.id.clone(),
+                        "部分分段下载失败".to_string(),
+                    ));
+                } else if let Err(e) = fs::rename(&temp_path, task.full_path()) {
                     // 重命名临时文件
-                    if let Err(e) = fs::rename(&temp_path_clone, task_clone.full_path()) {
-                        eprintln!("重命名文件失败: {}", e);
-                        let _ = task_clone.set_status(DownloadStatus::Failed);
-                        let _ = event_sender
-                            .send(DownloadEvent::Failed(
-                                task_id_clone.clone(),
-                                format!("重命名文件失败: {}", e),
-                            ))
-                            .await;
-                    } else {
-                        // 设置状态为已完成
-                        let _ = task_clone.set_status(DownloadStatus::Completed);
-                        let _ = event_sender
-                            .send(DownloadEvent::Completed(task_id_clone.clone()))
-                            .await;
-                    }
-                }
-                DownloadStatus::Cancelled => {
-                    // 删除临时文件
-                    let _ = fs::remove_file(&temp_path_clone);
+                    eprintln!("重命名文件失败: {}", e);
+                    task.set_status(DownloadStatus::Failed);
+                    let _ = event_sender.try_send(DownloadEvent::Failed(
+                        task.id.clone(),
+                        format!("重命名文件失败: {}", e),
+                    ));
+                } else {
+                    // 设置状态为已完成
+                    task.set_status(DownloadStatus::Completed);
+                    let _ = event_sender.try_send(DownloadEvent::Completed(task.id.clone()));
                 }
-                DownloadStatus::Failed => {
-                    // 删除临时文件
-                    let _ = fs::remove_file(&temp_path_clone);
-                    let _ = event_sender
-                        .send(DownloadEvent::Failed(
-                            task_id_clone.clone(),
-                            "下载失败".to_string(),
-                        ))
-                        .await;
-                }
-                _ => {}
             }
+            DownloadStatus::Cancelled => {
+                // 删除临时文件
+                let _ = fs::remove_file(&temp_path);
+            }
+            DownloadStatus::Failed => {
+                // 删除临时文件
+                let _ = fs::remove_file(&temp_path);
+                let _ = event_sender.try_send(DownloadEvent::Failed(
+                    task.id.clone(),
+                    "下载失败".to_string(),
+                ));
+            }
+            // 已暂停：保留临时文件和分段进度，以便之后继续下载
+            _ => {}
+        }
+    }
+}
 
-            // 从活跃任务列表中移除
-            let mut active = active_tasks.lock().unwrap();
-            active.remove(&task_id_clone);
-
-            // 检查是否有等待中的任务可以启动
-            // 这里简化处理，实际应该通知管理器启动下一个任务
-        });
+/// 下载分段，从分段已下载的位置继续
+///
+/// 收到停止信号时将已写入的数据刷新到磁盘后返回，分段进度保留在任务中。
+async fn download_segment(
+    http: &ClientSnapshot,
+    source: &Url,
+    file_path: &Path,
+    task: &DownloadTask,
+    segment_id: usize,
+    progress_tx: &mpsc::UnboundedSender<u64>,
+    stop_rx: &mut watch::Receiver<bool>,
+) -> Result<(), DownloadError> {
+    let segment = task
+        .get_segments()
+        .get(segment_id)
+        .cloned()
+        .ok_or_else(|| DownloadError::Other(format!("分段不存在: {}", segment_id)))?;
+
+    if segment.is_complete() || *stop_rx.borrow() {
+        return Ok(());
+    }
 
-        // 添加到活跃任务列表
-        {
-            let mut active_tasks = self
-                .active_tasks
-                .lock()
-                .map_err(|_| DownloadError::LockError)?;
-            active_tasks.insert(task_id.to_string(), vec![completion_handle]);
+    // 设置请求头
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        RANGE,
+        HeaderValue::from_str(&format!("bytes={}-{}", segment.next_offset(), segment.end))?,
+    );
+
+    // 发送请求，等待响应期间也要响应停止信号
+    let read_timeout = http.config.read_timeout();
+    let request = http.send(http.client.get(source.clone()).headers(headers));
+    let response = tokio::select! {
+        biased;
+        _ = stop_rx.changed() => return Ok(()),
+        response = tokio::time::timeout(read_timeout, request) => {
+            response.map_err(|_| DownloadError::ReadTimeout)??
         }
+    };
 
-        Ok(())
+    if !response.status().is_success() {
+        return Err(DownloadError::HttpStatus(response.status().as_u16()));
     }
 
-    /// 暂停下载任务
-    pub async fn pause_task(&self, task_id: &str) -> Result<(), DownloadError> {
-        let task = {
-            let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
-            tasks
-                .get(task_id)
-                .cloned()
-                .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))?
-        };
+    // 服务器忽略Range头时会从文件开头返回数据，只有从0开始的分段可以直接写入
+    if response.status() != StatusCode::PARTIAL_CONTENT && segment.next_offset() > 0 {
+        return Err(DownloadError::RangeNotSupported);
+    }
 
-        // 设置状态为已暂停
-        task.set_status(DownloadStatus::Paused)?;
-
-        // 取消活跃任务
-        {
-            let mut active_tasks = self
-                .active_tasks
-                .lock()
-                .map_err(|_| DownloadError::LockError)?;
-            if let Some(handles) = active_tasks.remove(task_id) {
-                for handle in handles {
-                    handle.abort();
-                }
-            }
-        }
+    // 打开文件
+    let mut file = fs::OpenOptions::new().write(true).open(file_path)?;
 
-        // 发送暂停事件
-        let _ = self
-            .event_sender
-            .send(DownloadEvent::Paused(task_id.to_string()))
-            .await;
+    // 设置文件指针位置
+    file.seek(SeekFrom::Start(segment.next_offset()))?;
 
-        Ok(())
-    }
+    // 下载数据，不写入超出分段范围的部分
+    let mut stream = response.bytes_stream();
+    let mut remaining = segment.len() - segment.downloaded;
 
-    /// 恢复下载任务
-    pub async fn resume_task(&self, task_id: &str) -> Result<(), DownloadError> {
-        let task = {
-            let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
-            tasks
-                .get(task_id)
-                .cloned()
-                .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))?
+    while remaining > 0 {
+        let chunk_result = tokio::select! {
+            biased;
+            _ = stop_rx.changed() => break,
+            chunk_result = tokio::time::timeout(read_timeout, stream.next()) => {
+                chunk_result.map_err(|_| DownloadError::ReadTimeout)?
+            }
         };
-
-        // 检查当前状态
-        let current_status = {
-            let progress = task.get_progress()?;
-            progress.status
+        let Some(chunk_result) = chunk_result else {
+            break;
         };
 
-        if current_status != DownloadStatus::Paused {
-            return Err(DownloadError::Other(format!(
-                "任务状态不是已暂停: {:?}",
-                current_status
-            )));
-        }
-
-        // 发送恢复事件
-        let _ = self
-            .event_sender
-            .send(DownloadEvent::Resumed(task_id.to_string()))
-            .await;
+        let chunk = chunk_result?;
+        let len = (chunk.len() as u64).min(remaining);
+        file.write_all(&chunk[..len as usize])?;
+        remaining -= len;
 
-        // 重新开始任务
-        self.start_task(task_id).await
+        // 写入成功后再记录进度，保证断点信息不超过实际写入的数据
+        task.advance_segment(segment_id, len)?;
+        let _ = progress_tx.send(len);
     }
 
-    /// 取消下载任务
-    pub async fn cancel_task(&self, task_id: &str) -> Result<(), DownloadError> {
-        let task = {
-            let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
-            tasks
-                .get(task_id)
-                .cloned()
-                .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))?
-        };
+    // 刷新到磁盘
+    file.sync_data()?;
 
-        // 设置状态为已取消
-        task.set_status(DownloadStatus::Cancelled)?;
-
-        // 取消活跃任务
-        {
-            let mut active_tasks = self
-                .active_tasks
-                .lock()
-                .map_err(|_| DownloadError::LockError)?;
-            if let Some(handles) = active_tasks.remove(task_id) {
-                for handle in handles {
-                    handle.abort();
-                }
-            }
-        }
+    if remaining > 0 && !*stop_rx.borrow() {
+        return Err(DownloadError::HttpError(format!(
+            "分段 {} 数据不完整",
+            segment_id
+        )));
+    }
 
-        // 删除临时文件
-        let temp_path = task.full_path().with_extension("part");
-        if temp_path.exists() {
-            let _ = fs::remove_file(temp_path);
-        }
+    Ok(())
+}
 
-        // 发送取消事件
-        let _ = self
-            .event_sender
-            .send(DownloadEvent::Cancelled(task_id.to_string()))
-            .await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::ProxyConfig;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 测试下载的文件内容
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ-_";
+
+    /// 不经过系统代理的下载管理器
+    fn manager(max_concurrent: usize) -> DownloadManager {
+        let shared = SharedClient::new();
+        // 代理模式没有从网络模块导出，按配置文件中的格式指定
+        let proxy: ProxyConfig = serde_json::from_str(r#"{ "mode": "direct" }"#).unwrap();
+        shared.set_proxy(proxy).unwrap();
+        DownloadManager::new(max_concurrent, shared)
+    }
 
-        Ok(())
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novacl-manager-{}", Uuid::new_v4()))
     }
 
-    /// 获取任务列表
-    pub fn get_tasks(&self) -> Result<Vec<(String, DownloadProgress)>, DownloadError> {
-        let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
+    /// 本地HTTP服务，支持HEAD和Range请求
+    ///
+    /// 每个GET请求先发送范围内的前一半数据，等`release`放行后再发送剩余部分。
+    async fn serve_file() -> (String, watch::Sender<bool>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (release, released) = watch::channel(false);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut released = released.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(len) if len > 0 => request.extend_from_slice(&buf[..len]),
+                            _ => return,
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+
+                    if request.starts_with("head") {
+                        let head = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+                            BODY.len()
+                        );
+                        let _ = socket.write_all(head.as_bytes()).await;
+                        return;
+                    }
 
-        let mut result = Vec::new();
-        for (id, task) in tasks.iter() {
-            if let Ok(progress) = task.get_progress() {
-                result.push((id.clone(), progress));
+                    let (start, end) = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim().split_once('-'))
+                        .map(|(start, end)| (start.parse().unwrap(), end.parse().unwrap()))
+                        .unwrap_or((0, BODY.len() - 1));
+                    let body = &BODY[start..=end];
+                    let head = format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        body.len(),
+                        start,
+                        end,
+                        BODY.len()
+                    );
+                    let (first, rest) = body.split_at(body.len() / 2);
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(first).await;
+                    let _ = socket.flush().await;
+                    if released.wait_for(|released| *released).await.is_ok() {
+                        let _ = socket.write_all(rest).await;
+                    }
+                });
             }
-        }
-
-        Ok(result)
+        });
+        (format!("http://{}/file.bin", addr), release)
     }
 
-    /// 获取任务进度
-    pub fn get_task_progress(&self, task_id: &str) -> Result<DownloadProgress, DownloadError> {
-        let tasks = self.tasks.lock().map_err(|_| DownloadError::LockError)?;
+    /// 等待任务进度满足条件
+    async fn wait_for(
+        manager: &DownloadManager,
+        task_id: &str,
+        condition: impl Fn(&DownloadProgress) -> bool,
+    ) -> DownloadProgress {
+        let wait = async {
+            loop {
+                let progress = manager.get_task_progress(task_id).await.unwrap();
+                if condition(&progress) {
+                    return progress;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(10), wait)
+            .await
+            .unwrap()
+    }
 
-        let task = tasks
-            .get(task_id)
-            .ok_or_else(|| DownloadError::TaskNotFound(task_id.to_string()))?;
-        task.get_progress()
+    /// 启动一个不会结束的作业，等待其开始运行
+    async fn pending_job(
+        manager: &DownloadManager,
+        job_id: &str,
+    ) -> tokio::task::JoinHandle<Result<(), DownloadError>> {
+        let (started_tx, started_rx) = oneshot::channel();
+        let manager = manager.clone();
+        let job_id = job_id.to_string();
+        let handle = tokio::spawn(async move {
+            let job = async move {
+                let _ = started_tx.send(());
+                std::future::pending().await
+            };
+            manager.run_job(&job_id, job).await
+        });
+        started_rx.await.unwrap();
+        handle
     }
 
-    /// 获取文件大小
-    async fn get_file_size(&self, url: &str) -> Result<u64, DownloadError> {
-        let response = self.client.head(url).send().await?;
+    #[tokio::test]
+    async fn runs_jobs_to_completion() {
+        let manager = DownloadManager::new(1, SharedClient::new());
+        let result = manager
+            .run_job("job", async { Ok::<_, DownloadError>(42) })
+            .await;
+        assert_eq!(result.unwrap(), 42);
+        assert!(!manager.cancel_job("job").await.unwrap());
+    }
 
-        if !response.status().is_success() {
-            return Err(DownloadError::HttpError(format!(
-                "HTTP错误: {}",
-                response.status()
-            )));
-        }
+    #[tokio::test]
+    async fn cancels_jobs() {
+        let manager = DownloadManager::new(1, SharedClient::new());
+        let job = pending_job(&manager, "job").await;
 
-        let content_length = response
-            .headers()
-            .get(reqwest::header::CONTENT_LENGTH)
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<u64>().ok())
-            .ok_or(DownloadError::ContentLengthError)?;
+        let result = manager
+            .run_job("job", async { Ok::<_, DownloadError>(()) })
+            .await;
+        assert!(matches!(result, Err(DownloadError::TaskAlreadyExists(_))));
 
-        Ok(content_length)
+        assert!(manager.cancel_job("job").await.unwrap());
+        assert!(matches!(job.await.unwrap(), Err(DownloadError::Cancelled)));
     }
 
-    /// 下载分段
-    async fn download_segment(
-        client: reqwest::Client,
-        url: &str,
-        file_path: &PathBuf,
-        start: u64,
-        end: u64,
-        segment_id: usize,
-        progress_tx: mpsc::Sender<(usize, u64)>,
-    ) -> Result<(), DownloadError> {
-        // 设置请求头
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            RANGE,
-            HeaderValue::from_str(&format!("bytes={}-{}", start, end))?,
-        );
+    #[tokio::test]
+    async fn shutdown_cancels_and_waits_for_jobs() {
+        let manager = DownloadManager::new(1, SharedClient::new());
+        let job = pending_job(&manager, "job").await;
 
-        // 发送请求
-        let response = client.get(url).headers(headers).send().await?;
+        manager.shutdown().await.unwrap();
+        assert!(matches!(job.await.unwrap(), Err(DownloadError::Cancelled)));
 
-        if !response.status().is_success() {
-            return Err(DownloadError::HttpError(format!(
-                "HTTP错误: {}",
-                response.status()
-            )));
-        }
+        let result = manager
+            .run_job("other", async { Ok::<_, DownloadError>(()) })
+            .await;
+        assert!(matches!(result, Err(DownloadError::ShuttingDown)));
+    }
 
-        // 打开文件
-        let mut file = fs::OpenOptions::new().write(true).open(file_path)?;
+    #[tokio::test]
+    async fn rejects_pausing_tasks_that_are_not_running() {
+        let dir = temp_dir();
+        let path = dir.join("checkpoints.json");
+        let checkpoint = TaskCheckpoint {
+            id: "task".to_string(),
+            url: "http://127.0.0.1:1/file.bin".to_string(),
+            resolved_url: "http://127.0.0.1:1/file.bin".to_string(),
+            save_path: dir.clone(),
+            filename: "file.bin".to_string(),
+            segments: 1,
+            total: 0,
+            segment_progress: Vec::new(),
+        };
+        save_checkpoints(&path, &[checkpoint]).unwrap();
+
+        let manager = manager(1);
+        let restored = manager.restore(path).await;
+        let result = manager.pause_task("task").await;
+        let _ = std::fs::remove_dir_all(&dir);
 
-        // 设置文件指针位置
-        file.seek(SeekFrom::Start(start))?;
+        assert_eq!(restored.unwrap(), 1);
+        assert!(matches!(result, Err(DownloadError::InvalidState(_))));
+    }
 
-        // 下载数据
-        let mut stream = response.bytes_stream();
-        let mut _downloaded = 0;
+    #[tokio::test]
+    async fn shutdown_pauses_active_tasks_and_saves_checkpoints() {
+        let (url, _release) = serve_file().await;
+        let dir = temp_dir();
+        let path = dir.join("checkpoints.json");
+
+        let manager = manager(1);
+        manager.restore(path.clone()).await.unwrap();
+        let task_id = manager
+            .add_task(&url, dir.clone(), Some("file.bin".to_string()), 1)
+            .await
+            .unwrap();
+        manager.start_task(&task_id).await.unwrap();
+        wait_for(&manager, &task_id, |progress| {
+            progress.downloaded == BODY.len() as u64 / 2
+        })
+        .await;
+
+        manager.shutdown().await.unwrap();
+        let progress = manager.get_task_progress(&task_id).await.unwrap();
+        let checkpoints = load_checkpoints(&path).unwrap();
+        let partial = std::fs::read(dir.join("file.bin.part")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(progress.status, DownloadStatus::Paused);
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].id, task_id);
+        assert_eq!(checkpoints[0].total, BODY.len() as u64);
+        assert_eq!(
+            checkpoints[0].segment_progress[0].downloaded,
+            BODY.len() as u64 / 2
+        );
+        assert_eq!(&partial[..BODY.len() / 2], &BODY[..BODY.len() / 2]);
+    }
 
-        while let Some(chunk_result) = stream.next().await {
-            let chunk = chunk_result?;
-            file.write_all(&chunk)?;
+    #[tokio::test]
+    async fn pauses_and_resumes_tasks() {
+        let (url, release) = serve_file().await;
+        let dir = temp_dir();
+
+        let manager = manager(1);
+        let task_id = manager
+            .add_task(&url, dir.clone(), Some("file.bin".to_string()), 1)
+            .await
+            .unwrap();
+        manager.start_task(&task_id).await.unwrap();
+        wait_for(&manager, &task_id, |progress| {
+            progress.downloaded == BODY.len() as u64 / 2
+        })
+        .await;
+
+        manager.pause_task(&task_id).await.unwrap();
+        let paused = manager.get_task_progress(&task_id).await.unwrap();
+        let resume_paused = manager.resume_task(&task_id).await;
+
+        release.send_replace(true);
+        let completed = wait_for(&manager, &task_id, |progress| {
+            progress.status == DownloadStatus::Completed
+        })
+        .await;
+        let resume_completed = manager.resume_task(&task_id).await;
+        let content = std::fs::read(dir.join("file.bin")).unwrap();
+        let temp_left = dir.join("file.bin.part").exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(paused.status, DownloadStatus::Paused);
+        assert_eq!(paused.downloaded, BODY.len() as u64 / 2);
+        assert!(resume_paused.is_ok());
+        assert_eq!(completed.downloaded, BODY.len() as u64);
+        assert!(matches!(
+            resume_completed,
+            Err(DownloadError::InvalidState(_))
+        ));
+        assert_eq!(content, BODY);
+        assert!(!temp_left);
+    }
 
-            _downloaded += chunk.len() as u64;
+    #[tokio::test]
+    async fn cancels_tasks_and_removes_temp_file() {
+        let (url, _release) = serve_file().await;
+        let dir = temp_dir();
+
+        let manager = manager(1);
+        let task_id = manager
+            .add_task(&url, dir.clone(), Some("file.bin".to_string()), 1)
+            .await
+            .unwrap();
+        manager.start_task(&task_id).await.unwrap();
+        wait_for(&manager, &task_id, |progress| progress.downloaded > 0).await;
+
+        manager.cancel_task(&task_id).await.unwrap();
+        let progress = manager.get_task_progress(&task_id).await.unwrap();
+        let pause = manager.pause_task(&task_id).await;
+        let temp_left = dir.join("file.bin.part").exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(progress.status, DownloadStatus::Cancelled);
+        assert!(matches!(pause, Err(DownloadError::InvalidState(_))));
+        assert!(!temp_left);
+    }
 
-            // 发送进度更新
-            if let Err(e) = progress_tx.send((segment_id, chunk.len() as u64)).await {
-                eprintln!("发送进度更新失败: {}", e);
-            }
+    #[tokio::test]
+    async fn queues_tasks_beyond_max_concurrent_downloads() {
+        let (url, release) = serve_file().await;
+        let dir = temp_dir();
+
+        let manager = manager(1);
+        let first = manager
+            .add_task(&url, dir.clone(), Some("first.bin".to_string()), 1)
+            .await
+            .unwrap();
+        let second = manager
+            .add_task(&url, dir.clone(), Some("second.bin".to_string()), 1)
+            .await
+            .unwrap();
+        manager.start_task(&first).await.unwrap();
+        manager.start_task(&second).await.unwrap();
+        wait_for(&manager, &first, |progress| progress.downloaded > 0).await;
+        let queued = manager.get_task_progress(&second).await.unwrap();
+
+        release.send_replace(true);
+        for task_id in [&first, &second] {
+            wait_for(&manager, task_id, |progress| {
+                progress.status == DownloadStatus::Completed
+            })
+            .await;
         }
-
-        Ok(())
+        let first_content = std::fs::read(dir.join("first.bin")).unwrap();
+        let second_content = std::fs::read(dir.join("second.bin")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(queued.status, DownloadStatus::Pending);
+        assert_eq!(queued.downloaded, 0);
+        assert_eq!(first_content, BODY);
+        assert_eq!(second_content, BODY);
     }
 }
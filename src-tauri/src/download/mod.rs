@@ -1,10 +1,17 @@
 //! 下载模块
 //!
-//! 这个模块提供了一个多线程下载管理器，支持暂停/恢复、多线程并行下载、进度报告和错误重试机制。
+//! 这个模块提供了一个多线程下载管理器，支持暂停/恢复、断点续传、多线程并行下载、进度报告和错误重试机制。
+//! 资源文件等大量小文件可以通过批量模式下载，并按SHA-1校验。
+//! 安装版本等由多次批量下载组成的操作可以作为作业运行，作业可以取消，关闭管理器时一并停止。
 
+mod batch;
+mod checkpoint;
 mod error;
+mod filename;
 mod manager;
 mod task;
 
+pub use batch::{verify_file, BatchItem, BatchProgress};
+pub use error::DownloadError;
 pub use manager::DownloadManager;
 pub use task::DownloadProgress;
@@ -1,10 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::{
-    atomic::{AtomicU64, Ordering},
-    Arc, Mutex,
-};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
 
 use crate::download::error::DownloadError;
 
@@ -81,23 +79,90 @@ impl DownloadProgress {
     }
 }
 
+/// 单个分段的下载进度，用于断点续传
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentProgress {
+    /// 起始字节（包含）
+    pub start: u64,
+    /// 结束字节（包含）
+    pub end: u64,
+    /// 已写入文件的字节数
+    pub downloaded: u64,
+}
+
+impl SegmentProgress {
+    /// 创建一个新的分段
+    pub fn new(start: u64, end: u64) -> Self {
+        Self {
+            start,
+            end,
+            downloaded: 0,
+        }
+    }
+
+    /// 分段的总字节数
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// 下一个要下载的字节位置
+    pub fn next_offset(&self) -> u64 {
+        self.start + self.downloaded
+    }
+
+    /// 分段是否已下载完成
+    pub fn is_complete(&self) -> bool {
+        self.downloaded >= self.len()
+    }
+
+    /// 将文件按分段数量划分为若干分段
+    pub fn split(file_size: u64, segments: usize) -> Vec<Self> {
+        if file_size == 0 {
+            return Vec::new();
+        }
+
+        // 分段数量不能超过文件字节数
+        let count = (segments.max(1) as u64).min(file_size);
+        let segment_size = file_size / count;
+
+        (0..count)
+            .map(|i| {
+                let start = i * segment_size;
+                let end = if i == count - 1 {
+                    file_size - 1
+                } else {
+                    (i + 1) * segment_size - 1
+                };
+                Self::new(start, end)
+            })
+            .collect()
+    }
+}
+
 /// 下载任务
+///
+/// 进度和分段信息保存在`watch`通道中：读取不会阻塞写入方，也不存在锁中毒，
+/// 可以在任意异步任务中安全地访问。
 #[derive(Debug)]
 pub struct DownloadTask {
     /// 任务ID
     pub id: String,
     /// 下载URL
     pub url: String,
+    /// 跟随重定向后的最终URL
+    pub resolved_url: String,
     /// 保存路径
     pub save_path: PathBuf,
     /// 文件名
     pub filename: String,
     /// 下载进度
-    pub progress: Arc<Mutex<DownloadProgress>>,
+    pub progress: watch::Sender<DownloadProgress>,
     /// 开始时间 - 使用AtomicU64存储UNIX时间戳（毫秒）
     pub start_time: AtomicU64,
     /// 分段数量
     pub segments: usize,
+    /// 各分段的下载进度
+    pub segment_progress: watch::Sender<Vec<SegmentProgress>>,
     /// 重试次数
     pub retry_count: usize,
     /// 最大重试次数
@@ -109,6 +174,7 @@ impl DownloadTask {
     pub fn new(
         id: String,
         url: String,
+        resolved_url: String,
         save_path: PathBuf,
         filename: String,
         segments: usize,
@@ -116,11 +182,13 @@ impl DownloadTask {
         Self {
             id,
             url,
+            resolved_url,
             save_path,
             filename,
-            progress: Arc::new(Mutex::new(DownloadProgress::new(0))),
+            progress: watch::Sender::new(DownloadProgress::new(0)),
             start_time: AtomicU64::new(0), // 0表示未开始
             segments,
+            segment_progress: watch::Sender::new(Vec::new()),
             retry_count: 0,
             max_retries: 3,
         }
@@ -131,17 +199,55 @@ impl DownloadTask {
         self.save_path.join(&self.filename)
     }
 
+    /// 获取临时文件路径（在完整文件名后追加`.part`，避免不同扩展名的文件互相覆盖）
+    pub fn temp_path(&self) -> PathBuf {
+        self.save_path.join(format!("{}.part", self.filename))
+    }
+
+    /// 获取各分段的下载进度
+    pub fn get_segments(&self) -> Vec<SegmentProgress> {
+        self.segment_progress.borrow().clone()
+    }
+
+    /// 设置各分段的下载进度
+    pub fn set_segments(&self, segments: Vec<SegmentProgress>) {
+        self.segment_progress.send_replace(segments);
+    }
+
+    /// 记录分段新写入的字节数，返回该分段的最新进度
+    pub fn advance_segment(
+        &self,
+        segment_id: usize,
+        bytes: u64,
+    ) -> Result<SegmentProgress, DownloadError> {
+        let mut result = None;
+        self.segment_progress.send_if_modified(|segments| {
+            let Some(segment) = segments.get_mut(segment_id) else {
+                return false;
+            };
+            segment.downloaded = (segment.downloaded + bytes).min(segment.len());
+            result = Some(segment.clone());
+            true
+        });
+        result.ok_or_else(|| DownloadError::Other(format!("分段不存在: {}", segment_id)))
+    }
+
+    /// 所有分段已下载的字节数之和
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.segment_progress
+            .borrow()
+            .iter()
+            .map(|s| s.downloaded)
+            .sum()
+    }
+
     /// 设置总大小
-    pub fn set_total_size(&self, size: u64) -> Result<(), DownloadError> {
-        let mut progress = self.progress.lock().map_err(|_| DownloadError::LockError)?;
-        progress.total = size;
-        Ok(())
+    pub fn set_total_size(&self, size: u64) {
+        self.progress.send_modify(|progress| progress.total = size);
     }
 
     /// 更新下载进度
-    pub fn update_progress(&self, downloaded: u64) -> Result<(), DownloadError> {
-        let mut progress = self.progress.lock().map_err(|_| DownloadError::LockError)?;
-
+    pub fn update_progress(&self, downloaded: u64) {
         let start_timestamp = self.start_time.load(Ordering::Relaxed);
         let elapsed = if start_timestamp > 0 {
             let now = SystemTime::now()
@@ -153,21 +259,24 @@ impl DownloadTask {
             Duration::from_secs(0)
         };
 
-        progress.update(downloaded, elapsed);
-        Ok(())
+        self.progress
+            .send_modify(|progress| progress.update(downloaded, elapsed));
     }
 
     /// 设置下载状态
-    pub fn set_status(&self, status: DownloadStatus) -> Result<(), DownloadError> {
-        let mut progress = self.progress.lock().map_err(|_| DownloadError::LockError)?;
-        progress.status = status;
-        Ok(())
+    pub fn set_status(&self, status: DownloadStatus) {
+        self.progress
+            .send_modify(|progress| progress.status = status);
+    }
+
+    /// 获取当前下载状态
+    pub fn status(&self) -> DownloadStatus {
+        self.progress.borrow().status
     }
 
     /// 获取当前下载进度
-    pub fn get_progress(&self) -> Result<DownloadProgress, DownloadError> {
-        let progress = self.progress.lock().map_err(|_| DownloadError::LockError)?;
-        Ok(progress.clone())
+    pub fn get_progress(&self) -> DownloadProgress {
+        self.progress.borrow().clone()
     }
 
     /// 设置开始时间
@@ -0,0 +1,403 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::download::DownloadError;
+use crate::minecraft::MinecraftError;
+use crate::network::NetworkError;
+
+/// 错误码，前端根据错误码显示本地化的提示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 读写文件失败
+    Io,
+    /// 无效的URL
+    InvalidUrl,
+    /// 无效的参数或配置
+    InvalidArgument,
+    /// 请求超时
+    Timeout,
+    /// 无法连接服务器或连接中断
+    Connection,
+    /// 服务器返回了非2xx状态码
+    HttpStatus,
+    /// 无法解析响应
+    ResponseParse,
+    /// 离线模式下缓存中没有该请求的响应
+    CacheMiss,
+    /// 无法获取文件大小
+    ContentLength,
+    /// 服务器不支持断点续传
+    RangeNotSupported,
+    /// 下载任务已存在
+    TaskAlreadyExists,
+    /// 下载任务不存在
+    TaskNotFound,
+    /// 下载任务当前的状态不允许该操作
+    InvalidState,
+    /// 下载管理器已停止或正在关闭
+    ShuttingDown,
+    /// 读写设置失败
+    Settings,
+    /// 请求已被取消
+    Cancelled,
+    /// 下载的文件校验失败
+    ChecksumMismatch,
+    /// 批量下载中有文件失败
+    BatchFailed,
+    /// Minecraft版本不存在
+    VersionNotFound,
+    /// 版本JSON内容无效
+    InvalidVersion,
+    /// 其他错误
+    Other,
+}
+
+/// 返回给前端的错误
+#[derive(Debug, Clone, Serialize)]
+pub struct AppError {
+    /// 错误码
+    pub code: ErrorCode,
+    /// 错误信息
+    pub message: String,
+    /// HTTP状态码，只有服务器返回了响应时才有
+    pub status: Option<u16>,
+    /// 响应体的开头部分
+    pub body: Option<String>,
+    /// 稍后重试是否可能成功
+    pub retryable: bool,
+    /// 导致该错误的底层错误，由外到内排列
+    pub sources: Vec<String>,
+}
+
+impl AppError {
+    /// 创建一个错误
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            status: None,
+            body: None,
+            retryable: false,
+            sources: Vec::new(),
+        }
+    }
+
+    /// 记录底层错误链
+    fn with_sources(mut self, err: &dyn std::error::Error) -> Self {
+        let mut source = err.source();
+        while let Some(err) = source {
+            self.sources.push(err.to_string());
+            source = err.source();
+        }
+        self
+    }
+
+    /// 记录HTTP状态码，并根据状态码判断是否可以重试
+    fn with_status(mut self, status: u16, body: Option<String>) -> Self {
+        self.status = Some(status);
+        self.body = body;
+        // 408、429和5xx通常是暂时性的
+        self.retryable = matches!(status, 408 | 429 | 500..=599);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<NetworkError> for AppError {
+    fn from(err: NetworkError) -> Self {
+        // 重试后仍然失败时，按最后一次失败的原因分类
+        let mut cause = &err;
+        while let NetworkError::RetriesExhausted { source, .. } = cause {
+            cause = source;
+        }
+
+        let code = match cause {
+            NetworkError::IoError(_) => ErrorCode::Io,
+            NetworkError::HttpError { .. } => ErrorCode::HttpStatus,
+            NetworkError::InvalidUrl(_) => ErrorCode::InvalidUrl,
+            NetworkError::Timeout => ErrorCode::Timeout,
+            NetworkError::ConnectionError(_) => ErrorCode::Connection,
+            NetworkError::ResponseParseError(_) => ErrorCode::ResponseParse,
+            NetworkError::CacheMiss(_) => ErrorCode::CacheMiss,
+            NetworkError::InvalidArgument(_) => ErrorCode::InvalidArgument,
+            NetworkError::Cancelled => ErrorCode::Cancelled,
+            NetworkError::RetriesExhausted { .. } | NetworkError::Other(_) => ErrorCode::Other,
+        };
+
+        let error = Self::new(code, err.to_string()).with_sources(&err);
+        match cause {
+            NetworkError::HttpError { status, body } => error.with_status(*status, body.clone()),
+            cause => Self {
+                retryable: cause.is_retryable(),
+                ..error
+            },
+        }
+    }
+}
+
+impl From<DownloadError> for AppError {
+    fn from(err: DownloadError) -> Self {
+        // 下载时的网络错误按网络错误分类
+        let err = match err {
+            DownloadError::Network(err) => return Self::from(err),
+            err => err,
+        };
+
+        let code = match &err {
+            DownloadError::Network(_) => ErrorCode::Other,
+            DownloadError::IoError(_) | DownloadError::WriteError(_) => ErrorCode::Io,
+            DownloadError::HttpError(_) => ErrorCode::Connection,
+            DownloadError::HttpStatus(_) => ErrorCode::HttpStatus,
+            DownloadError::InvalidUrl(_) => ErrorCode::InvalidUrl,
+            DownloadError::ContentLengthError => ErrorCode::ContentLength,
+            DownloadError::RangeNotSupported => ErrorCode::RangeNotSupported,
+            DownloadError::TaskAlreadyExists(_) => ErrorCode::TaskAlreadyExists,
+            DownloadError::TaskNotFound(_) => ErrorCode::TaskNotFound,
+            DownloadError::InvalidState(_) => ErrorCode::InvalidState,
+            DownloadError::ManagerStopped | DownloadError::ShuttingDown => ErrorCode::ShuttingDown,
+            DownloadError::ReadTimeout => ErrorCode::Timeout,
+            DownloadError::Cancelled => ErrorCode::Cancelled,
+            DownloadError::ChecksumMismatch(_) => ErrorCode::ChecksumMismatch,
+            DownloadError::BatchFailed { .. } => ErrorCode::BatchFailed,
+            DownloadError::Other(_) => ErrorCode::Other,
+        };
+
+        let retryable = err.is_retryable();
+        let error = Self::new(code, err.to_string()).with_sources(&err);
+        let error = match err {
+            DownloadError::HttpStatus(status) => error.with_status(status, None),
+            _ => error,
+        };
+        // 与下载重试使用同一判断
+        Self { retryable, ..error }
+    }
+}
+
+impl From<MinecraftError> for AppError {
+    fn from(err: MinecraftError) -> Self {
+        // 网络和下载错误按原来的错误分类
+        let err = match err {
+            MinecraftError::Network(err) => return Self::from(err),
+            MinecraftError::Download(err) => return Self::from(err),
+            err => err,
+        };
+
+        let code = match &err {
+            MinecraftError::Network(_) | MinecraftError::Download(_) => ErrorCode::Other,
+            MinecraftError::IoError(_) => ErrorCode::Io,
+            MinecraftError::ParseError(_) => ErrorCode::ResponseParse,
+            MinecraftError::VersionNotFound(_) => ErrorCode::VersionNotFound,
+            MinecraftError::InvalidProfile(_) => ErrorCode::InvalidVersion,
+            MinecraftError::InvalidSettings(_) => ErrorCode::InvalidArgument,
+        };
+        Self::new(code, err.to_string()).with_sources(&err)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::new(ErrorCode::InvalidArgument, format!("数据格式错误: {}", err))
+    }
+}
+
+impl From<tauri_plugin_store::Error> for AppError {
+    fn from(err: tauri_plugin_store::Error) -> Self {
+        Self::new(ErrorCode::Settings, format!("读写设置失败: {}", err)).with_sources(&err)
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(err: tauri::Error) -> Self {
+        Self::new(ErrorCode::Other, err.to_string()).with_sources(&err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    fn io_error() -> io::Error {
+        io::Error::other("disk full")
+    }
+
+    fn assert_maps(error: AppError, code: ErrorCode, retryable: bool) {
+        assert_eq!(error.code, code, "{}", error.message);
+        assert_eq!(error.retryable, retryable, "{}", error.message);
+    }
+
+    #[test]
+    fn maps_network_errors() {
+        let cases = [
+            (NetworkError::IoError(io_error()), ErrorCode::Io, false),
+            (
+                NetworkError::http_status(404, b"not found"),
+                ErrorCode::HttpStatus,
+                false,
+            ),
+            (
+                NetworkError::http_status(503, b""),
+                ErrorCode::HttpStatus,
+                true,
+            ),
+            (
+                NetworkError::http_status(429, b""),
+                ErrorCode::HttpStatus,
+                true,
+            ),
+            (
+                NetworkError::InvalidUrl(String::new()),
+                ErrorCode::InvalidUrl,
+                false,
+            ),
+            (NetworkError::Timeout, ErrorCode::Timeout, true),
+            (
+                NetworkError::ConnectionError(String::new()),
+                ErrorCode::Connection,
+                true,
+            ),
+            (
+                NetworkError::ResponseParseError(String::new()),
+                ErrorCode::ResponseParse,
+                false,
+            ),
+            (
+                NetworkError::CacheMiss(String::new()),
+                ErrorCode::CacheMiss,
+                false,
+            ),
+            (
+                NetworkError::RetriesExhausted {
+                    attempts: vec!["第1次: 请求超时".to_string()],
+                    source: Box::new(NetworkError::Timeout),
+                },
+                ErrorCode::Timeout,
+                true,
+            ),
+            (
+                NetworkError::InvalidArgument(String::new()),
+                ErrorCode::InvalidArgument,
+                false,
+            ),
+            (NetworkError::Cancelled, ErrorCode::Cancelled, false),
+            (NetworkError::Other(String::new()), ErrorCode::Other, false),
+        ];
+        for (error, code, retryable) in cases {
+            assert_maps(AppError::from(error), code, retryable);
+        }
+    }
+
+    #[test]
+    fn maps_download_errors() {
+        let cases = [
+            (DownloadError::IoError(io_error()), ErrorCode::Io, false),
+            (
+                DownloadError::HttpError(String::new()),
+                ErrorCode::Connection,
+                false,
+            ),
+            (
+                DownloadError::Network(NetworkError::Timeout),
+                ErrorCode::Timeout,
+                true,
+            ),
+            (DownloadError::HttpStatus(404), ErrorCode::HttpStatus, false),
+            (DownloadError::HttpStatus(502), ErrorCode::HttpStatus, true),
+            (
+                DownloadError::InvalidUrl(String::new()),
+                ErrorCode::InvalidUrl,
+                false,
+            ),
+            (
+                DownloadError::ContentLengthError,
+                ErrorCode::ContentLength,
+                false,
+            ),
+            (
+                DownloadError::RangeNotSupported,
+                ErrorCode::RangeNotSupported,
+                false,
+            ),
+            (
+                DownloadError::TaskAlreadyExists(String::new()),
+                ErrorCode::TaskAlreadyExists,
+                false,
+            ),
+            (
+                DownloadError::TaskNotFound(String::new()),
+                ErrorCode::TaskNotFound,
+                false,
+            ),
+            (
+                DownloadError::InvalidState(String::new()),
+                ErrorCode::InvalidState,
+                false,
+            ),
+            (
+                DownloadError::ManagerStopped,
+                ErrorCode::ShuttingDown,
+                false,
+            ),
+            (
+                DownloadError::WriteError(String::new()),
+                ErrorCode::Io,
+                false,
+            ),
+            (DownloadError::ShuttingDown, ErrorCode::ShuttingDown, false),
+            (DownloadError::ReadTimeout, ErrorCode::Timeout, true),
+            (DownloadError::Cancelled, ErrorCode::Cancelled, false),
+            (
+                DownloadError::ChecksumMismatch(String::new()),
+                ErrorCode::ChecksumMismatch,
+                false,
+            ),
+            (
+                DownloadError::BatchFailed {
+                    failed: 1,
+                    reason: String::new(),
+                },
+                ErrorCode::BatchFailed,
+                false,
+            ),
+            (DownloadError::Other(String::new()), ErrorCode::Other, false),
+        ];
+        for (error, code, retryable) in cases {
+            let expected = error.is_retryable();
+            let error = AppError::from(error);
+            assert_eq!(error.retryable, expected, "{}", error.message);
+            assert_maps(error, code, retryable);
+        }
+    }
+
+    #[test]
+    fn maps_invalid_headers_to_invalid_argument() {
+        let invalid = reqwest::header::HeaderValue::from_str("a\nb").unwrap_err();
+        assert_maps(
+            AppError::from(NetworkError::from(invalid)),
+            ErrorCode::InvalidArgument,
+            false,
+        );
+
+        let invalid = reqwest::header::HeaderValue::from_str("a\nb").unwrap_err();
+        assert_maps(
+            AppError::from(DownloadError::from(invalid)),
+            ErrorCode::InvalidArgument,
+            false,
+        );
+
+        let invalid = reqwest::header::HeaderName::from_bytes(b"a b").unwrap_err();
+        assert_maps(
+            AppError::from(NetworkError::from(invalid)),
+            ErrorCode::InvalidArgument,
+            false,
+        );
+    }
+}
@@ -1,23 +1,68 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod download;
+mod error;
+mod minecraft;
 mod network;
 
-use std::path::PathBuf;
-use std::sync::Arc;
-use tauri::async_runtime::Mutex;
-use tauri::State;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, RunEvent, State};
+use tauri_plugin_store::StoreExt;
 
-use download::{DownloadManager, DownloadProgress};
-use network::HttpClient;
+use download::{BatchProgress, DownloadManager, DownloadProgress};
+use error::AppError;
+use minecraft::{
+    build_launch_command, extract_natives, install_assets, install_version, resolve_libraries,
+    resolve_profile, GameDir, InstallProgress, LaunchAccount, LaunchCommand, LaunchInstance,
+    LaunchSettings, LibraryFile, MinecraftError, Platform, VersionEntry, VersionList,
+    VersionManifestService, VersionProfile,
+};
+use network::{
+    CancelRegistry, DiagnosticsConfig, DiagnosticsReport, HttpCache, HttpClient, HttpClientConfig,
+    HttpRequest, HttpResponse, MirrorConfig, MultipartPart, NetworkError, ProxyConfig,
+    RecorderConfig, RequestOptions, SharedClient, StreamAcks, StreamEvent, StreamFormat,
+    TrustConfig, UploadProgress,
+};
+
+// 设置存储文件
+const SETTINGS_STORE: &str = "settings.json";
+// 代理设置的存储键
+const PROXY_SETTINGS_KEY: &str = "proxy";
+// HTTP客户端设置的存储键
+const HTTP_SETTINGS_KEY: &str = "http";
+// 证书信任设置的存储键
+const TRUST_SETTINGS_KEY: &str = "trust";
+// 流量记录设置的存储键
+const RECORDER_SETTINGS_KEY: &str = "recorder";
+// 镜像设置的存储键
+const MIRROR_SETTINGS_KEY: &str = "mirror";
+// HTTP缓存目录
+const HTTP_CACHE_DIR: &str = "http";
+// 下载断点文件
+const DOWNLOAD_CHECKPOINT_FILE: &str = "downloads.json";
+// 退出时等待下载暂停并保存断点的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+// 关闭超时后直接保存断点的最长时间
+const SAVE_CHECKPOINT_TIMEOUT: Duration = Duration::from_secs(1);
 
 // 全局下载管理器状态
 struct DownloadManagerState {
-    manager: Arc<Mutex<DownloadManager>>,
+    manager: DownloadManager,
 }
 
 // 全局HTTP客户端状态
 struct HttpClientState {
-    client: Arc<Mutex<HttpClient>>,
+    client: HttpClient,
+    // 可以由前端取消的进行中请求
+    requests: CancelRegistry,
+    // 流式请求的确认进度
+    streams: StreamAcks,
+}
+
+// 全局Minecraft版本状态
+struct MinecraftState {
+    manifest: VersionManifestService,
 }
 
 #[tauri::command]
@@ -27,70 +72,62 @@ async fn start_download(
     filename: Option<String>,
     segments: Option<usize>,
     state: State<'_, DownloadManagerState>,
-) -> Result<String, String> {
-    let manager = state.inner().manager.lock().await;
+) -> Result<String, AppError> {
+    let manager = &state.inner().manager;
     let save_path = PathBuf::from(save_path);
     let segments = segments.unwrap_or(4); // 默认4个分段
 
     manager
         .add_task(&url, save_path, filename, segments)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn pause_download(
     task_id: String,
     state: State<'_, DownloadManagerState>,
-) -> Result<(), String> {
-    let manager = state.inner().manager.lock().await;
-    manager
-        .pause_task(&task_id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    let manager = &state.inner().manager;
+    manager.pause_task(&task_id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn resume_download(
     task_id: String,
     state: State<'_, DownloadManagerState>,
-) -> Result<(), String> {
-    let manager = state.inner().manager.lock().await;
-    manager
-        .resume_task(&task_id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    let manager = &state.inner().manager;
+    manager.resume_task(&task_id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn cancel_download(
     task_id: String,
     state: State<'_, DownloadManagerState>,
-) -> Result<(), String> {
-    let manager = state.inner().manager.lock().await;
-    manager
-        .cancel_task(&task_id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    let manager = &state.inner().manager;
+    manager.cancel_task(&task_id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn get_download_progress(
     task_id: String,
     state: State<'_, DownloadManagerState>,
-) -> Result<DownloadProgress, String> {
-    let manager = state.inner().manager.lock().await;
+) -> Result<DownloadProgress, AppError> {
+    let manager = &state.inner().manager;
     manager
         .get_task_progress(&task_id)
-        .map_err(|e| e.to_string())
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn get_all_downloads(
     state: State<'_, DownloadManagerState>,
-) -> Result<Vec<(String, DownloadProgress)>, String> {
-    let manager = state.inner().manager.lock().await;
-    manager.get_tasks().map_err(|e| e.to_string())
+) -> Result<Vec<(String, DownloadProgress)>, AppError> {
+    let manager = &state.inner().manager;
+    manager.get_tasks().await.map_err(AppError::from)
 }
 
 // HTTP客户端命令
@@ -98,51 +135,635 @@ async fn get_all_downloads(
 async fn http_get(
     url: String,
     user_agent: Option<String>,
+    options: Option<RequestOptions>,
+    request_id: Option<String>,
     state: State<'_, HttpClientState>,
-) -> Result<String, String> {
-    let mut client = state.inner().client.lock().await;
-
-    // 如果提供了自定义UserAgent，则重新创建客户端
-    if let Some(ua) = &user_agent {
-        *client = HttpClient::with_user_agent(ua);
-    }
+) -> Result<String, AppError> {
+    // 请求选项只对本次请求生效，不会修改共享客户端
+    let options = merge_user_agent(options, user_agent);
+    let state = state.inner();
 
-    client.get_text(&url).await.map_err(|e| e.to_string())
+    state
+        .requests
+        .run(request_id, state.client.get_text(&url, &options))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn http_post_json<T: serde::Serialize>(
+async fn http_post_json(
     url: String,
-    data: T,
+    data: serde_json::Value,
     user_agent: Option<String>,
+    options: Option<RequestOptions>,
+    request_id: Option<String>,
     state: State<'_, HttpClientState>,
-) -> Result<String, String> {
-    let mut client = state.inner().client.lock().await;
+) -> Result<String, AppError> {
+    // 请求选项只对本次请求生效，不会修改共享客户端
+    let options = merge_user_agent(options, user_agent);
+    let state = state.inner();
+
+    state
+        .requests
+        .run(
+            request_id,
+            state
+                .client
+                .post_json::<_, serde_json::Value>(&url, &data, &options),
+        )
+        .await
+        .map(|json| json.to_string())
+        .map_err(AppError::from)
+}
 
-    // 如果提供了自定义UserAgent，则重新创建客户端
-    if let Some(ua) = &user_agent {
-        *client = HttpClient::with_user_agent(ua);
+#[tauri::command]
+async fn http_request(
+    request: HttpRequest,
+    request_id: Option<String>,
+    state: State<'_, HttpClientState>,
+) -> Result<HttpResponse, AppError> {
+    // 非2xx状态码同样作为响应返回，由前端根据状态码处理
+    let state = state.inner();
+
+    state
+        .requests
+        .run(request_id, state.client.execute(&request))
+        .await
+        .map_err(AppError::from)
+}
+
+/// 以multipart/form-data格式上传文本字段和文件，非2xx状态码返回错误
+#[tauri::command]
+async fn http_post_multipart(
+    url: String,
+    parts: Vec<MultipartPart>,
+    options: Option<RequestOptions>,
+    request_id: Option<String>,
+    on_progress: Channel<UploadProgress>,
+    state: State<'_, HttpClientState>,
+) -> Result<HttpResponse, AppError> {
+    let options = options.unwrap_or_default();
+    let state = state.inner();
+    // 前端不再接收进度时忽略发送失败，不影响上传
+    let report = move |progress| {
+        let _ = on_progress.send(progress);
+    };
+
+    state
+        .requests
+        .run(
+            request_id,
+            state.client.post_multipart(&url, &parts, &options, report),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// 流式读取响应体，通过通道分块发送给前端，返回接收的总字节数
+///
+/// 前端处理完每个数据块后需要调用`ack_stream`确认，否则读取会暂停，30秒内仍未确认时以错误结束；
+/// 流ID同时作为请求ID，可以通过`cancel_request`取消。
+#[tauri::command]
+async fn http_stream(
+    request: HttpRequest,
+    format: Option<StreamFormat>,
+    stream_id: String,
+    on_event: Channel<StreamEvent>,
+    state: State<'_, HttpClientState>,
+) -> Result<u64, AppError> {
+    let state = state.inner();
+    let acks = state.streams.register(&stream_id)?;
+    let emit = |event| {
+        on_event
+            .send(event)
+            .map_err(|e| NetworkError::Other(format!("发送数据失败: {}", e)))
+    };
+
+    state
+        .requests
+        .run(
+            Some(stream_id),
+            state
+                .client
+                .stream(&request, format.unwrap_or_default(), acks, emit),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// 确认流式请求中序号及之前的数据块，流不存在或已经结束时返回false
+#[tauri::command]
+async fn ack_stream(
+    stream_id: String,
+    seq: u64,
+    state: State<'_, HttpClientState>,
+) -> Result<bool, AppError> {
+    Ok(state.inner().streams.ack(&stream_id, seq))
+}
+
+/// 取消带有请求ID的请求，请求不存在或已经结束时返回false
+#[tauri::command]
+async fn cancel_request(
+    request_id: String,
+    state: State<'_, HttpClientState>,
+) -> Result<bool, AppError> {
+    Ok(state.inner().requests.cancel(&request_id))
+}
+
+/// 合并单独传入的UserAgent参数，选项中已设置UserAgent时以选项为准
+fn merge_user_agent(options: Option<RequestOptions>, user_agent: Option<String>) -> RequestOptions {
+    let mut options = options.unwrap_or_default();
+    if options.user_agent.is_none() {
+        options.user_agent = user_agent;
     }
+    options
+}
+
+// 代理设置命令
+/// 获取代理配置，不返回密码
+#[tauri::command]
+async fn get_proxy_config(state: State<'_, HttpClientState>) -> Result<ProxyConfig, AppError> {
+    Ok(state.inner().client.shared().proxy().without_password())
+}
+
+/// 保存代理配置，没有传入密码时沿用之前的密码
+///
+/// 密码以明文保存在设置文件中。
+#[tauri::command]
+async fn set_proxy_config(
+    config: ProxyConfig,
+    app: AppHandle,
+    state: State<'_, HttpClientState>,
+) -> Result<(), AppError> {
+    let config = config.keep_password(&state.inner().client.shared().proxy());
+    // HTTP客户端和下载管理器共用同一个客户端，配置无效时直接返回，不影响现有设置
+    state
+        .inner()
+        .client
+        .shared()
+        .set_proxy(config.clone())
+        .map_err(AppError::from)?;
+
+    // 持久化保存
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    store.set(
+        PROXY_SETTINGS_KEY,
+        serde_json::to_value(&config).map_err(AppError::from)?,
+    );
+    store.save().map_err(AppError::from)
+}
+
+// HTTP客户端设置命令
+#[tauri::command]
+async fn get_http_config(state: State<'_, HttpClientState>) -> Result<HttpClientConfig, AppError> {
+    Ok(state.inner().client.shared().config())
+}
+
+#[tauri::command]
+async fn set_http_config(
+    config: HttpClientConfig,
+    app: AppHandle,
+    state: State<'_, HttpClientState>,
+) -> Result<(), AppError> {
+    // 新配置只影响之后发出的请求和开始的下载，配置无效时不影响现有设置
+    state
+        .inner()
+        .client
+        .shared()
+        .set_config(config.clone())
+        .map_err(AppError::from)?;
+
+    // 持久化保存
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    store.set(
+        HTTP_SETTINGS_KEY,
+        serde_json::to_value(&config).map_err(AppError::from)?,
+    );
+    store.save().map_err(AppError::from)
+}
+
+// 镜像设置命令
+#[tauri::command]
+async fn get_mirror_config(state: State<'_, HttpClientState>) -> Result<MirrorConfig, AppError> {
+    Ok(state.inner().client.shared().mirrors())
+}
+
+#[tauri::command]
+async fn set_mirror_config(
+    config: MirrorConfig,
+    app: AppHandle,
+    state: State<'_, HttpClientState>,
+) -> Result<(), AppError> {
+    // HTTP请求和下载都按新规则改写地址，规则无效时不影响现有设置
+    state
+        .inner()
+        .client
+        .shared()
+        .set_mirrors(config.clone())
+        .map_err(AppError::from)?;
 
-    client
-        .post_json::<T, serde_json::Value>(&url, &data)
+    // 持久化保存
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    store.set(
+        MIRROR_SETTINGS_KEY,
+        serde_json::to_value(&config).map_err(AppError::from)?,
+    );
+    store.save().map_err(AppError::from)
+}
+
+// Minecraft版本命令
+#[tauri::command]
+async fn get_minecraft_versions(
+    refresh: bool,
+    state: State<'_, MinecraftState>,
+) -> Result<VersionList, AppError> {
+    let manifest = state
+        .inner()
+        .manifest
+        .manifest(refresh)
         .await
-        .map(|json| json.to_string())
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    Ok(manifest.classify())
+}
+
+#[tauri::command]
+async fn get_minecraft_version(
+    id: String,
+    state: State<'_, MinecraftState>,
+) -> Result<VersionEntry, AppError> {
+    state
+        .inner()
+        .manifest
+        .version(&id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 读取游戏目录中的版本JSON，并合并继承的版本
+#[tauri::command]
+async fn get_version_profile(
+    game_dir: String,
+    version_id: String,
+) -> Result<VersionProfile, AppError> {
+    resolve_profile(&GameDir::new(game_dir), &version_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 获取版本在当前平台上需要的依赖库文件
+#[tauri::command]
+async fn get_version_libraries(
+    game_dir: String,
+    version_id: String,
+) -> Result<Vec<LibraryFile>, AppError> {
+    let profile = resolve_profile(&GameDir::new(game_dir), &version_id)
+        .await
+        .map_err(AppError::from)?;
+    resolve_libraries(
+        &profile.libraries,
+        &Platform::current(),
+        &Default::default(),
+    )
+    .map_err(AppError::from)
+}
+
+/// 为实例下载版本的资源索引和资源文件，通过通道报告已完成的文件数
+///
+/// 与`install_minecraft_version`相同，作为下载管理器的作业运行，可以通过`cancel_install`取消。
+#[tauri::command]
+async fn install_version_assets(
+    game_dir: String,
+    version_id: String,
+    instance: LaunchInstance,
+    install_id: String,
+    on_progress: Channel<BatchProgress>,
+    state: State<'_, DownloadManagerState>,
+) -> Result<BatchProgress, AppError> {
+    let game_dir = GameDir::new(game_dir);
+    let manager = &state.inner().manager;
+    let install = async {
+        let profile = resolve_profile(&game_dir, &version_id).await?;
+        let index = profile.asset_index.ok_or_else(|| {
+            MinecraftError::InvalidProfile(format!("版本没有资源索引: {}", version_id))
+        })?;
+
+        // 最早的版本从实例的运行目录读取资源文件
+        install_assets(
+            manager,
+            &game_dir,
+            &instance.resources_dir(),
+            &index,
+            |progress| {
+                let _ = on_progress.send(progress);
+            },
+        )
+        .await
+    };
+
+    manager
+        .run_job(&install_id, install)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 为实例安装版本运行所需的全部文件，通过通道报告各阶段的进度
+///
+/// 已存在且校验通过的文件会被跳过，可以重复执行；
+/// 安装作为下载管理器的作业运行，可以通过`cancel_install`取消，退出应用时同样会被取消。
+#[tauri::command]
+async fn install_minecraft_version(
+    game_dir: String,
+    version_id: String,
+    instance: LaunchInstance,
+    install_id: String,
+    on_progress: Channel<InstallProgress>,
+    downloads: State<'_, DownloadManagerState>,
+    minecraft: State<'_, MinecraftState>,
+) -> Result<(), AppError> {
+    let game_dir = GameDir::new(game_dir);
+    let manager = &downloads.inner().manager;
+    let install = install_version(
+        manager,
+        &minecraft.inner().manifest,
+        &game_dir,
+        &version_id,
+        &instance,
+        |progress| {
+            let _ = on_progress.send(progress);
+        },
+    );
+
+    manager
+        .run_job(&install_id, install)
+        .await
+        .map(|_| ())
+        .map_err(AppError::from)
+}
+
+/// 取消正在进行的版本安装，安装不存在或已经结束时返回false
+#[tauri::command]
+async fn cancel_install(
+    install_id: String,
+    state: State<'_, DownloadManagerState>,
+) -> Result<bool, AppError> {
+    state
+        .inner()
+        .manager
+        .cancel_job(&install_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// 将版本的本地库解压到新的目录，返回该目录的路径
+#[tauri::command]
+async fn extract_version_natives(game_dir: String, version_id: String) -> Result<String, AppError> {
+    let game_dir = GameDir::new(game_dir);
+    let profile = resolve_profile(&game_dir, &version_id)
+        .await
+        .map_err(AppError::from)?;
+    let libraries = resolve_libraries(
+        &profile.libraries,
+        &Platform::current(),
+        &Default::default(),
+    )
+    .map_err(AppError::from)?;
+    let dir = extract_natives(&game_dir, &version_id, &libraries)
+        .await
+        .map_err(AppError::from)?;
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// 生成启动游戏的命令，本地库目录为`extract_version_natives`返回的目录
+#[tauri::command]
+async fn get_launch_command(
+    game_dir: String,
+    version_id: String,
+    natives_dir: String,
+    account: LaunchAccount,
+    instance: LaunchInstance,
+    settings: LaunchSettings,
+) -> Result<LaunchCommand, AppError> {
+    let game_dir = GameDir::new(game_dir);
+    let profile = resolve_profile(&game_dir, &version_id)
+        .await
+        .map_err(AppError::from)?;
+    let libraries = resolve_libraries(
+        &profile.libraries,
+        &Platform::current(),
+        &Default::default(),
+    )
+    .map_err(AppError::from)?;
+
+    build_launch_command(
+        &game_dir,
+        &profile,
+        &libraries,
+        Path::new(&natives_dir),
+        &account,
+        &instance,
+        &settings,
+    )
+    .map_err(AppError::from)
+}
+
+// 证书信任命令
+#[tauri::command]
+async fn get_trust_config(state: State<'_, HttpClientState>) -> Result<TrustConfig, AppError> {
+    Ok(state.inner().client.shared().trust())
+}
+
+#[tauri::command]
+async fn set_trust_config(
+    config: TrustConfig,
+    app: AppHandle,
+    state: State<'_, HttpClientState>,
+) -> Result<(), AppError> {
+    state
+        .inner()
+        .client
+        .shared()
+        .set_trust(config.clone())
+        .map_err(AppError::from)?;
+
+    // 持久化保存
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    store.set(
+        TRUST_SETTINGS_KEY,
+        serde_json::to_value(&config).map_err(AppError::from)?,
+    );
+    store.save().map_err(AppError::from)
+}
+
+// 流量记录命令
+#[tauri::command]
+async fn get_recorder_config(
+    state: State<'_, HttpClientState>,
+) -> Result<RecorderConfig, AppError> {
+    Ok(state.inner().client.shared().recorder().config())
+}
+
+#[tauri::command]
+async fn set_recorder_config(
+    config: RecorderConfig,
+    app: AppHandle,
+    state: State<'_, HttpClientState>,
+) -> Result<(), AppError> {
+    state
+        .inner()
+        .client
+        .shared()
+        .recorder()
+        .set_config(config.clone())
+        .map_err(AppError::from)?;
+
+    // 持久化保存
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    store.set(
+        RECORDER_SETTINGS_KEY,
+        serde_json::to_value(&config).map_err(AppError::from)?,
+    );
+    store.save().map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn clear_traffic_records(state: State<'_, HttpClientState>) -> Result<(), AppError> {
+    state.inner().client.shared().recorder().clear();
+    Ok(())
+}
+
+/// 将记录的流量导出为HAR文件
+///
+/// 请求体和响应体不做隐藏，携带Authorization的请求不记录请求体和响应体。
+#[tauri::command]
+async fn export_har(path: String, state: State<'_, HttpClientState>) -> Result<(), AppError> {
+    let har = state.inner().client.shared().recorder().to_har();
+    let content = serde_json::to_vec_pretty(&har).map_err(AppError::from)?;
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| AppError::from(NetworkError::IoError(e)))
+}
+
+/// 诊断网络连接，未传入配置时检查默认的官方地址和镜像
+#[tauri::command]
+async fn run_network_diagnostics(
+    config: Option<DiagnosticsConfig>,
+    state: State<'_, HttpClientState>,
+) -> Result<DiagnosticsReport, AppError> {
+    state
+        .inner()
+        .client
+        .diagnose(&config.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
+}
+
+/// 从设置存储中加载HTTP客户端、代理、证书信任、镜像和流量记录配置，并应用到共享客户端
+///
+/// 各项设置分别加载，某一项无效时只有该项使用默认值。
+fn load_network_settings(app: &AppHandle) -> Result<(), AppError> {
+    let store = app.store(SETTINGS_STORE).map_err(AppError::from)?;
+    let shared = app.state::<HttpClientState>().client.shared().clone();
+
+    apply_setting(HTTP_SETTINGS_KEY, store.get(HTTP_SETTINGS_KEY), |config| {
+        shared.set_config(config)
+    });
+    apply_setting(
+        PROXY_SETTINGS_KEY,
+        store.get(PROXY_SETTINGS_KEY),
+        |config| shared.set_proxy(config),
+    );
+    apply_setting(
+        TRUST_SETTINGS_KEY,
+        store.get(TRUST_SETTINGS_KEY),
+        |config| shared.set_trust(config),
+    );
+    apply_setting(
+        MIRROR_SETTINGS_KEY,
+        store.get(MIRROR_SETTINGS_KEY),
+        |config| shared.set_mirrors(config),
+    );
+    apply_setting(
+        RECORDER_SETTINGS_KEY,
+        store.get(RECORDER_SETTINGS_KEY),
+        |config| shared.recorder().set_config(config),
+    );
+
+    Ok(())
+}
+
+/// 解析并应用一项已保存的设置，失败时输出错误，该项保持默认值
+fn apply_setting<T, E>(
+    key: &str,
+    value: Option<serde_json::Value>,
+    apply: impl FnOnce(T) -> Result<(), E>,
+) -> bool
+where
+    T: serde::de::DeserializeOwned,
+    AppError: From<E>,
+{
+    let Some(value) = value else {
+        return false;
+    };
+    let result = serde_json::from_value::<T>(value)
+        .map_err(<AppError as From<serde_json::Error>>::from)
+        .and_then(|config| apply(config).map_err(AppError::from));
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("加载设置{}失败，使用默认值: {}", key, e);
+            false
+        }
+    }
+}
+
+/// 恢复上次退出时未完成的下载任务
+fn restore_downloads(app: &AppHandle) -> Result<(), AppError> {
+    let checkpoint_path = app
+        .path()
+        .app_data_dir()
+        .map_err(AppError::from)?
+        .join(DOWNLOAD_CHECKPOINT_FILE);
+
+    let manager = &app.state::<DownloadManagerState>().manager;
+    tauri::async_runtime::block_on(manager.restore(checkpoint_path))
+        .map(|_| ())
+        .map_err(AppError::from)
+}
+
+/// 退出前暂停所有下载并保存断点，最多等待 SHUTDOWN_TIMEOUT
+fn shutdown_downloads(app: &AppHandle) {
+    let manager = app.state::<DownloadManagerState>().manager.clone();
+
+    tauri::async_runtime::block_on(async move {
+        let result = tokio::time::timeout(SHUTDOWN_TIMEOUT, manager.shutdown()).await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("关闭下载管理器失败: {}", e),
+            Err(_) => {
+                eprintln!("等待下载暂停超时，尝试直接保存断点信息");
+                // 超时后按当前记录的分段进度保存，分段进度不会超过已写入的数据
+                match tokio::time::timeout(SAVE_CHECKPOINT_TIMEOUT, manager.save_checkpoints())
+                    .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => eprintln!("保存断点信息失败: {}", e),
+                    Err(_) => eprintln!("保存断点信息超时"),
+                }
+            }
+        }
+    });
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 创建共享的HTTP客户端，HTTP请求和下载共用同一个连接池
+    let shared_client = SharedClient::new();
+
     // 创建下载管理器
-    let download_manager = DownloadManager::new(5); // 最大5个并发下载
+    let download_manager = DownloadManager::new(5, shared_client.clone()); // 最大5个并发下载
     let download_manager_state = DownloadManagerState {
-        manager: Arc::new(Mutex::new(download_manager)),
-    };
-
-    // 创建HTTP客户端
-    let http_client = HttpClient::new();
-    let http_client_state = HttpClientState {
-        client: Arc::new(Mutex::new(http_client)),
+        manager: download_manager,
     };
 
     tauri::Builder::default()
@@ -152,7 +773,33 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .manage(download_manager_state)
-        .manage(http_client_state)
+        .setup(move |app| {
+            // 创建HTTP客户端，缓存目录在应用启动后才能确定
+            let mut http_client = HttpClient::new(shared_client);
+            match app.path().app_cache_dir() {
+                Ok(dir) => {
+                    http_client = http_client.with_cache(HttpCache::new(dir.join(HTTP_CACHE_DIR)))
+                }
+                Err(e) => eprintln!("获取缓存目录失败，HTTP缓存不可用: {}", e),
+            }
+            app.manage(MinecraftState {
+                manifest: VersionManifestService::new(http_client.clone()),
+            });
+            app.manage(HttpClientState {
+                client: http_client,
+                requests: CancelRegistry::default(),
+                streams: StreamAcks::default(),
+            });
+
+            // 网络配置加载失败时使用默认设置，不阻止应用启动
+            if let Err(e) = load_network_settings(app.handle()) {
+                eprintln!("加载网络配置失败: {}", e);
+            }
+            if let Err(e) = restore_downloads(app.handle()) {
+                eprintln!("恢复下载任务失败: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             start_download,
             pause_download,
@@ -161,8 +808,77 @@ pub fn run() {
             get_download_progress,
             get_all_downloads,
             http_get,
-            http_post_json
+            http_post_json,
+            http_request,
+            cancel_request,
+            http_post_multipart,
+            http_stream,
+            ack_stream,
+            get_proxy_config,
+            set_proxy_config,
+            get_http_config,
+            set_http_config,
+            get_mirror_config,
+            set_mirror_config,
+            run_network_diagnostics,
+            get_minecraft_versions,
+            get_minecraft_version,
+            get_version_profile,
+            get_version_libraries,
+            install_version_assets,
+            install_minecraft_version,
+            cancel_install,
+            extract_version_natives,
+            get_launch_command,
+            get_trust_config,
+            set_trust_config,
+            get_recorder_config,
+            set_recorder_config,
+            clear_traffic_records,
+            export_har
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                shutdown_downloads(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use network::{NetworkError, SharedClient};
+
+    #[test]
+    fn applies_settings_independently() {
+        let shared = SharedClient::new();
+        let invalid = serde_json::json!({ "mode": "unknown" });
+        assert!(!apply_setting(
+            PROXY_SETTINGS_KEY,
+            Some(invalid),
+            |config| { shared.set_proxy(config) }
+        ));
+        assert_eq!(shared.proxy(), ProxyConfig::default());
+
+        let rejected = serde_json::json!({ "capacity": 0 });
+        assert!(!apply_setting(
+            RECORDER_SETTINGS_KEY,
+            Some(rejected),
+            |config| shared.recorder().set_config(config)
+        ));
+
+        let mirrors = serde_json::json!({});
+        assert!(apply_setting(
+            MIRROR_SETTINGS_KEY,
+            Some(mirrors),
+            |config| { shared.set_mirrors(config) }
+        ));
+        assert!(!apply_setting(
+            HTTP_SETTINGS_KEY,
+            None,
+            |_: HttpClientConfig| { Err::<(), _>(NetworkError::Cancelled) }
+        ));
+    }
 }
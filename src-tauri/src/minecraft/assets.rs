@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::download::{verify_file, BatchItem, BatchProgress, DownloadManager};
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::paths::GameDir;
+use crate::minecraft::profile::AssetIndexInfo;
+
+/// 官方资源文件地址，启用镜像时会按镜像规则改写
+const RESOURCES_URL: &str = "https://resources.download.minecraft.net";
+
+/// 资源索引中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetObject {
+    /// SHA-1，同时决定文件在`objects`中的位置
+    pub hash: String,
+    /// 文件大小
+    pub size: u64,
+}
+
+impl AssetObject {
+    /// 检查SHA-1格式，避免拼接出异常的路径
+    fn validate(&self) -> Result<(), MinecraftError> {
+        if self.hash.len() != 40 || !self.hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "无效的资源文件SHA-1: {}",
+                self.hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// 下载地址：`<hh>/<hash>`
+    fn url(&self) -> String {
+        format!("{}/{}/{}", RESOURCES_URL, &self.hash[..2], self.hash)
+    }
+}
+
+/// 资源索引（`assets/indexes/<id>.json`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetIndex {
+    /// 资源名称到文件的映射
+    #[serde(default)]
+    pub objects: BTreeMap<String, AssetObject>,
+    /// 是否需要按名称复制到游戏目录的`resources`中（1.6之前的版本）
+    #[serde(default)]
+    pub map_to_resources: bool,
+    /// 是否需要按名称复制到`assets/virtual/<id>`中（1.7.2之前的版本）
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+}
+
+/// 下载版本的资源索引和所有资源文件
+///
+/// 资源文件保存在`assets/objects`中并按SHA-1校验，已存在的文件会被跳过；
+/// 旧版本的资源索引还会把文件按名称复制到对应的目录，
+/// 其中最早的版本直接读取游戏运行目录中的`resources_dir`。
+pub async fn install_assets(
+    downloads: &DownloadManager,
+    game_dir: &GameDir,
+    resources_dir: &Path,
+    info: &AssetIndexInfo,
+    on_progress: impl Fn(BatchProgress),
+) -> Result<BatchProgress, MinecraftError> {
+    let index = download_index(downloads, game_dir, info).await?;
+    let items = object_items(game_dir, &index)?;
+    let progress = downloads.download_batch(items, on_progress).await?;
+
+    let legacy_dir = if index.map_to_resources {
+        Some(resources_dir.to_path_buf())
+    } else if index.is_virtual {
+        Some(game_dir.virtual_assets_dir(&info.id))
+    } else {
+        None
+    };
+    if let Some(dir) = legacy_dir {
+        copy_legacy_assets(game_dir, &index, &dir).await?;
+    }
+
+    Ok(progress)
+}
+
+/// 资源文件的下载项，多个名称对应同一个文件时只下载一次
+fn object_items(game_dir: &GameDir, index: &AssetIndex) -> Result<Vec<BatchItem>, MinecraftError> {
+    let mut hashes = HashSet::new();
+    let mut items = Vec::new();
+    for object in index.objects.values() {
+        object.validate()?;
+        if hashes.insert(object.hash.as_str()) {
+            items.push(BatchItem {
+                url: object.url(),
+                path: game_dir.asset_object(&object.hash),
+                size: Some(object.size),
+                sha1: Some(object.hash.clone()),
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// 下载并读取资源索引，已存在且校验通过时直接读取
+async fn download_index(
+    downloads: &DownloadManager,
+    game_dir: &GameDir,
+    info: &AssetIndexInfo,
+) -> Result<AssetIndex, MinecraftError> {
+    let path = game_dir.asset_index(&info.id);
+    let item = BatchItem {
+        url: info.url.clone(),
+        path: path.clone(),
+        size: Some(info.size).filter(|size| *size > 0),
+        sha1: Some(info.sha1.clone()),
+    };
+    downloads.download_batch(vec![item], |_| {}).await?;
+
+    let content = tokio::fs::read(&path).await?;
+    serde_json::from_slice(&content)
+        .map_err(|e| MinecraftError::ParseError(format!("{}: {}", path.display(), e)))
+}
+
+/// 将资源文件按名称复制到`dir`中，内容一致的文件会被跳过
+async fn copy_legacy_assets(
+    game_dir: &GameDir,
+    index: &AssetIndex,
+    dir: &Path,
+) -> Result<(), MinecraftError> {
+    for (name, object) in &index.objects {
+        let target = legacy_path(dir, name)?;
+        if verify_file(&target, Some(object.size), Some(&object.hash)).await? {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::copy(game_dir.asset_object(&object.hash), &target).await?;
+    }
+    Ok(())
+}
+
+/// 资源名称对应的路径，名称不能跳出目标目录
+fn legacy_path(dir: &Path, name: &str) -> Result<PathBuf, MinecraftError> {
+    let relative = Path::new(name);
+    let is_safe = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !is_safe {
+        return Err(MinecraftError::InvalidProfile(format!(
+            "无效的资源名称: {}",
+            name
+        )));
+    }
+    Ok(dir.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("novacl-assets-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn object(hash: &str, size: u64) -> AssetObject {
+        AssetObject {
+            hash: hash.to_string(),
+            size,
+        }
+    }
+
+    fn asset_index(objects: &[(&str, AssetObject)], map_to_resources: bool) -> AssetIndex {
+        AssetIndex {
+            objects: objects
+                .iter()
+                .map(|(name, object)| (name.to_string(), object.clone()))
+                .collect(),
+            map_to_resources,
+            is_virtual: !map_to_resources,
+        }
+    }
+
+    /// 在资源文件目录中放入一个文件，返回对应的索引项
+    fn store_object(game_dir: &GameDir, content: &[u8]) -> AssetObject {
+        let hash = format!("{:x}", Sha1::digest(content));
+        let path = game_dir.asset_object(&hash);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        object(&hash, content.len() as u64)
+    }
+
+    #[test]
+    fn validates_object_hashes() {
+        assert!(object("b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356", 1)
+            .validate()
+            .is_ok());
+        for hash in [
+            "",
+            "b62ca8ec",
+            "b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e335",
+            "z62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356",
+            "../ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356",
+            "b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356a",
+        ] {
+            assert!(object(hash, 1).validate().is_err(), "{}", hash);
+        }
+    }
+
+    #[test]
+    fn rejects_names_outside_target_dir() {
+        let dir = Path::new("resources");
+        assert_eq!(
+            legacy_path(dir, "sound/step/grass1.ogg").unwrap(),
+            dir.join("sound/step/grass1.ogg")
+        );
+        for name in ["../x", "sound/../../x", "/etc/passwd", "./x"] {
+            assert!(legacy_path(dir, name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn downloads_shared_objects_once() {
+        let game_dir = GameDir::new("game");
+        let shared = object("b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356", 4);
+        let other = object("0a1b2c3d4e5f60718293a4b5c6d7e8f901234567", 8);
+        let index = asset_index(
+            &[
+                ("a.ogg", shared.clone()),
+                ("b.ogg", other.clone()),
+                ("c.ogg", shared.clone()),
+            ],
+            false,
+        );
+
+        let items = object_items(&game_dir, &index).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].url,
+            "https://resources.download.minecraft.net/b6/b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356"
+        );
+        assert_eq!(items[0].path, game_dir.asset_object(&shared.hash));
+        assert_eq!(items[1].sha1.as_deref(), Some(other.hash.as_str()));
+
+        let invalid = asset_index(&[("a.ogg", object("../../x", 1))], false);
+        assert!(object_items(&game_dir, &invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn copies_legacy_assets_by_name() {
+        let root = temp_dir();
+        let game_dir = GameDir::new(&root);
+        let grass = store_object(&game_dir, b"grass");
+        let music = store_object(&game_dir, b"music");
+        let index = asset_index(
+            &[
+                ("sound/step/grass1.ogg", grass.clone()),
+                ("sound/step/grass2.ogg", grass),
+                ("music/calm1.ogg", music),
+            ],
+            true,
+        );
+        let resources = root.join("instance").join("resources");
+
+        copy_legacy_assets(&game_dir, &index, &resources)
+            .await
+            .unwrap();
+        let grass2 = std::fs::read(resources.join("sound/step/grass2.ogg")).unwrap();
+        let calm = std::fs::read(resources.join("music/calm1.ogg")).unwrap();
+
+        // 已复制且内容一致的文件不会再从资源文件目录读取
+        std::fs::remove_dir_all(game_dir.assets_dir().join("objects")).unwrap();
+        let second = copy_legacy_assets(&game_dir, &index, &resources).await;
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(grass2, b"grass");
+        assert_eq!(calm, b"music");
+        assert!(second.is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::io;
+
+use crate::download::DownloadError;
+use crate::network::NetworkError;
+
+/// Minecraft版本相关的错误类型
+#[derive(Debug)]
+pub enum MinecraftError {
+    /// IO错误
+    IoError(io::Error),
+    /// 获取元数据时的网络错误
+    Network(NetworkError),
+    /// 下载文件失败
+    Download(DownloadError),
+    /// 无法解析版本列表或版本JSON
+    ParseError(String),
+    /// 版本不存在
+    VersionNotFound(String),
+    /// 版本JSON内容无效，如继承关系存在循环
+    InvalidProfile(String),
+    /// 启动设置无效，如最小内存大于最大内存
+    InvalidSettings(String),
+}
+
+impl fmt::Display for MinecraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IoError(err) => write!(f, "IO错误: {}", err),
+            Self::Network(err) => write!(f, "{}", err),
+            Self::Download(err) => write!(f, "{}", err),
+            Self::ParseError(err) => write!(f, "解析失败: {}", err),
+            Self::VersionNotFound(id) => write!(f, "版本不存在: {}", id),
+            Self::InvalidProfile(err) => write!(f, "版本JSON无效: {}", err),
+            Self::InvalidSettings(err) => write!(f, "启动设置无效: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MinecraftError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            Self::Network(err) => Some(err),
+            Self::Download(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MinecraftError {
+    fn from(err: io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+impl From<NetworkError> for MinecraftError {
+    fn from(err: NetworkError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<DownloadError> for MinecraftError {
+    fn from(err: DownloadError) -> Self {
+        Self::Download(err)
+    }
+}
+
+impl From<serde_json::Error> for MinecraftError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::ParseError(err.to_string())
+    }
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path};
+
+use crate::download::{verify_file, BatchItem, BatchProgress, DownloadManager};
+use crate::minecraft::assets::install_assets;
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::launch::LaunchInstance;
+use crate::minecraft::library::resolve_libraries;
+use crate::minecraft::manifest::VersionManifestService;
+use crate::minecraft::paths::GameDir;
+use crate::minecraft::profile::{resolve_profile, VersionProfile};
+use crate::minecraft::rule::Platform;
+
+/// 安装版本的阶段和进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "stage", content = "progress", rename_all = "snake_case")]
+pub enum InstallProgress {
+    /// 获取版本JSON并合并继承链
+    Resolving,
+    /// 下载客户端jar、依赖库和本地库
+    Libraries(BatchProgress),
+    /// 下载资源索引和资源文件
+    Assets(BatchProgress),
+    /// 下载日志配置
+    Finalizing,
+}
+
+/// 为实例安装版本运行所需的全部文件，返回合并后的版本JSON
+///
+/// 版本JSON、依赖库和资源文件保存在游戏目录中由各实例共用，
+/// 实例的运行目录会被创建，最早的版本使用的资源文件复制到其中。
+/// 本地缺少的版本JSON按版本列表下载，其他文件按SHA-1校验，已存在且校验通过的文件会被跳过，
+/// 因此可以重复执行；中途取消时已下载的文件会保留，临时文件在下次执行时覆盖。
+pub async fn install_version(
+    downloads: &DownloadManager,
+    manifest: &VersionManifestService,
+    game_dir: &GameDir,
+    version_id: &str,
+    instance: &LaunchInstance,
+    on_progress: impl Fn(InstallProgress),
+) -> Result<VersionProfile, MinecraftError> {
+    on_progress(InstallProgress::Resolving);
+    tokio::fs::create_dir_all(&instance.game_directory).await?;
+    download_profiles(downloads, manifest, game_dir, version_id).await?;
+    let profile = resolve_profile(game_dir, version_id).await?;
+
+    let mut items = Vec::new();
+    let jar = profile.jar_id();
+    match profile.downloads.get("client") {
+        Some(client) => items.push(BatchItem {
+            url: client.url.clone(),
+            path: game_dir.client_jar(jar),
+            size: client.size,
+            sha1: client.sha1.clone(),
+        }),
+        None => require_local(&game_dir.client_jar(jar)).await?,
+    }
+    let libraries = resolve_libraries(&profile.libraries, &Platform::current(), &BTreeMap::new())?;
+    for library in libraries {
+        let path = game_dir.library(&library.path);
+        match library.url {
+            Some(url) => items.push(BatchItem {
+                url,
+                path,
+                size: library.size,
+                sha1: library.sha1,
+            }),
+            // 加载器安装时本地生成的依赖库无法下载
+            None => require_local(&path).await?,
+        }
+    }
+    on_progress(InstallProgress::Libraries(BatchProgress {
+        total: items.len(),
+        ..BatchProgress::default()
+    }));
+    downloads
+        .download_batch(items, |progress| {
+            on_progress(InstallProgress::Libraries(progress))
+        })
+        .await?;
+
+    if let Some(index) = &profile.asset_index {
+        let resources_dir = instance.resources_dir();
+        install_assets(downloads, game_dir, &resources_dir, index, |progress| {
+            on_progress(InstallProgress::Assets(progress))
+        })
+        .await?;
+    }
+
+    on_progress(InstallProgress::Finalizing);
+    if let Some(logging) = profile.logging.as_ref().and_then(|l| l.client.as_ref()) {
+        let file = &logging.file;
+        let is_file_name = matches!(
+            Path::new(&file.id).components().collect::<Vec<_>>()[..],
+            [Component::Normal(_)]
+        );
+        if !is_file_name {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "无效的日志配置文件名: {}",
+                file.id
+            )));
+        }
+        let item = BatchItem {
+            url: file.url.clone(),
+            path: game_dir.log_config(&file.id),
+            size: Some(file.size).filter(|size| *size > 0),
+            sha1: Some(file.sha1.clone()),
+        };
+        downloads.download_batch(vec![item], |_| {}).await?;
+    }
+
+    Ok(profile)
+}
+
+/// 下载继承链上本地缺少的版本JSON，已存在的版本JSON不会被覆盖
+async fn download_profiles(
+    downloads: &DownloadManager,
+    manifest: &VersionManifestService,
+    game_dir: &GameDir,
+    version_id: &str,
+) -> Result<(), MinecraftError> {
+    // 继承关系的循环和层级由`resolve_profile`检查，这里只需要保证能够结束
+    let mut visited = HashSet::new();
+    let mut next = Some(version_id.to_string());
+    while let Some(id) = next.take() {
+        if !visited.insert(id.clone()) {
+            break;
+        }
+        let path = game_dir.version_json(&id);
+        if !tokio::fs::try_exists(&path).await? {
+            let entry = manifest.version(&id).await?;
+            let item = BatchItem {
+                url: entry.url,
+                path,
+                size: None,
+                sha1: Some(entry.sha1),
+            };
+            downloads.download_batch(vec![item], |_| {}).await?;
+        }
+        next = VersionProfile::load(game_dir, &id).await?.inherits_from;
+    }
+    Ok(())
+}
+
+/// 没有下载地址的文件必须已经存在
+async fn require_local(path: &Path) -> Result<(), MinecraftError> {
+    if verify_file(path, None, None).await? {
+        return Ok(());
+    }
+    Err(MinecraftError::InvalidProfile(format!(
+        "文件不存在且没有下载地址: {}",
+        path.display()
+    )))
+}
@@ -0,0 +1,614 @@
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::library::LibraryFile;
+use crate::minecraft::paths::GameDir;
+use crate::minecraft::profile::{Argument, ArgumentValue, VersionProfile};
+use crate::minecraft::rule::{rules_allow, Platform};
+
+/// 启动参数中`${launcher_name}`的取值
+const LAUNCHER_NAME: &str = "NovaCL";
+
+/// 启动使用的账户
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchAccount {
+    /// 玩家名
+    pub name: String,
+    /// UUID，不含连字符
+    pub uuid: String,
+    /// 访问令牌，离线账户可以为任意值
+    pub access_token: String,
+    /// 账户类型：`msa`、`mojang`、`legacy`
+    pub user_type: String,
+    /// Xbox用户ID，只有微软账户有
+    #[serde(default)]
+    pub xuid: Option<String>,
+    /// 登录时使用的客户端ID
+    #[serde(default)]
+    pub client_id: Option<String>,
+}
+
+/// 安装和启动的游戏实例
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchInstance {
+    /// 游戏运行目录，开启版本隔离时为版本目录，否则为游戏目录
+    pub game_directory: PathBuf,
+}
+
+impl LaunchInstance {
+    /// 最早的版本直接读取的资源目录：`<运行目录>/resources`
+    pub fn resources_dir(&self) -> PathBuf {
+        self.game_directory.join("resources")
+    }
+}
+
+/// 启动设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchSettings {
+    /// Java可执行文件
+    pub java_path: String,
+    /// 最小内存（MB），不能大于最大内存
+    #[serde(default)]
+    pub min_memory: Option<u32>,
+    /// 最大内存（MB）
+    #[serde(default)]
+    pub max_memory: Option<u32>,
+    /// 额外的JVM参数，每一项为一个参数
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    /// 窗口宽度，和高度同时设置时生效
+    #[serde(default)]
+    pub window_width: Option<u32>,
+    /// 窗口高度
+    #[serde(default)]
+    pub window_height: Option<u32>,
+}
+
+/// 启动游戏的完整命令
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LaunchCommand {
+    /// Java可执行文件
+    pub program: String,
+    /// 参数，不需要再经过shell转义
+    pub args: Vec<String>,
+    /// 工作目录
+    pub working_dir: PathBuf,
+}
+
+/// 生成启动游戏的命令
+///
+/// `libraries`为[`resolve_libraries`](crate::minecraft::resolve_libraries)的结果，
+/// `natives_dir`为[`extract_natives`](crate::minecraft::extract_natives)解压的目录。
+/// 同时设置的最小内存大于最大内存时返回[`MinecraftError::InvalidSettings`]。
+/// 参数依次为内存设置、用户JVM参数、版本JVM参数、日志配置、主类和游戏参数；
+/// 新格式的参数按规则筛选，旧格式的`minecraftArguments`按空白分隔，
+/// 未知的占位符保持原样。
+pub fn build_launch_command(
+    game_dir: &GameDir,
+    profile: &VersionProfile,
+    libraries: &[LibraryFile],
+    natives_dir: &Path,
+    account: &LaunchAccount,
+    instance: &LaunchInstance,
+    settings: &LaunchSettings,
+) -> Result<LaunchCommand, MinecraftError> {
+    if let (Some(min), Some(max)) = (settings.min_memory, settings.max_memory) {
+        if min > max {
+            return Err(MinecraftError::InvalidSettings(format!(
+                "最小内存{}MB大于最大内存{}MB",
+                min, max
+            )));
+        }
+    }
+    let Some(main_class) = profile.main_class.clone() else {
+        return Err(MinecraftError::InvalidProfile(format!(
+            "版本没有主类: {}",
+            profile.id
+        )));
+    };
+    let platform = Platform::current();
+    let resolution = settings.window_width.zip(settings.window_height);
+    let features = BTreeMap::from([
+        ("is_demo_user".to_string(), false),
+        ("has_custom_resolution".to_string(), resolution.is_some()),
+    ]);
+    let variables = variables(
+        game_dir,
+        profile,
+        libraries,
+        natives_dir,
+        account,
+        instance,
+        settings,
+    );
+    let placeholder = Regex::new(r"\$\{([A-Za-z0-9_]+)\}")
+        .map_err(|e| MinecraftError::ParseError(format!("无效的占位符表达式: {}", e)))?;
+    let substitute = |arg: &str| {
+        placeholder
+            .replace_all(arg, |caps: &Captures| {
+                variables
+                    .get(&caps[1])
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    };
+
+    let mut args = Vec::new();
+    if let Some(min_memory) = settings.min_memory {
+        args.push(format!("-Xms{}m", min_memory));
+    }
+    if let Some(max_memory) = settings.max_memory {
+        args.push(format!("-Xmx{}m", max_memory));
+    }
+    args.extend(settings.jvm_args.iter().cloned());
+
+    let arguments = profile.arguments.as_ref();
+    match arguments.filter(|arguments| !arguments.jvm.is_empty()) {
+        Some(arguments) => args.extend(
+            evaluate(&arguments.jvm, &platform, &features)
+                .iter()
+                .map(|arg| substitute(arg)),
+        ),
+        None => args.extend(legacy_jvm_args(&platform).iter().map(|arg| substitute(arg))),
+    }
+
+    if let Some(logging) = profile.logging.as_ref().and_then(|l| l.client.as_ref()) {
+        let path = game_dir.log_config(&logging.file.id);
+        args.push(logging.argument.replace("${path}", &path.to_string_lossy()));
+    }
+
+    args.push(main_class);
+
+    match arguments.filter(|arguments| !arguments.game.is_empty()) {
+        Some(arguments) => args.extend(
+            evaluate(&arguments.game, &platform, &features)
+                .iter()
+                .map(|arg| substitute(arg)),
+        ),
+        None => {
+            let legacy = profile.minecraft_arguments.as_deref().unwrap_or_default();
+            args.extend(legacy.split_whitespace().map(substitute));
+            // 旧格式的参数中没有窗口大小
+            if let Some((width, height)) = resolution {
+                args.extend([
+                    "--width".to_string(),
+                    width.to_string(),
+                    "--height".to_string(),
+                    height.to_string(),
+                ]);
+            }
+        }
+    }
+
+    Ok(LaunchCommand {
+        program: settings.java_path.clone(),
+        args,
+        working_dir: instance.game_directory.clone(),
+    })
+}
+
+/// 按规则筛选新格式的参数，保持原有顺序
+fn evaluate<'a>(
+    arguments: &'a [Argument],
+    platform: &Platform,
+    features: &BTreeMap<String, bool>,
+) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    for argument in arguments {
+        let value = match argument {
+            Argument::Plain(value) => {
+                result.push(value.as_str());
+                continue;
+            }
+            Argument::Conditional { rules, value } if rules_allow(rules, platform, features) => {
+                value
+            }
+            Argument::Conditional { .. } => continue,
+        };
+        match value {
+            ArgumentValue::Single(value) => result.push(value.as_str()),
+            ArgumentValue::Multiple(values) => result.extend(values.iter().map(String::as_str)),
+        }
+    }
+    result
+}
+
+/// 旧版本JSON没有JVM参数，使用与官方启动器相同的默认参数
+fn legacy_jvm_args(platform: &Platform) -> Vec<&'static str> {
+    let mut args = Vec::new();
+    match platform.os {
+        "windows" => args.push(
+            "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump",
+        ),
+        "osx" => args.push("-XstartOnFirstThread"),
+        _ => {}
+    }
+    if platform.arch == "x86" {
+        args.push("-Xss1M");
+    }
+    args.extend([
+        "-Djava.library.path=${natives_directory}",
+        "-Dminecraft.launcher.brand=${launcher_name}",
+        "-Dminecraft.launcher.version=${launcher_version}",
+        "-cp",
+        "${classpath}",
+    ]);
+    args
+}
+
+/// 占位符的取值
+fn variables(
+    game_dir: &GameDir,
+    profile: &VersionProfile,
+    libraries: &[LibraryFile],
+    natives_dir: &Path,
+    account: &LaunchAccount,
+    instance: &LaunchInstance,
+    settings: &LaunchSettings,
+) -> HashMap<&'static str, String> {
+    let path = |path: &Path| path.to_string_lossy().into_owned();
+    let separator = if cfg!(windows) { ";" } else { ":" };
+
+    // 依赖库按版本JSON中的顺序在前，游戏本体在最后
+    let classpath = libraries
+        .iter()
+        .filter(|library| library.classpath)
+        .map(|library| path(&game_dir.library(&library.path)))
+        .chain([path(&game_dir.client_jar(profile.jar_id()))])
+        .collect::<Vec<_>>()
+        .join(separator);
+
+    let assets_index = profile
+        .asset_index
+        .as_ref()
+        .map(|index| index.id.clone())
+        .or_else(|| profile.assets.clone())
+        .unwrap_or_else(|| "legacy".to_string());
+    let game_assets = match assets_index.as_str() {
+        "pre-1.6" => instance.resources_dir(),
+        "legacy" => game_dir.virtual_assets_dir("legacy"),
+        _ => game_dir.assets_dir(),
+    };
+
+    HashMap::from([
+        ("auth_player_name", account.name.clone()),
+        ("auth_uuid", account.uuid.clone()),
+        ("auth_access_token", account.access_token.clone()),
+        (
+            "auth_session",
+            format!("token:{}:{}", account.access_token, account.uuid),
+        ),
+        ("auth_xuid", account.xuid.clone().unwrap_or_default()),
+        ("clientid", account.client_id.clone().unwrap_or_default()),
+        ("user_type", account.user_type.clone()),
+        ("user_properties", "{}".to_string()),
+        ("version_name", profile.id.clone()),
+        (
+            "version_type",
+            profile
+                .version_type
+                .clone()
+                .unwrap_or_else(|| "release".to_string()),
+        ),
+        ("game_directory", path(&instance.game_directory)),
+        ("assets_root", path(&game_dir.assets_dir())),
+        ("game_assets", path(&game_assets)),
+        ("assets_index_name", assets_index),
+        ("natives_directory", path(natives_dir)),
+        ("library_directory", path(&game_dir.libraries_dir())),
+        ("classpath_separator", separator.to_string()),
+        ("classpath", classpath),
+        ("launcher_name", LAUNCHER_NAME.to_string()),
+        ("launcher_version", env!("CARGO_PKG_VERSION").to_string()),
+        (
+            "resolution_width",
+            settings.window_width.unwrap_or_default().to_string(),
+        ),
+        (
+            "resolution_height",
+            settings.window_height.unwrap_or_default().to_string(),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn game_dir() -> GameDir {
+        GameDir::new("/game")
+    }
+
+    fn account() -> LaunchAccount {
+        LaunchAccount {
+            name: "Steve".to_string(),
+            uuid: "0123456789abcdef0123456789abcdef".to_string(),
+            access_token: "token".to_string(),
+            user_type: "msa".to_string(),
+            xuid: None,
+            client_id: None,
+        }
+    }
+
+    fn instance() -> LaunchInstance {
+        LaunchInstance {
+            game_directory: PathBuf::from("/game/versions/test"),
+        }
+    }
+
+    fn settings() -> LaunchSettings {
+        LaunchSettings {
+            java_path: "java".to_string(),
+            min_memory: None,
+            max_memory: None,
+            jvm_args: Vec::new(),
+            window_width: None,
+            window_height: None,
+        }
+    }
+
+    fn library(path: &str, native: bool, classpath: bool) -> LibraryFile {
+        LibraryFile {
+            name: path.to_string(),
+            path: path.to_string(),
+            url: None,
+            size: None,
+            sha1: None,
+            native,
+            classpath,
+            extract: None,
+        }
+    }
+
+    fn modern_profile() -> VersionProfile {
+        serde_json::from_value(json!({
+            "id": "1.20.1",
+            "type": "release",
+            "mainClass": "net.minecraft.client.main.Main",
+            "assetIndex": { "id": "5", "url": "", "sha1": "" },
+            "arguments": {
+                "jvm": [
+                    "-Djava.library.path=${natives_directory}",
+                    {
+                        "rules": [{ "action": "allow", "os": { "name": "unknown" } }],
+                        "value": ["-XstartOnFirstThread"]
+                    },
+                    "-cp",
+                    "${classpath}"
+                ],
+                "game": [
+                    "--username", "${auth_player_name}",
+                    "--version", "${version_name}",
+                    "--assetIndex", "${assets_index_name}",
+                    "--extra", "${unknown_placeholder}",
+                    {
+                        "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                        "value": "--demo"
+                    },
+                    {
+                        "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+                        "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+                    }
+                ]
+            },
+            "logging": {
+                "client": {
+                    "argument": "-Dlog4j.configurationFile=${path}",
+                    "file": { "id": "client-1.12.xml", "url": "", "sha1": "" },
+                    "type": "log4j2-xml"
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn legacy_profile(assets: &str) -> VersionProfile {
+        serde_json::from_value(json!({
+            "id": "1.5.2",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "assets": assets,
+            "minecraftArguments": "${auth_player_name} ${auth_session} --gameDir ${game_directory} --assetsDir ${game_assets}",
+        }))
+        .unwrap()
+    }
+
+    fn build(
+        profile: &VersionProfile,
+        libraries: &[LibraryFile],
+        settings: &LaunchSettings,
+    ) -> Result<LaunchCommand, MinecraftError> {
+        build_launch_command(
+            &game_dir(),
+            profile,
+            libraries,
+            Path::new("/natives"),
+            &account(),
+            &instance(),
+            settings,
+        )
+    }
+
+    fn path(path: PathBuf) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    fn position(args: &[String], arg: &str) -> usize {
+        args.iter().position(|a| a == arg).unwrap()
+    }
+
+    #[test]
+    fn builds_modern_arguments() {
+        let libraries = [
+            library("a/a.jar", false, true),
+            library("b/b-natives.jar", true, false),
+            library("c/c.jar", false, true),
+        ];
+        let command = build(&modern_profile(), &libraries, &settings()).unwrap();
+        let args = &command.args;
+
+        assert_eq!(command.program, "java");
+        assert_eq!(command.working_dir, instance().game_directory);
+        assert_eq!(args[0], "-Djava.library.path=/natives");
+        assert!(!args.contains(&"-XstartOnFirstThread".to_string()));
+
+        // 本地库jar不在classpath中，游戏本体在最后
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let classpath = [
+            path(game_dir().library("a/a.jar")),
+            path(game_dir().library("c/c.jar")),
+            path(game_dir().client_jar("1.20.1")),
+        ]
+        .join(separator);
+        assert_eq!(args[position(args, "-cp") + 1], classpath);
+
+        let logging = format!(
+            "-Dlog4j.configurationFile={}",
+            path(game_dir().log_config("client-1.12.xml"))
+        );
+        let main_class = position(args, "net.minecraft.client.main.Main");
+        assert!(position(args, &logging) < main_class);
+        assert!(position(args, "-cp") < main_class);
+
+        assert_eq!(
+            args[main_class + 1..],
+            [
+                "--username",
+                "Steve",
+                "--version",
+                "1.20.1",
+                "--assetIndex",
+                "5",
+                "--extra",
+                "${unknown_placeholder}",
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_legacy_arguments() {
+        let libraries = [
+            library("lwjgl/lwjgl.jar", false, true),
+            library("lwjgl/lwjgl-platform-natives.jar", true, false),
+        ];
+        let command = build(&legacy_profile("legacy"), &libraries, &settings()).unwrap();
+        let args = &command.args;
+
+        assert!(args.contains(&"-Djava.library.path=/natives".to_string()));
+        assert!(args.contains(&"-Dminecraft.launcher.brand=NovaCL".to_string()));
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let classpath = [
+            path(game_dir().library("lwjgl/lwjgl.jar")),
+            path(game_dir().client_jar("1.5.2")),
+        ]
+        .join(separator);
+        assert_eq!(args[position(args, "-cp") + 1], classpath);
+
+        let main_class = position(args, "net.minecraft.launchwrapper.Launch");
+        assert_eq!(
+            args[main_class + 1..],
+            [
+                "Steve".to_string(),
+                "token:token:0123456789abcdef0123456789abcdef".to_string(),
+                "--gameDir".to_string(),
+                path(instance().game_directory),
+                "--assetsDir".to_string(),
+                path(game_dir().virtual_assets_dir("legacy")),
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_instance_resources_for_oldest_versions() {
+        let command = build(&legacy_profile("pre-1.6"), &[], &settings()).unwrap();
+        assert_eq!(command.args.last(), Some(&path(instance().resources_dir())));
+    }
+
+    #[test]
+    fn applies_memory_and_jvm_settings() {
+        let custom = LaunchSettings {
+            min_memory: Some(512),
+            max_memory: Some(4096),
+            jvm_args: vec!["-XX:+UseG1GC".to_string()],
+            ..settings()
+        };
+        let command = build(&modern_profile(), &[], &custom).unwrap();
+        assert_eq!(
+            command.args[..4],
+            [
+                "-Xms512m",
+                "-Xmx4096m",
+                "-XX:+UseG1GC",
+                "-Djava.library.path=/natives"
+            ]
+        );
+
+        let only_max = LaunchSettings {
+            max_memory: Some(2048),
+            ..settings()
+        };
+        let command = build(&modern_profile(), &[], &only_max).unwrap();
+        assert_eq!(command.args[0], "-Xmx2048m");
+        assert!(!command.args.iter().any(|arg| arg.starts_with("-Xms")));
+    }
+
+    #[test]
+    fn rejects_min_memory_above_max() {
+        let invalid = LaunchSettings {
+            min_memory: Some(4096),
+            max_memory: Some(2048),
+            ..settings()
+        };
+        assert!(matches!(
+            build(&modern_profile(), &[], &invalid),
+            Err(MinecraftError::InvalidSettings(_))
+        ));
+
+        let equal = LaunchSettings {
+            min_memory: Some(2048),
+            max_memory: Some(2048),
+            ..settings()
+        };
+        assert!(build(&modern_profile(), &[], &equal).is_ok());
+    }
+
+    #[test]
+    fn applies_window_size() {
+        let sized = LaunchSettings {
+            window_width: Some(854),
+            window_height: Some(480),
+            ..settings()
+        };
+        let resolution = ["--width", "854", "--height", "480"];
+
+        let command = build(&modern_profile(), &[], &sized).unwrap();
+        assert!(command.args.ends_with(&resolution.map(String::from)));
+        assert!(!command.args.contains(&"--demo".to_string()));
+
+        // 旧格式的参数中没有窗口大小，追加在最后
+        let command = build(&legacy_profile("legacy"), &[], &sized).unwrap();
+        assert!(command.args.ends_with(&resolution.map(String::from)));
+
+        // 只设置宽度时不生效
+        let width_only = LaunchSettings {
+            window_width: Some(854),
+            ..settings()
+        };
+        let command = build(&modern_profile(), &[], &width_only).unwrap();
+        assert!(!command.args.contains(&"--width".to_string()));
+    }
+
+    #[test]
+    fn requires_main_class() {
+        let mut profile = modern_profile();
+        profile.main_class = None;
+        assert!(matches!(
+            build(&profile, &[], &settings()),
+            Err(MinecraftError::InvalidProfile(_))
+        ));
+    }
+}
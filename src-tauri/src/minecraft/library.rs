@@ -0,0 +1,326 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::maven::MavenCoordinate;
+use crate::minecraft::natives::native_classifier_matches;
+use crate::minecraft::profile::{ExtractRules, Library, LibraryArtifact};
+use crate::minecraft::rule::{rules_allow, Platform};
+
+/// 依赖库没有指定仓库地址时使用的Maven仓库，启用镜像时会按镜像规则改写
+const DEFAULT_LIBRARY_REPOSITORY: &str = "https://libraries.minecraft.net/";
+
+/// 需要下载的依赖库文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LibraryFile {
+    /// Maven坐标
+    pub name: String,
+    /// 相对于`libraries`目录的路径
+    pub path: String,
+    /// 下载地址，加载器安装时本地生成的依赖库为`None`
+    pub url: Option<String>,
+    /// 文件大小
+    pub size: Option<u64>,
+    /// SHA-1
+    pub sha1: Option<String>,
+    /// 是否为需要解压的本地库
+    pub native: bool,
+    /// 是否加入classpath，旧版本`natives`分类器对应的本地库jar不加入
+    pub classpath: bool,
+    /// 解压本地库时的排除规则
+    pub extract: Option<ExtractRules>,
+}
+
+impl LibraryFile {
+    /// 使用版本JSON中给出的下载信息，没有路径时按坐标生成
+    fn from_artifact(name: &str, artifact: &LibraryArtifact, coordinate: &MavenCoordinate) -> Self {
+        Self {
+            name: name.to_string(),
+            path: artifact.path.clone().unwrap_or_else(|| coordinate.path()),
+            url: Some(artifact.url.clone()).filter(|url| !url.is_empty()),
+            size: artifact.size,
+            sha1: artifact.sha1.clone(),
+            native: false,
+            classpath: true,
+            extract: None,
+        }
+    }
+
+    /// 按坐标在Maven仓库中拼接下载地址
+    fn from_repository(name: &str, repository: Option<&str>, coordinate: &MavenCoordinate) -> Self {
+        let repository = repository.unwrap_or(DEFAULT_LIBRARY_REPOSITORY);
+        let path = coordinate.path();
+        Self {
+            name: name.to_string(),
+            url: Some(format!("{}/{}", repository.trim_end_matches('/'), path)),
+            path,
+            size: None,
+            sha1: None,
+            native: false,
+            classpath: true,
+            extract: None,
+        }
+    }
+}
+
+/// 按当前平台筛选依赖库，并生成需要下载的文件列表
+///
+/// 结果保持版本JSON中的顺序，路径相同的文件只保留第一个。
+pub fn resolve_libraries(
+    libraries: &[Library],
+    platform: &Platform,
+    features: &BTreeMap<String, bool>,
+) -> Result<Vec<LibraryFile>, MinecraftError> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+
+    for library in libraries {
+        if !rules_allow(
+            library.rules.as_deref().unwrap_or_default(),
+            platform,
+            features,
+        ) {
+            continue;
+        }
+
+        let coordinate = MavenCoordinate::parse(&library.name)?;
+        let candidates = [
+            artifact_file(library, &coordinate, platform),
+            native_file(library, &coordinate, platform),
+        ];
+        for file in candidates.into_iter().flatten() {
+            if seen.insert(file.path.clone()) {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// 依赖库本身的文件
+///
+/// 只包含本地库的旧版本依赖库，以及不适用于当前平台的新版本本地库返回`None`。
+fn artifact_file(
+    library: &Library,
+    coordinate: &MavenCoordinate,
+    platform: &Platform,
+) -> Option<LibraryFile> {
+    // 新版本的本地库是带`natives-<os>[-<arch>]`分类器的普通依赖库，同一系统的各架构共用规则
+    let native = match coordinate.classifier.as_deref() {
+        Some(classifier) => match native_classifier_matches(classifier, platform) {
+            Some(false) => return None,
+            Some(true) => true,
+            None => false,
+        },
+        None => false,
+    };
+
+    let downloads = library.downloads.as_ref();
+    let mut file =
+        if let Some(artifact) = downloads.and_then(|downloads| downloads.artifact.as_ref()) {
+            LibraryFile::from_artifact(&library.name, artifact, coordinate)
+        } else if downloads.is_some() || library.natives.is_some() {
+            // 有下载信息但没有构件本身时，依赖库只提供本地库
+            return None;
+        } else {
+            let mut file =
+                LibraryFile::from_repository(&library.name, library.url.as_deref(), coordinate);
+            file.size = library.size;
+            file.sha1 = library.sha1.clone();
+            file
+        };
+    file.native = native;
+    Some(file)
+}
+
+/// 当前平台的本地库文件，没有对应分类器时返回`None`
+fn native_file(
+    library: &Library,
+    coordinate: &MavenCoordinate,
+    platform: &Platform,
+) -> Option<LibraryFile> {
+    let classifier = library
+        .natives
+        .as_ref()?
+        .get(platform.os)?
+        .replace("${arch}", platform.arch_bits());
+    let coordinate = coordinate.with_classifier(&classifier);
+
+    let artifact = library
+        .downloads
+        .as_ref()
+        .and_then(|downloads| downloads.classifiers.get(&classifier));
+    let mut file = match artifact {
+        Some(artifact) => LibraryFile::from_artifact(&library.name, artifact, &coordinate),
+        None => LibraryFile::from_repository(&library.name, library.url.as_deref(), &coordinate),
+    };
+    file.native = true;
+    file.classpath = false;
+    file.extract = library.extract.clone();
+    Some(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(os: &'static str, arch: &'static str) -> Platform {
+        Platform {
+            os,
+            arch,
+            os_version: None,
+        }
+    }
+
+    fn resolve(json: &str, platform: &Platform) -> Vec<LibraryFile> {
+        let libraries: Vec<Library> = serde_json::from_str(json).unwrap();
+        resolve_libraries(&libraries, platform, &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn resolves_legacy_natives_with_arch() {
+        let json = r#"[{
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "natives": { "windows": "natives-windows-${arch}", "linux": "natives-linux" },
+            "extract": { "exclude": ["META-INF/"] },
+            "downloads": {
+                "classifiers": {
+                    "natives-windows-64": {
+                        "path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-natives-windows-64.jar",
+                        "url": "https://libraries.minecraft.net/lwjgl-platform-2.9.4-natives-windows-64.jar",
+                        "sha1": "b84d5102b9dbfabfeb5e43c7e2828d98a7fc80f0",
+                        "size": 613748
+                    }
+                }
+            }
+        }]"#;
+
+        let files = resolve(json, &platform("windows", "x86_64"));
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert!(file.native);
+        assert!(!file.classpath);
+        assert!(file
+            .path
+            .ends_with("lwjgl-platform-2.9.4-natives-windows-64.jar"));
+        assert_eq!(
+            file.url.as_deref(),
+            Some("https://libraries.minecraft.net/lwjgl-platform-2.9.4-natives-windows-64.jar")
+        );
+        assert_eq!(file.size, Some(613748));
+        assert_eq!(file.extract.as_ref().unwrap().exclude, ["META-INF/"]);
+
+        // 下载信息中没有32位的分类器时按坐标拼接地址
+        let files = resolve(json, &platform("windows", "x86"));
+        assert_eq!(
+            files[0].url.as_deref(),
+            Some("https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-natives-windows-32.jar")
+        );
+
+        // 没有对应系统的分类器时不需要任何文件
+        assert!(resolve(json, &platform("osx", "x86_64")).is_empty());
+    }
+
+    #[test]
+    fn resolves_native_classifier_libraries() {
+        let json = r#"[
+            {
+                "name": "org.lwjgl:lwjgl:3.3.3",
+                "downloads": { "artifact": { "path": "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar", "url": "https://libraries.minecraft.net/lwjgl-3.3.3.jar" } }
+            },
+            {
+                "name": "org.lwjgl:lwjgl:3.3.3:natives-linux",
+                "downloads": { "artifact": { "path": "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.jar", "url": "https://libraries.minecraft.net/lwjgl-3.3.3-natives-linux.jar" } },
+                "rules": [{ "action": "allow", "os": { "name": "linux" } }]
+            },
+            {
+                "name": "org.lwjgl:lwjgl:3.3.3:natives-linux-arm64",
+                "downloads": { "artifact": { "path": "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux-arm64.jar", "url": "https://libraries.minecraft.net/lwjgl-3.3.3-natives-linux-arm64.jar" } },
+                "rules": [{ "action": "allow", "os": { "name": "linux" } }]
+            }
+        ]"#;
+
+        let files = resolve(json, &platform("linux", "x86_64"));
+        let names: Vec<_> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "org.lwjgl:lwjgl:3.3.3",
+                "org.lwjgl:lwjgl:3.3.3:natives-linux"
+            ]
+        );
+        assert!(!files[0].native);
+        assert!(files[1].native);
+        // 新版本的本地库jar同时加入classpath
+        assert!(files[1].classpath);
+
+        let files = resolve(json, &platform("linux", "arm64"));
+        assert_eq!(files[1].name, "org.lwjgl:lwjgl:3.3.3:natives-linux-arm64");
+        assert!(resolve(json, &platform("windows", "x86_64"))
+            .iter()
+            .all(|file| !file.native));
+    }
+
+    #[test]
+    fn builds_url_from_maven_repository() {
+        let json = r#"[
+            { "name": "net.fabricmc:fabric-loader:0.15.11", "url": "https://maven.fabricmc.net/" },
+            { "name": "com.mojang:brigadier:1.0.18" }
+        ]"#;
+
+        let files = resolve(json, &platform("linux", "x86_64"));
+        assert_eq!(
+            files[0].url.as_deref(),
+            Some("https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar")
+        );
+        assert_eq!(
+            files[0].path,
+            "net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar"
+        );
+        assert_eq!(
+            files[1].url.as_deref(),
+            Some(
+                "https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar"
+            )
+        );
+        assert!(files.iter().all(|file| file.classpath && !file.native));
+    }
+
+    #[test]
+    fn skips_libraries_disallowed_by_rules() {
+        let json = r#"[{
+            "name": "ca.weblite:java-objc-bridge:1.1",
+            "rules": [{ "action": "allow", "os": { "name": "osx" } }]
+        }, {
+            "name": "tv.twitch:twitch:6.5",
+            "rules": [{ "action": "allow" }, { "action": "disallow", "os": { "name": "osx" } }]
+        }]"#;
+
+        let files = resolve(json, &platform("osx", "arm64"));
+        let names: Vec<_> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["ca.weblite:java-objc-bridge:1.1"]);
+
+        let files = resolve(json, &platform("linux", "x86_64"));
+        let names: Vec<_> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, ["tv.twitch:twitch:6.5"]);
+    }
+
+    #[test]
+    fn keeps_libraries_without_download_url() {
+        // 加载器安装时本地生成的依赖库只有路径，没有下载地址
+        let json = r#"[{
+            "name": "net.minecraftforge:forge:1.20.1-47.2.0:client",
+            "downloads": { "artifact": { "path": "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar", "url": "", "sha1": "0a1b2c", "size": 12 } }
+        }]"#;
+
+        let files = resolve(json, &platform("linux", "x86_64"));
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].url, None);
+        assert_eq!(
+            files[0].path,
+            "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar"
+        );
+        assert_eq!(files[0].sha1.as_deref(), Some("0a1b2c"));
+    }
+}
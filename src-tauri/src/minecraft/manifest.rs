@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::minecraft::error::MinecraftError;
+use crate::network::{CachePolicy, HttpClient, RequestOptions};
+
+/// 官方版本列表地址，启用镜像时会按镜像规则改写
+pub const VERSION_MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+/// 在4月1日发布、但属于正式快照的版本
+const APRIL_FOOLS_EXCEPTIONS: &[&str] = &["21w13a"];
+
+/// 版本列表中的版本类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionType {
+    /// 正式版
+    Release,
+    /// 快照
+    Snapshot,
+    /// 远古Beta版
+    OldBeta,
+    /// 远古Alpha版
+    OldAlpha,
+}
+
+/// 版本的分类，愚人节版本单独分为一类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionCategory {
+    /// 正式版
+    Release,
+    /// 快照
+    Snapshot,
+    /// 远古Beta版
+    OldBeta,
+    /// 远古Alpha版
+    OldAlpha,
+    /// 愚人节版本
+    AprilFools,
+}
+
+/// 最新版本的ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatestVersions {
+    /// 最新正式版
+    pub release: String,
+    /// 最新快照
+    pub snapshot: String,
+}
+
+/// 版本列表中的一个版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionEntry {
+    /// 版本ID
+    pub id: String,
+    /// 版本类型
+    #[serde(rename = "type")]
+    pub version_type: VersionType,
+    /// 版本JSON的地址
+    pub url: String,
+    /// 最后更新时间（RFC 3339）
+    pub time: String,
+    /// 发布时间（RFC 3339）
+    pub release_time: String,
+    /// 版本JSON的SHA-1
+    pub sha1: String,
+    /// 是否支持安全聊天等新特性，旧版本为0
+    #[serde(default)]
+    pub compliance_level: u32,
+}
+
+impl VersionEntry {
+    /// 版本的分类，4月1日发布的版本视为愚人节版本
+    pub fn category(&self) -> VersionCategory {
+        if self.is_released_on_april_first() && !APRIL_FOOLS_EXCEPTIONS.contains(&self.id.as_str())
+        {
+            return VersionCategory::AprilFools;
+        }
+        match self.version_type {
+            VersionType::Release => VersionCategory::Release,
+            VersionType::Snapshot => VersionCategory::Snapshot,
+            VersionType::OldBeta => VersionCategory::OldBeta,
+            VersionType::OldAlpha => VersionCategory::OldAlpha,
+        }
+    }
+
+    /// 发布时间是否为4月1日
+    ///
+    /// 按时间字符串中的日期判断，版本列表中的时间均为UTC，因此结果与用户所在时区无关。
+    fn is_released_on_april_first(&self) -> bool {
+        self.release_time.get(4..10) == Some("-04-01")
+    }
+}
+
+/// 版本列表（version_manifest_v2.json）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionManifest {
+    /// 最新版本
+    pub latest: LatestVersions,
+    /// 所有版本，从新到旧排列
+    pub versions: Vec<VersionEntry>,
+}
+
+impl VersionManifest {
+    /// 按ID查找版本
+    pub fn get(&self, id: &str) -> Option<&VersionEntry> {
+        self.versions.iter().find(|version| version.id == id)
+    }
+
+    /// 按分类整理版本
+    pub fn classify(&self) -> VersionList {
+        let mut list = VersionList {
+            latest: self.latest.clone(),
+            releases: Vec::new(),
+            snapshots: Vec::new(),
+            old_beta: Vec::new(),
+            old_alpha: Vec::new(),
+            april_fools: Vec::new(),
+        };
+        for version in &self.versions {
+            let group = match version.category() {
+                VersionCategory::Release => &mut list.releases,
+                VersionCategory::Snapshot => &mut list.snapshots,
+                VersionCategory::OldBeta => &mut list.old_beta,
+                VersionCategory::OldAlpha => &mut list.old_alpha,
+                VersionCategory::AprilFools => &mut list.april_fools,
+            };
+            group.push(version.clone());
+        }
+        list
+    }
+}
+
+/// 按分类整理后的版本列表
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionList {
+    /// 最新版本
+    pub latest: LatestVersions,
+    /// 正式版
+    pub releases: Vec<VersionEntry>,
+    /// 快照
+    pub snapshots: Vec<VersionEntry>,
+    /// 远古Beta版
+    pub old_beta: Vec<VersionEntry>,
+    /// 远古Alpha版
+    pub old_alpha: Vec<VersionEntry>,
+    /// 愚人节版本
+    pub april_fools: Vec<VersionEntry>,
+}
+
+/// 版本列表服务
+///
+/// 所有模块通过同一个实例获取版本列表。版本列表按网络优先的策略保存在HTTP缓存中，
+/// 网络不可用时使用缓存的版本列表。
+#[derive(Debug, Clone)]
+pub struct VersionManifestService {
+    /// HTTP客户端
+    http: HttpClient,
+    /// 内存中的版本列表
+    current: Arc<RwLock<Option<Arc<VersionManifest>>>>,
+}
+
+impl VersionManifestService {
+    /// 创建版本列表服务
+    pub fn new(http: HttpClient) -> Self {
+        Self {
+            http,
+            current: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// 获取版本列表，`refresh`为`true`时忽略内存中的版本列表重新获取
+    pub async fn manifest(&self, refresh: bool) -> Result<Arc<VersionManifest>, MinecraftError> {
+        if !refresh {
+            if let Some(manifest) = self.current.read().await.as_ref() {
+                return Ok(manifest.clone());
+            }
+        }
+
+        let options = RequestOptions {
+            cache: CachePolicy::NetworkFirst,
+            ..RequestOptions::default()
+        };
+        let body = self.http.get_bytes(VERSION_MANIFEST_URL, &options).await?;
+        let manifest = Arc::new(serde_json::from_slice::<VersionManifest>(&body)?);
+        *self.current.write().await = Some(manifest.clone());
+        Ok(manifest)
+    }
+
+    /// 按ID查找版本
+    pub async fn version(&self, id: &str) -> Result<VersionEntry, MinecraftError> {
+        self.manifest(false)
+            .await?
+            .get(id)
+            .cloned()
+            .ok_or_else(|| MinecraftError::VersionNotFound(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, version_type: VersionType, release_time: &str) -> VersionEntry {
+        VersionEntry {
+            id: id.to_string(),
+            version_type,
+            url: String::new(),
+            time: release_time.to_string(),
+            release_time: release_time.to_string(),
+            sha1: String::new(),
+            compliance_level: 0,
+        }
+    }
+
+    #[test]
+    fn classifies_april_fools_by_utc_date() {
+        let cases = [
+            (
+                "24w14potato",
+                "2024-04-01T12:02:04+00:00",
+                VersionCategory::AprilFools,
+            ),
+            (
+                "2.0",
+                "2013-04-01T00:00:00+00:00",
+                VersionCategory::AprilFools,
+            ),
+            // UTC+8的本地时间为4月1日，但UTC日期为3月31日
+            (
+                "late",
+                "2024-03-31T20:00:00+00:00",
+                VersionCategory::Snapshot,
+            ),
+            (
+                "21w13a",
+                "2021-04-01T12:00:00+00:00",
+                VersionCategory::Snapshot,
+            ),
+            (
+                "1.20.5",
+                "2024-04-23T12:00:00+00:00",
+                VersionCategory::Snapshot,
+            ),
+        ];
+        for (id, release_time, expected) in cases {
+            let version = entry(id, VersionType::Snapshot, release_time);
+            assert_eq!(version.category(), expected, "{}", id);
+        }
+    }
+
+    #[test]
+    fn classifies_by_version_type() {
+        let manifest = VersionManifest {
+            latest: LatestVersions {
+                release: "1.21".to_string(),
+                snapshot: "24w14potato".to_string(),
+            },
+            versions: vec![
+                entry(
+                    "24w14potato",
+                    VersionType::Snapshot,
+                    "2024-04-01T12:00:00+00:00",
+                ),
+                entry("1.21", VersionType::Release, "2024-06-13T08:24:03+00:00"),
+                entry("b1.7.3", VersionType::OldBeta, "2011-07-07T22:00:00+00:00"),
+                entry("a1.0.4", VersionType::OldAlpha, "2010-07-09T22:00:00+00:00"),
+            ],
+        };
+        let list = manifest.classify();
+        let ids =
+            |versions: &[VersionEntry]| versions.iter().map(|v| v.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&list.releases), ["1.21"]);
+        assert!(list.snapshots.is_empty());
+        assert_eq!(ids(&list.old_beta), ["b1.7.3"]);
+        assert_eq!(ids(&list.old_alpha), ["a1.0.4"]);
+        assert_eq!(ids(&list.april_fools), ["24w14potato"]);
+        assert_eq!(
+            manifest.get("1.21").map(|v| v.version_type),
+            Some(VersionType::Release)
+        );
+        assert!(manifest.get("missing").is_none());
+    }
+}
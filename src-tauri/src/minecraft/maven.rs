@@ -0,0 +1,150 @@
+use crate::minecraft::error::MinecraftError;
+
+/// Maven坐标：`group:artifact:version[:classifier][@ext]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenCoordinate {
+    /// 组ID，如`org.lwjgl`
+    pub group: String,
+    /// 构件ID，如`lwjgl`
+    pub artifact: String,
+    /// 版本
+    pub version: String,
+    /// 分类器，如`natives-linux`
+    pub classifier: Option<String>,
+    /// 扩展名，默认为`jar`
+    pub extension: String,
+}
+
+impl MavenCoordinate {
+    /// 解析Maven坐标
+    pub fn parse(name: &str) -> Result<Self, MinecraftError> {
+        let (coordinate, extension) = match name.split_once('@') {
+            Some((coordinate, extension)) => (coordinate, extension),
+            None => (name, "jar"),
+        };
+
+        let parts: Vec<&str> = coordinate.split(':').collect();
+        let (group, artifact, version, classifier) = match parts[..] {
+            [group, artifact, version] => (group, artifact, version, None),
+            [group, artifact, version, classifier] => (group, artifact, version, Some(classifier)),
+            _ => {
+                return Err(MinecraftError::InvalidProfile(format!(
+                    "无效的Maven坐标: {}",
+                    name
+                )))
+            }
+        };
+        if [group, artifact, version, extension]
+            .iter()
+            .any(|part| part.is_empty())
+        {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "无效的Maven坐标: {}",
+                name
+            )));
+        }
+
+        Ok(Self {
+            group: group.to_string(),
+            artifact: artifact.to_string(),
+            version: version.to_string(),
+            classifier: classifier.map(str::to_string),
+            extension: extension.to_string(),
+        })
+    }
+
+    /// 替换分类器
+    pub fn with_classifier(&self, classifier: &str) -> Self {
+        Self {
+            classifier: Some(classifier.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// 仓库中的相对路径：`group/path/artifact/version/artifact-version[-classifier].ext`
+    pub fn path(&self) -> String {
+        let mut file_name = format!("{}-{}", self.artifact, self.version);
+        if let Some(classifier) = &self.classifier {
+            file_name.push('-');
+            file_name.push_str(classifier);
+        }
+        format!(
+            "{}/{}/{}/{}.{}",
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.version,
+            file_name,
+            self.extension
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_coordinates() {
+        let coordinate = MavenCoordinate::parse("org.lwjgl:lwjgl:3.3.3").unwrap();
+        assert_eq!(
+            coordinate,
+            MavenCoordinate {
+                group: "org.lwjgl".to_string(),
+                artifact: "lwjgl".to_string(),
+                version: "3.3.3".to_string(),
+                classifier: None,
+                extension: "jar".to_string(),
+            }
+        );
+
+        let coordinate = MavenCoordinate::parse("org.lwjgl:lwjgl:3.3.3:natives-linux").unwrap();
+        assert_eq!(coordinate.classifier.as_deref(), Some("natives-linux"));
+        assert_eq!(coordinate.extension, "jar");
+
+        let coordinate = MavenCoordinate::parse("de.oceanlabs.mcp:mcp_config:1.20.1@zip").unwrap();
+        assert_eq!(coordinate.version, "1.20.1");
+        assert_eq!(coordinate.extension, "zip");
+
+        let coordinate = MavenCoordinate::parse("net.minecraft:client:1.20.1:srg@txt").unwrap();
+        assert_eq!(coordinate.classifier.as_deref(), Some("srg"));
+        assert_eq!(coordinate.extension, "txt");
+    }
+
+    #[test]
+    fn rejects_invalid_coordinates() {
+        for name in [
+            "",
+            "org.lwjgl:lwjgl",
+            "a:b:c:d:e",
+            "org.lwjgl::3.3.3",
+            ":lwjgl:3.3.3",
+            "org.lwjgl:lwjgl:",
+            "org.lwjgl:lwjgl:3.3.3@",
+        ] {
+            assert!(
+                matches!(
+                    MavenCoordinate::parse(name),
+                    Err(MinecraftError::InvalidProfile(_))
+                ),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn builds_repository_paths() {
+        let coordinate = MavenCoordinate::parse("org.lwjgl:lwjgl:3.3.3").unwrap();
+        assert_eq!(coordinate.path(), "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3.jar");
+        assert_eq!(
+            coordinate.with_classifier("natives-windows").path(),
+            "org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-windows.jar"
+        );
+        assert_eq!(
+            MavenCoordinate::parse("de.oceanlabs.mcp:mcp_config:1.20.1@zip")
+                .unwrap()
+                .path(),
+            "de/oceanlabs/mcp/mcp_config/1.20.1/mcp_config-1.20.1.zip"
+        );
+    }
+}
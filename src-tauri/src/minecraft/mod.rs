@@ -0,0 +1,35 @@
+//! Minecraft模块
+//!
+//! 这个模块负责获取和解析Minecraft的版本元数据。
+//! 版本列表由[`VersionManifestService`]统一获取并缓存到磁盘，供其他模块共用；
+//! 版本JSON通过[`resolve_profile`]合并`inheritsFrom`继承链后使用，
+//! 其中的依赖库由[`resolve_libraries`]按当前平台筛选并转换为需要下载的文件，
+//! 资源文件由[`install_assets`]批量下载到`assets/objects`，
+//! 启动前由[`extract_natives`]将本地库解压到本次启动使用的目录。
+//! [`install_version`]将以上步骤组合为一次完整的版本安装，
+//! [`build_launch_command`]根据合并后的版本JSON、账户和设置生成启动命令。
+
+mod assets;
+mod error;
+mod install;
+mod launch;
+mod library;
+mod manifest;
+mod maven;
+mod natives;
+mod paths;
+mod profile;
+mod rule;
+
+pub use assets::install_assets;
+pub use error::MinecraftError;
+pub use install::{install_version, InstallProgress};
+pub use launch::{
+    build_launch_command, LaunchAccount, LaunchCommand, LaunchInstance, LaunchSettings,
+};
+pub use library::{resolve_libraries, LibraryFile};
+pub use manifest::{VersionEntry, VersionList, VersionManifestService};
+pub use natives::extract_natives;
+pub use paths::GameDir;
+pub use profile::{resolve_profile, VersionProfile};
+pub use rule::Platform;
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::library::LibraryFile;
+use crate::minecraft::paths::GameDir;
+use crate::minecraft::rule::Platform;
+
+/// 新版本本地库jar中需要提取的动态库扩展名
+const NATIVE_EXTENSIONS: [&str; 4] = ["dll", "so", "dylib", "jnilib"];
+/// 超过这个时间没有修改的本地库目录视为已不再使用
+const STALE_NATIVES_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 判断新版本本地库的分类器是否适用于当前平台
+///
+/// 分类器形如`natives-<os>[-<arch>]`，不带架构时为x86_64，
+/// 如`natives-linux`、`natives-windows-arm64`、`natives-macos-arm64`。
+/// 不是本地库分类器时返回`None`。
+pub(crate) fn native_classifier_matches(classifier: &str, platform: &Platform) -> Option<bool> {
+    let suffix = classifier.strip_prefix("natives-")?;
+    let (os, arch) = match suffix.split_once('-') {
+        Some((os, arch)) => (os, arch),
+        None => (suffix, "x86_64"),
+    };
+    let os = match os {
+        "macos" => "osx",
+        other => other,
+    };
+    let arch = match arch {
+        "arm32" => "arm",
+        other => other,
+    };
+    Some(os == platform.os && arch == platform.arch)
+}
+
+/// 将本地库解压到本次启动使用的目录中，返回该目录
+///
+/// 目录为`versions/<id>/natives/<uuid>`，每次启动各不相同，同一版本可以同时运行多个实例。
+/// 解压前会删除该版本超过一天没有修改的旧目录；Linux和macOS上正在使用的目录同样可以删除，
+/// 因此无法通过删除是否失败判断目录是否仍在使用，较新的目录一律保留。
+/// 旧版本的本地库jar按`extract.exclude`整体解压，
+/// 新版本的本地库jar同时在classpath中，只提取其中的动态库文件。
+pub async fn extract_natives(
+    game_dir: &GameDir,
+    version_id: &str,
+    libraries: &[LibraryFile],
+) -> Result<PathBuf, MinecraftError> {
+    let root = game_dir.natives_dir(version_id);
+    let jobs: Vec<(PathBuf, LibraryFile)> = libraries
+        .iter()
+        .filter(|library| library.native)
+        .map(|library| (game_dir.library(&library.path), library.clone()))
+        .collect();
+
+    tokio::task::spawn_blocking(move || {
+        remove_stale_natives(&root, STALE_NATIVES_AGE);
+        let dir = root.join(Uuid::new_v4().simple().to_string());
+        std::fs::create_dir_all(&dir)?;
+        for (jar, library) in &jobs {
+            extract_jar(jar, library, &dir)?;
+        }
+        Ok(dir)
+    })
+    .await
+    .map_err(|e| MinecraftError::IoError(io::Error::other(e)))?
+}
+
+/// 删除之前启动时留下、超过`max_age`没有修改的本地库目录，只处理以UUID命名的目录
+///
+/// 无法获取修改时间的目录会被保留。
+fn remove_stale_natives(root: &Path, max_age: Duration) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let is_launch_dir = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| Uuid::try_parse(name).is_ok());
+        let is_stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|age| age >= max_age));
+        if is_launch_dir && is_stale && entry.path().is_dir() {
+            if let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                eprintln!("删除旧的本地库目录失败: {}: {}", entry.path().display(), e);
+            }
+        }
+    }
+}
+
+/// 解压单个本地库jar
+fn extract_jar(jar: &Path, library: &LibraryFile, dir: &Path) -> Result<(), MinecraftError> {
+    let zip_error = |e: zip::result::ZipError| match e {
+        zip::result::ZipError::Io(e) => MinecraftError::IoError(e),
+        e => MinecraftError::ParseError(format!("{}: {}", jar.display(), e)),
+    };
+    let mut archive = ZipArchive::new(File::open(jar)?).map_err(zip_error)?;
+    let excludes = library
+        .extract
+        .as_ref()
+        .map(|extract| extract.exclude.as_slice())
+        .unwrap_or_default();
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(zip_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "本地库中包含不安全的路径: {}: {}",
+                jar.display(),
+                entry.name()
+            )));
+        };
+
+        let target = if library.classpath {
+            // 新版本的动态库位于`<os>/<arch>/...`中，提取到目录顶层
+            let is_native = name
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| NATIVE_EXTENSIONS.contains(&ext));
+            match name.file_name() {
+                Some(file_name) if is_native => dir.join(file_name),
+                _ => continue,
+            }
+        } else {
+            if excludes
+                .iter()
+                .any(|prefix| entry.name().starts_with(prefix.as_str()))
+            {
+                continue;
+            }
+            dir.join(name)
+        };
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(&target)?;
+        io::copy(&mut entry, &mut file)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("novacl-natives-{}", Uuid::new_v4()))
+    }
+
+    fn launch_dir(root: &Path) -> PathBuf {
+        let dir = root.join(Uuid::new_v4().simple().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("liblwjgl.so"), b"").unwrap();
+        dir
+    }
+
+    #[test]
+    fn keeps_recent_natives() {
+        let root = temp_root();
+        let dir = launch_dir(&root);
+
+        remove_stale_natives(&root, STALE_NATIVES_AGE);
+        assert!(dir.join("liblwjgl.so").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn removes_stale_launch_dirs_only() {
+        let root = temp_root();
+        let dir = launch_dir(&root);
+        let other = root.join("custom");
+        std::fs::create_dir_all(&other).unwrap();
+        let file = root.join(Uuid::new_v4().simple().to_string());
+        std::fs::write(&file, b"").unwrap();
+
+        remove_stale_natives(&root, Duration::ZERO);
+        assert!(!dir.exists());
+        assert!(other.exists());
+        assert!(file.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn ignores_missing_root() {
+        remove_stale_natives(&temp_root(), Duration::ZERO);
+    }
+
+    #[test]
+    fn matches_native_classifiers() {
+        let platform = |os, arch| Platform {
+            os,
+            arch,
+            os_version: None,
+        };
+        let linux = platform("linux", "x86_64");
+        let mac = platform("osx", "arm64");
+        assert_eq!(
+            native_classifier_matches("natives-linux", &linux),
+            Some(true)
+        );
+        assert_eq!(
+            native_classifier_matches("natives-linux-arm64", &linux),
+            Some(false)
+        );
+        assert_eq!(
+            native_classifier_matches("natives-macos-arm64", &mac),
+            Some(true)
+        );
+        assert_eq!(
+            native_classifier_matches("natives-macos", &mac),
+            Some(false)
+        );
+        assert_eq!(
+            native_classifier_matches("natives-windows-arm32", &platform("windows", "arm")),
+            Some(true)
+        );
+        assert_eq!(native_classifier_matches("sources", &linux), None);
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+/// 游戏目录（`.minecraft`）中各文件的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameDir {
+    /// 游戏目录
+    root: PathBuf,
+}
+
+impl GameDir {
+    /// 使用指定的游戏目录
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 版本目录：`versions/<id>`
+    pub fn version_dir(&self, id: &str) -> PathBuf {
+        self.root.join("versions").join(id)
+    }
+
+    /// 版本JSON：`versions/<id>/<id>.json`
+    pub fn version_json(&self, id: &str) -> PathBuf {
+        self.version_dir(id).join(format!("{}.json", id))
+    }
+
+    /// 游戏本体：`versions/<id>/<id>.jar`
+    pub fn client_jar(&self, id: &str) -> PathBuf {
+        self.version_dir(id).join(format!("{}.jar", id))
+    }
+
+    /// 本地库的解压目录：`versions/<id>/natives`
+    pub fn natives_dir(&self, id: &str) -> PathBuf {
+        self.version_dir(id).join("natives")
+    }
+
+    /// 依赖库目录：`libraries`
+    pub fn libraries_dir(&self) -> PathBuf {
+        self.root.join("libraries")
+    }
+
+    /// 依赖库文件：`libraries/<path>`
+    pub fn library(&self, path: &str) -> PathBuf {
+        self.libraries_dir().join(path)
+    }
+
+    /// 资源目录：`assets`
+    pub fn assets_dir(&self) -> PathBuf {
+        self.root.join("assets")
+    }
+
+    /// 资源索引：`assets/indexes/<id>.json`
+    pub fn asset_index(&self, id: &str) -> PathBuf {
+        self.assets_dir()
+            .join("indexes")
+            .join(format!("{}.json", id))
+    }
+
+    /// 资源文件：`assets/objects/<hh>/<hash>`，`hash`需要是有效的SHA-1
+    pub fn asset_object(&self, hash: &str) -> PathBuf {
+        self.assets_dir()
+            .join("objects")
+            .join(&hash[..2])
+            .join(hash)
+    }
+
+    /// 日志配置文件：`assets/log_configs/<id>`
+    pub fn log_config(&self, id: &str) -> PathBuf {
+        self.assets_dir().join("log_configs").join(id)
+    }
+
+    /// 旧版本按文件名存放的资源目录：`assets/virtual/<id>`
+    pub fn virtual_assets_dir(&self, id: &str) -> PathBuf {
+        self.assets_dir().join("virtual").join(id)
+    }
+}
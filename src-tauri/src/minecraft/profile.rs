@@ -0,0 +1,554 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::minecraft::error::MinecraftError;
+use crate::minecraft::paths::GameDir;
+use crate::minecraft::rule::Rule;
+
+/// 继承链的最大长度，防止异常的版本JSON导致无限读取
+const MAX_INHERITANCE_DEPTH: usize = 16;
+
+/// 带SHA-1和大小的下载文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadInfo {
+    /// 下载地址
+    pub url: String,
+    /// SHA-1
+    #[serde(default)]
+    pub sha1: Option<String>,
+    /// 文件大小
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// 依赖库中的文件，`path`为相对于`libraries`目录的路径
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryArtifact {
+    /// 相对于`libraries`目录的路径
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 下载地址，本地生成的文件为空
+    #[serde(default)]
+    pub url: String,
+    /// SHA-1
+    #[serde(default)]
+    pub sha1: Option<String>,
+    /// 文件大小
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// 依赖库的下载信息
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryDownloads {
+    /// 依赖库本身
+    #[serde(default)]
+    pub artifact: Option<LibraryArtifact>,
+    /// 按分类器区分的文件，旧版本的本地库在这里
+    #[serde(default)]
+    pub classifiers: BTreeMap<String, LibraryArtifact>,
+}
+
+/// 解压本地库时的排除规则
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractRules {
+    /// 排除的路径前缀，如`META-INF/`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// 依赖库
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Library {
+    /// Maven坐标：`group:artifact:version[:classifier][@ext]`
+    pub name: String,
+    /// 下载信息，加载器的依赖库可能没有
+    #[serde(default)]
+    pub downloads: Option<LibraryDownloads>,
+    /// Maven仓库地址，没有下载信息时按坐标拼接
+    #[serde(default)]
+    pub url: Option<String>,
+    /// SHA-1，部分加载器的依赖库写在这里
+    #[serde(default)]
+    pub sha1: Option<String>,
+    /// 文件大小，部分加载器的依赖库写在这里
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// 生效规则
+    #[serde(default)]
+    pub rules: Option<Vec<Rule>>,
+    /// 操作系统到本地库分类器的映射，分类器中可能含有`${arch}`
+    #[serde(default)]
+    pub natives: Option<BTreeMap<String, String>>,
+    /// 解压本地库时的排除规则
+    #[serde(default)]
+    pub extract: Option<ExtractRules>,
+}
+
+impl Library {
+    /// 用于判断依赖库是否相同的键：去掉版本号的坐标
+    ///
+    /// 子版本中的依赖库会替换父版本中键相同的依赖库。
+    pub fn key(&self) -> String {
+        let (coordinate, extension) = match self.name.split_once('@') {
+            Some((coordinate, extension)) => (coordinate, Some(extension)),
+            None => (self.name.as_str(), None),
+        };
+        let mut parts: Vec<&str> = coordinate.split(':').collect();
+        if parts.len() >= 3 {
+            parts.remove(2);
+        }
+        let mut key = parts.join(":");
+        if let Some(extension) = extension {
+            key.push('@');
+            key.push_str(extension);
+        }
+        key
+    }
+}
+
+/// 一个启动参数的值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    /// 单个参数
+    Single(String),
+    /// 多个参数
+    Multiple(Vec<String>),
+}
+
+/// 启动参数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    /// 总是使用的参数
+    Plain(String),
+    /// 满足规则时使用的参数
+    Conditional {
+        /// 生效规则
+        rules: Vec<Rule>,
+        /// 参数
+        value: ArgumentValue,
+    },
+}
+
+/// 新格式（1.13及以后）的启动参数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arguments {
+    /// 游戏参数
+    #[serde(default)]
+    pub game: Vec<Argument>,
+    /// JVM参数
+    #[serde(default)]
+    pub jvm: Vec<Argument>,
+}
+
+/// 资源索引
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetIndexInfo {
+    /// 索引ID，对应`assets/indexes/<id>.json`
+    pub id: String,
+    /// 下载地址
+    pub url: String,
+    /// SHA-1
+    pub sha1: String,
+    /// 索引文件大小
+    #[serde(default)]
+    pub size: u64,
+    /// 所有资源文件的总大小
+    #[serde(default)]
+    pub total_size: u64,
+}
+
+/// 需要的Java版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    /// 运行时组件名，如`java-runtime-gamma`
+    pub component: String,
+    /// 主版本号
+    pub major_version: u32,
+}
+
+/// 日志配置文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggingFile {
+    /// 文件名，保存在`assets/log_configs`中
+    pub id: String,
+    /// 下载地址
+    pub url: String,
+    /// SHA-1
+    pub sha1: String,
+    /// 文件大小
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// 日志配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// JVM参数，含有`${path}`占位符
+    pub argument: String,
+    /// 配置文件
+    pub file: LoggingFile,
+    /// 配置类型，如`log4j2-xml`
+    #[serde(rename = "type")]
+    pub config_type: String,
+}
+
+/// 各端的日志配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Logging {
+    /// 客户端
+    #[serde(default)]
+    pub client: Option<LoggingConfig>,
+}
+
+/// 版本JSON（`versions/<id>/<id>.json`）
+///
+/// 加载器的版本JSON通过`inheritsFrom`继承原版的版本JSON，只写出需要修改的部分，
+/// 使用前需要通过[`resolve_profile`]合并继承链。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionProfile {
+    /// 版本ID
+    pub id: String,
+    /// 继承的版本ID
+    #[serde(default)]
+    pub inherits_from: Option<String>,
+    /// 使用的游戏本体所属的版本ID，未设置时为继承链最顶层的版本
+    #[serde(default)]
+    pub jar: Option<String>,
+    /// 版本类型，如`release`
+    #[serde(default, rename = "type")]
+    pub version_type: Option<String>,
+    /// 最后更新时间
+    #[serde(default)]
+    pub time: Option<String>,
+    /// 发布时间
+    #[serde(default)]
+    pub release_time: Option<String>,
+    /// 主类
+    #[serde(default)]
+    pub main_class: Option<String>,
+    /// 新格式的启动参数
+    #[serde(default)]
+    pub arguments: Option<Arguments>,
+    /// 旧格式（1.12.2及以前）的游戏参数
+    #[serde(default)]
+    pub minecraft_arguments: Option<String>,
+    /// 依赖库
+    #[serde(default)]
+    pub libraries: Vec<Library>,
+    /// 资源索引
+    #[serde(default)]
+    pub asset_index: Option<AssetIndexInfo>,
+    /// 资源索引ID
+    #[serde(default)]
+    pub assets: Option<String>,
+    /// 游戏本体等下载文件，键为`client`、`server`、`client_mappings`等
+    #[serde(default)]
+    pub downloads: BTreeMap<String, DownloadInfo>,
+    /// 需要的Java版本
+    #[serde(default)]
+    pub java_version: Option<JavaVersion>,
+    /// 日志配置
+    #[serde(default)]
+    pub logging: Option<Logging>,
+}
+
+impl VersionProfile {
+    /// 游戏本体所属的版本ID，合并继承链后没有设置`jar`时为版本自身
+    pub fn jar_id(&self) -> &str {
+        self.jar.as_deref().unwrap_or(&self.id)
+    }
+
+    /// 读取游戏目录中的版本JSON，不合并继承链
+    pub async fn load(game_dir: &GameDir, id: &str) -> Result<Self, MinecraftError> {
+        let path = game_dir.version_json(id);
+        let content = match tokio::fs::read(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(MinecraftError::VersionNotFound(id.to_string()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&content)
+            .map_err(|e| MinecraftError::ParseError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// 将子版本合并到当前版本上，当前版本为父版本
+    ///
+    /// - 主类、旧格式参数、资源索引等单值字段由子版本替换
+    /// - 新格式参数追加在父版本之后
+    /// - 依赖库中子版本在前，父版本中被子版本替换的依赖库会被移除
+    fn merge(self, child: VersionProfile) -> VersionProfile {
+        let arguments = match (self.arguments, child.arguments) {
+            (Some(mut parent), Some(child)) => {
+                parent.game.extend(child.game);
+                parent.jvm.extend(child.jvm);
+                Some(parent)
+            }
+            (parent, child) => child.or(parent),
+        };
+
+        let overridden: HashSet<String> = child.libraries.iter().map(Library::key).collect();
+        let mut libraries = child.libraries;
+        libraries.extend(
+            self.libraries
+                .into_iter()
+                .filter(|library| !overridden.contains(&library.key())),
+        );
+
+        let mut downloads = self.downloads;
+        downloads.extend(child.downloads);
+
+        VersionProfile {
+            id: child.id,
+            inherits_from: None,
+            jar: child.jar.or(self.jar).or(Some(self.id)),
+            version_type: child.version_type.or(self.version_type),
+            time: child.time.or(self.time),
+            release_time: child.release_time.or(self.release_time),
+            main_class: child.main_class.or(self.main_class),
+            arguments,
+            minecraft_arguments: child.minecraft_arguments.or(self.minecraft_arguments),
+            libraries,
+            asset_index: child.asset_index.or(self.asset_index),
+            assets: child.assets.or(self.assets),
+            downloads,
+            java_version: child.java_version.or(self.java_version),
+            logging: child.logging.or(self.logging),
+        }
+    }
+}
+
+/// 读取版本JSON并合并整个继承链
+pub async fn resolve_profile(
+    game_dir: &GameDir,
+    id: &str,
+) -> Result<VersionProfile, MinecraftError> {
+    let profile = VersionProfile::load(game_dir, id).await?;
+    let mut visited = HashSet::from([id.to_string()]);
+    let mut parents = Vec::new();
+
+    let mut next = profile.inherits_from.clone();
+    while let Some(parent) = next {
+        if !visited.insert(parent.clone()) {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "版本继承关系存在循环: {}",
+                parent
+            )));
+        }
+        if parents.len() >= MAX_INHERITANCE_DEPTH {
+            return Err(MinecraftError::InvalidProfile(format!(
+                "版本继承层级过多: {}",
+                id
+            )));
+        }
+        let parent = VersionProfile::load(game_dir, &parent).await?;
+        next = parent.inherits_from.clone();
+        parents.push(parent);
+    }
+
+    // 从最顶层的父版本开始依次合并子版本
+    let Some(mut resolved) = parents.pop() else {
+        return Ok(profile);
+    };
+    while let Some(child) = parents.pop() {
+        resolved = resolved.merge(child);
+    }
+    Ok(resolved.merge(profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_root() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("novacl-game-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn write_profile(game_dir: &GameDir, profile: serde_json::Value) {
+        let id = profile["id"].as_str().unwrap();
+        let path = game_dir.version_json(id);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, profile.to_string()).unwrap();
+    }
+
+    fn library_names(profile: &VersionProfile) -> Vec<&str> {
+        profile
+            .libraries
+            .iter()
+            .map(|library| library.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn library_key_ignores_version() {
+        let library = |name: &str| Library {
+            name: name.to_string(),
+            downloads: None,
+            url: None,
+            sha1: None,
+            size: None,
+            rules: None,
+            natives: None,
+            extract: None,
+        };
+        assert_eq!(library("org.ow2.asm:asm:9.6").key(), "org.ow2.asm:asm");
+        assert_eq!(
+            library("org.lwjgl:lwjgl:3.3.3:natives-linux").key(),
+            "org.lwjgl:lwjgl:natives-linux"
+        );
+        assert_eq!(library("a:b:1.0@zip").key(), "a:b@zip");
+    }
+
+    #[tokio::test]
+    async fn merges_inheritance_chain() {
+        let root = temp_root();
+        let game_dir = GameDir::new(&root);
+        write_profile(
+            &game_dir,
+            json!({
+                "id": "1.20.1",
+                "type": "release",
+                "mainClass": "net.minecraft.client.main.Main",
+                "arguments": { "game": ["--username"], "jvm": ["-cp"] },
+                "libraries": [
+                    { "name": "org.ow2.asm:asm:9.3" },
+                    { "name": "com.google.guava:guava:31.1" },
+                ],
+                "assetIndex": { "id": "5", "url": "https://example.com/5.json", "sha1": "a" },
+                "downloads": {
+                    "client": { "url": "https://example.com/client.jar", "sha1": "b", "size": 1 },
+                },
+            }),
+        );
+        write_profile(
+            &game_dir,
+            json!({
+                "id": "fabric",
+                "inheritsFrom": "1.20.1",
+                "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+                "arguments": { "jvm": ["-DFabricMcEmu=net.minecraft.client.main.Main"] },
+                "libraries": [{ "name": "org.ow2.asm:asm:9.6" }],
+            }),
+        );
+        write_profile(
+            &game_dir,
+            json!({
+                "id": "modpack",
+                "inheritsFrom": "fabric",
+                "libraries": [{ "name": "net.fabricmc:fabric-loader:0.15.0" }],
+            }),
+        );
+
+        let profile = resolve_profile(&game_dir, "modpack").await.unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(profile.id, "modpack");
+        assert_eq!(profile.inherits_from, None);
+        // 游戏本体来自继承链最顶层的版本
+        assert_eq!(profile.jar_id(), "1.20.1");
+        assert_eq!(profile.version_type.as_deref(), Some("release"));
+        assert_eq!(
+            profile.main_class.as_deref(),
+            Some("net.fabricmc.loader.impl.launch.knot.KnotClient")
+        );
+        let arguments = profile.arguments.as_ref().unwrap();
+        assert_eq!(arguments.game, [Argument::Plain("--username".to_string())]);
+        assert_eq!(
+            arguments.jvm,
+            [
+                Argument::Plain("-cp".to_string()),
+                Argument::Plain("-DFabricMcEmu=net.minecraft.client.main.Main".to_string()),
+            ]
+        );
+        // 子版本的依赖库在前，被替换的旧版本依赖库被移除
+        assert_eq!(
+            library_names(&profile),
+            [
+                "net.fabricmc:fabric-loader:0.15.0",
+                "org.ow2.asm:asm:9.6",
+                "com.google.guava:guava:31.1",
+            ]
+        );
+        assert_eq!(
+            profile.asset_index.map(|index| index.id).as_deref(),
+            Some("5")
+        );
+        assert!(profile.downloads.contains_key("client"));
+    }
+
+    #[tokio::test]
+    async fn keeps_profile_without_parent() {
+        let root = temp_root();
+        let game_dir = GameDir::new(&root);
+        write_profile(
+            &game_dir,
+            json!({ "id": "1.8.9", "minecraftArguments": "--demo" }),
+        );
+
+        let profile = resolve_profile(&game_dir, "1.8.9").await.unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(profile.jar_id(), "1.8.9");
+        assert_eq!(profile.minecraft_arguments.as_deref(), Some("--demo"));
+    }
+
+    #[tokio::test]
+    async fn rejects_inheritance_cycles() {
+        let root = temp_root();
+        let game_dir = GameDir::new(&root);
+        write_profile(&game_dir, json!({ "id": "a", "inheritsFrom": "b" }));
+        write_profile(&game_dir, json!({ "id": "b", "inheritsFrom": "a" }));
+        write_profile(&game_dir, json!({ "id": "self", "inheritsFrom": "self" }));
+
+        for id in ["a", "self"] {
+            let result = resolve_profile(&game_dir, id).await;
+            assert!(
+                matches!(&result, Err(MinecraftError::InvalidProfile(message)) if message.contains("循环")),
+                "{:?}",
+                result
+            );
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn rejects_deep_inheritance() {
+        let root = temp_root();
+        let game_dir = GameDir::new(&root);
+        write_profile(&game_dir, json!({ "id": "v0" }));
+        for i in 1..=MAX_INHERITANCE_DEPTH + 1 {
+            write_profile(
+                &game_dir,
+                json!({ "id": format!("v{}", i), "inheritsFrom": format!("v{}", i - 1) }),
+            );
+        }
+
+        let deepest = format!("v{}", MAX_INHERITANCE_DEPTH + 1);
+        let result = resolve_profile(&game_dir, &deepest).await;
+        assert!(matches!(result, Err(MinecraftError::InvalidProfile(_))));
+        let allowed = format!("v{}", MAX_INHERITANCE_DEPTH);
+        assert!(resolve_profile(&game_dir, &allowed).await.is_ok());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn reports_missing_parent() {
+        let root = temp_root();
+        let game_dir = GameDir::new(&root);
+        write_profile(
+            &game_dir,
+            json!({ "id": "forge", "inheritsFrom": "1.12.2" }),
+        );
+
+        let result = resolve_profile(&game_dir, "forge").await;
+        let _ = std::fs::remove_dir_all(&root);
+        assert!(matches!(result, Err(MinecraftError::VersionNotFound(id)) if id == "1.12.2"));
+    }
+}
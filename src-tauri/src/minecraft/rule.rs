@@ -0,0 +1,414 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+/// 规则的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// 条件满足时允许
+    Allow,
+    /// 条件满足时禁止
+    Disallow,
+}
+
+/// 操作系统条件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OsRule {
+    /// 系统名称：`windows`、`osx`、`linux`
+    pub name: Option<String>,
+    /// 系统版本，为正则表达式
+    pub version: Option<String>,
+    /// 架构，如`x86`
+    pub arch: Option<String>,
+}
+
+/// 依赖库和启动参数的生效规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    /// 动作
+    pub action: RuleAction,
+    /// 操作系统条件
+    #[serde(default)]
+    pub os: Option<OsRule>,
+    /// 启动器特性条件，如`is_demo_user`、`has_custom_resolution`
+    #[serde(default)]
+    pub features: Option<BTreeMap<String, bool>>,
+}
+
+/// 当前运行的平台，用于判断规则是否生效
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    /// 版本JSON中使用的系统名称：`windows`、`osx`、`linux`
+    pub os: &'static str,
+    /// 版本JSON中使用的架构名称，如`x86`、`x86_64`、`arm64`
+    pub arch: &'static str,
+    /// 系统版本，无法获取时包含版本条件的规则不生效
+    pub os_version: Option<String>,
+}
+
+impl Platform {
+    /// 当前平台
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "osx",
+            other => other,
+        };
+        let arch = match std::env::consts::ARCH {
+            "aarch64" => "arm64",
+            other => other,
+        };
+        Self {
+            os,
+            arch,
+            os_version: os_version(),
+        }
+    }
+
+    /// 本地库分类器中`${arch}`的取值
+    pub fn arch_bits(&self) -> &'static str {
+        match self.arch {
+            "x86" | "arm" => "32",
+            _ => "64",
+        }
+    }
+}
+
+/// 系统版本，格式与Java的`os.version`一致，只在第一次调用时读取
+fn os_version() -> Option<String> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION.get_or_init(read_os_version).clone()
+}
+
+/// Windows上为`主版本.次版本`，如`10.0`
+///
+/// `GetVersionEx`受兼容性清单影响，使用`RtlGetVersion`获取真实版本。
+#[cfg(windows)]
+fn read_os_version() -> Option<String> {
+    #[repr(C)]
+    struct OsVersionInfo {
+        size: u32,
+        major: u32,
+        minor: u32,
+        build: u32,
+        platform_id: u32,
+        csd_version: [u16; 128],
+    }
+
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn RtlGetVersion(info: *mut OsVersionInfo) -> i32;
+    }
+
+    let mut info = OsVersionInfo {
+        size: std::mem::size_of::<OsVersionInfo>() as u32,
+        major: 0,
+        minor: 0,
+        build: 0,
+        platform_id: 0,
+        csd_version: [0; 128],
+    };
+    // SAFETY: `info`是按OSVERSIONINFOW布局的有效结构体，且已设置大小
+    let status = unsafe { RtlGetVersion(&mut info) };
+    (status == 0).then(|| format!("{}.{}", info.major, info.minor))
+}
+
+/// macOS上为系统版本，如`14.4.1`
+#[cfg(target_os = "macos")]
+fn read_os_version() -> Option<String> {
+    command_output("sw_vers", &["-productVersion"])
+}
+
+/// Linux等系统上为内核版本，如`6.8.0-31-generic`
+#[cfg(not(any(windows, target_os = "macos")))]
+fn read_os_version() -> Option<String> {
+    command_output("uname", &["-r"])
+}
+
+/// 运行命令并返回去掉首尾空白的输出，失败或输出为空时返回`None`
+#[cfg(not(windows))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_version_output(&output.stdout)
+}
+
+/// 从命令输出中取出系统版本，输出不是UTF-8或为空时返回`None`
+#[cfg(any(not(windows), test))]
+fn parse_version_output(stdout: &[u8]) -> Option<String> {
+    let version = std::str::from_utf8(stdout).ok()?.trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// 系统版本是否匹配规则中的正则表达式，无效的表达式不匹配
+///
+/// 版本JSON中的表达式只有少数几种，编译结果按表达式缓存，避免每次判断规则时重新编译。
+fn version_matches(pattern: &str, version: &str) -> bool {
+    static REGEXES: OnceLock<Mutex<HashMap<String, Option<Regex>>>> = OnceLock::new();
+    let mut regexes = REGEXES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    regexes
+        .entry(pattern.to_string())
+        .or_insert_with(|| Regex::new(pattern).ok())
+        .as_ref()
+        .is_some_and(|regex| regex.is_match(version))
+}
+
+impl OsRule {
+    /// 当前平台是否满足条件
+    fn matches(&self, platform: &Platform) -> bool {
+        if let Some(name) = &self.name {
+            if name != platform.os {
+                return false;
+            }
+        }
+        if let Some(arch) = &self.arch {
+            if arch != platform.arch {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.version {
+            let matched = platform
+                .os_version
+                .as_deref()
+                .is_some_and(|version| version_matches(pattern, version));
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Rule {
+    /// 规则的条件是否满足，未列出的特性视为关闭
+    fn matches(&self, platform: &Platform, features: &BTreeMap<String, bool>) -> bool {
+        if let Some(os) = &self.os {
+            if !os.matches(platform) {
+                return false;
+            }
+        }
+        if let Some(required) = &self.features {
+            for (name, value) in required {
+                if features.get(name).copied().unwrap_or(false) != *value {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// 判断一组规则是否允许使用
+///
+/// 没有规则时允许；有规则时默认禁止，按顺序由最后一条满足条件的规则决定。
+pub fn rules_allow(rules: &[Rule], platform: &Platform, features: &BTreeMap<String, bool>) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(platform, features))
+        .is_some_and(|rule| rule.action == RuleAction::Allow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform(os: &'static str, arch: &'static str, os_version: Option<&str>) -> Platform {
+        Platform {
+            os,
+            arch,
+            os_version: os_version.map(str::to_string),
+        }
+    }
+
+    fn os_rule(action: RuleAction, name: Option<&str>, version: Option<&str>) -> Rule {
+        Rule {
+            action,
+            os: Some(OsRule {
+                name: name.map(str::to_string),
+                version: version.map(str::to_string),
+                arch: None,
+            }),
+            features: None,
+        }
+    }
+
+    fn allow() -> Rule {
+        Rule {
+            action: RuleAction::Allow,
+            os: None,
+            features: None,
+        }
+    }
+
+    fn no_features() -> BTreeMap<String, bool> {
+        BTreeMap::new()
+    }
+
+    #[test]
+    fn allows_without_rules() {
+        let linux = platform("linux", "x86_64", None);
+        assert!(rules_allow(&[], &linux, &no_features()));
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        // 除macOS外都允许
+        let rules = [allow(), os_rule(RuleAction::Disallow, Some("osx"), None)];
+        assert!(rules_allow(
+            &rules,
+            &platform("linux", "x86_64", None),
+            &no_features()
+        ));
+        assert!(!rules_allow(
+            &rules,
+            &platform("osx", "arm64", None),
+            &no_features()
+        ));
+
+        // 顺序相反时后面的允许规则覆盖前面的禁止规则
+        let rules = [os_rule(RuleAction::Disallow, Some("osx"), None), allow()];
+        assert!(rules_allow(
+            &rules,
+            &platform("osx", "arm64", None),
+            &no_features()
+        ));
+    }
+
+    #[test]
+    fn disallows_when_no_rule_matches() {
+        let rules = [os_rule(RuleAction::Allow, Some("windows"), None)];
+        assert!(rules_allow(
+            &rules,
+            &platform("windows", "x86_64", None),
+            &no_features()
+        ));
+        assert!(!rules_allow(
+            &rules,
+            &platform("linux", "x86_64", None),
+            &no_features()
+        ));
+
+        let rules = [os_rule(RuleAction::Disallow, Some("osx"), None)];
+        assert!(!rules_allow(
+            &rules,
+            &platform("linux", "x86_64", None),
+            &no_features()
+        ));
+    }
+
+    #[test]
+    fn matches_os_version_pattern() {
+        let rules = [os_rule(RuleAction::Allow, Some("windows"), Some(r"^10\."))];
+        let windows = |version| platform("windows", "x86_64", version);
+        assert!(rules_allow(&rules, &windows(Some("10.0")), &no_features()));
+        assert!(!rules_allow(&rules, &windows(Some("6.1")), &no_features()));
+        // 无法获取系统版本时不满足版本条件
+        assert!(!rules_allow(&rules, &windows(None), &no_features()));
+
+        // 无效的正则表达式不满足条件
+        let rules = [os_rule(RuleAction::Allow, None, Some("("))];
+        assert!(!rules_allow(&rules, &windows(Some("10.0")), &no_features()));
+    }
+
+    #[test]
+    fn matches_arch() {
+        let rules = [Rule {
+            action: RuleAction::Allow,
+            os: Some(OsRule {
+                arch: Some("x86".to_string()),
+                ..OsRule::default()
+            }),
+            features: None,
+        }];
+        assert!(rules_allow(
+            &rules,
+            &platform("windows", "x86", None),
+            &no_features()
+        ));
+        assert!(!rules_allow(
+            &rules,
+            &platform("windows", "x86_64", None),
+            &no_features()
+        ));
+    }
+
+    #[test]
+    fn matches_features() {
+        let rules = [Rule {
+            action: RuleAction::Allow,
+            os: None,
+            features: Some(BTreeMap::from([(
+                "has_custom_resolution".to_string(),
+                true,
+            )])),
+        }];
+        let linux = platform("linux", "x86_64", None);
+        let features = |value| BTreeMap::from([("has_custom_resolution".to_string(), value)]);
+        assert!(rules_allow(&rules, &linux, &features(true)));
+        assert!(!rules_allow(&rules, &linux, &features(false)));
+        // 未列出的特性视为关闭
+        assert!(!rules_allow(&rules, &linux, &no_features()));
+    }
+
+    #[test]
+    fn parses_rules_from_json() {
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[
+                { "action": "allow" },
+                { "action": "disallow", "os": { "name": "osx" } },
+                { "action": "allow", "features": { "is_demo_user": true } }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(rules[1].os.as_ref().unwrap().name.as_deref(), Some("osx"));
+        assert!(rules_allow(
+            &rules,
+            &platform("linux", "x86_64", None),
+            &no_features()
+        ));
+    }
+
+    #[test]
+    fn reports_arch_bits() {
+        assert_eq!(platform("windows", "x86", None).arch_bits(), "32");
+        assert_eq!(platform("linux", "arm", None).arch_bits(), "32");
+        assert_eq!(platform("linux", "x86_64", None).arch_bits(), "64");
+        assert_eq!(platform("osx", "arm64", None).arch_bits(), "64");
+    }
+
+    #[test]
+    fn parses_version_output() {
+        assert_eq!(
+            parse_version_output(b"6.8.0-31-generic\n").as_deref(),
+            Some("6.8.0-31-generic")
+        );
+        assert_eq!(
+            parse_version_output(b" 14.4.1 \r\n").as_deref(),
+            Some("14.4.1")
+        );
+        assert_eq!(parse_version_output(b" \n"), None);
+        assert_eq!(parse_version_output(&[0xff, 0xfe]), None);
+    }
+
+    #[test]
+    fn matches_cached_version_patterns() {
+        for _ in 0..2 {
+            assert!(version_matches(r"^10\.", "10.0"));
+            assert!(!version_matches(r"^10\.", "6.1"));
+            assert!(!version_matches("(", "10.0"));
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use bytes::Bytes;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CACHE_CONTROL, CONTENT_LENGTH, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, VARY,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::network::error::NetworkError;
+use crate::network::recorder::BodyRecorder;
+
+/// 缓存策略，只作用于GET请求
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachePolicy {
+    /// 不使用缓存
+    #[default]
+    Bypass,
+    /// 优先请求网络（携带条件请求头），网络不可用时使用缓存
+    NetworkFirst,
+    /// 缓存未过期时直接使用缓存，过期后按网络优先处理
+    CacheFirst,
+    /// 只使用缓存，不发送请求，用于离线模式
+    CacheOnly,
+}
+
+/// 已读取完整响应体的响应，可能来自网络或缓存
+#[derive(Debug, Clone)]
+pub struct FetchedResponse {
+    /// 状态码
+    pub status: StatusCode,
+    /// 响应头
+    pub headers: HeaderMap,
+    /// 跟随重定向后的最终URL
+    pub url: String,
+    /// 响应体
+    pub body: Bytes,
+    /// 是否来自缓存
+    pub from_cache: bool,
+    /// 之前失败的尝试，没有重试时为空
+    pub failed_attempts: Vec<String>,
+}
+
+impl FetchedResponse {
+    /// 读取网络响应的完整响应体，启用流量记录时同时记录响应体
+    pub async fn read(mut response: reqwest::Response) -> Result<Self, NetworkError> {
+        let recorder = response.extensions_mut().remove::<BodyRecorder>();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let url = response.url().to_string();
+        let body = response.bytes().await?;
+
+        if let Some(recorder) = recorder {
+            recorder.finish(&body);
+        }
+        Ok(Self {
+            status,
+            headers,
+            url,
+            body,
+            from_cache: false,
+            failed_attempts: Vec::new(),
+        })
+    }
+}
+
+/// 缓存条目的元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheMeta {
+    /// 请求URL
+    url: String,
+    /// 跟随重定向后的最终URL
+    final_url: String,
+    /// 响应头
+    headers: Vec<(String, String)>,
+    /// 保存或最近一次验证的时间（UNIX时间戳，秒）
+    stored_at: u64,
+    /// Cache-Control中的max-age（秒）
+    max_age: Option<u64>,
+}
+
+/// 缓存条目
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// 元数据
+    meta: CacheMeta,
+    /// 响应体
+    body: Bytes,
+}
+
+impl CacheEntry {
+    /// 缓存是否仍在max-age范围内
+    pub fn is_fresh(&self) -> bool {
+        self.meta
+            .max_age
+            .is_some_and(|max_age| now_secs() < self.meta.stored_at.saturating_add(max_age))
+    }
+
+    /// 为条件请求添加If-None-Match和If-Modified-Since请求头
+    pub fn add_conditional_headers(&self, headers: &mut HeaderMap) {
+        let cached = self.headers();
+        if let Some(etag) = cached.get(ETAG) {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = cached.get(LAST_MODIFIED) {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    /// 转换为响应
+    pub fn to_response(&self) -> FetchedResponse {
+        FetchedResponse {
+            status: StatusCode::OK,
+            headers: self.headers(),
+            url: self.meta.final_url.clone(),
+            body: self.body.clone(),
+            from_cache: true,
+            failed_attempts: Vec::new(),
+        }
+    }
+
+    /// 缓存的响应头
+    fn headers(&self) -> HeaderMap {
+        self.meta
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// 磁盘HTTP缓存
+///
+/// 每个URL对应两个文件：`<sha1>.json`保存元数据，`<sha1>.body`保存响应体。
+/// 只缓存状态码为200的GET响应；缓存键只有URL，因此带有Vary头的响应不会缓存。
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    /// 缓存目录
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    /// 创建一个使用指定目录的缓存
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 读取缓存条目，不存在或已损坏时返回`None`
+    pub async fn load(&self, url: &str) -> Option<CacheEntry> {
+        let (meta_path, body_path) = self.paths(url);
+        let meta = tokio::fs::read(&meta_path).await.ok()?;
+        let meta: CacheMeta = serde_json::from_slice(&meta).ok()?;
+        if meta.url != url {
+            return None;
+        }
+        let body = tokio::fs::read(&body_path).await.ok()?;
+
+        Some(CacheEntry {
+            meta,
+            body: Bytes::from(body),
+        })
+    }
+
+    /// 保存响应，响应不可缓存时不做任何事
+    pub async fn store(&self, url: &str, response: &FetchedResponse) -> Result<(), NetworkError> {
+        if response.status != StatusCode::OK {
+            return Ok(());
+        }
+        let directives = CacheControl::parse(&response.headers);
+        // 响应随请求头变化时，以URL为键的缓存可能返回给不匹配的请求
+        let varies = response
+            .headers
+            .get_all(VARY)
+            .iter()
+            .any(|value| !value.as_bytes().trim_ascii().is_empty());
+        if directives.no_store || varies {
+            return Ok(());
+        }
+
+        let meta = CacheMeta {
+            url: url.to_string(),
+            final_url: response.url.clone(),
+            headers: header_pairs(&response.headers),
+            stored_at: now_secs(),
+            max_age: directives.max_age(),
+        };
+
+        let (meta_path, body_path) = self.paths(url);
+        tokio::fs::create_dir_all(&self.dir).await?;
+        write_atomic(&body_path, &response.body).await?;
+        write_atomic(&meta_path, &serde_json::to_vec(&meta)?).await
+    }
+
+    /// 服务器返回304后更新缓存的验证时间和响应头
+    pub async fn revalidated(
+        &self,
+        entry: &mut CacheEntry,
+        headers: &HeaderMap,
+    ) -> Result<(), NetworkError> {
+        let mut merged = entry.headers();
+        // 304响应没有响应体，不能用它的Content-Length覆盖缓存的值
+        for (name, value) in headers {
+            if name != CONTENT_LENGTH {
+                merged.insert(name.clone(), value.clone());
+            }
+        }
+
+        entry.meta.headers = header_pairs(&merged);
+        entry.meta.stored_at = now_secs();
+        entry.meta.max_age = CacheControl::parse(&merged).max_age();
+
+        let (meta_path, _) = self.paths(&entry.meta.url);
+        write_atomic(&meta_path, &serde_json::to_vec(&entry.meta)?).await
+    }
+
+    /// 元数据和响应体文件的路径
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = format!("{:x}", Sha1::digest(url.as_bytes()));
+        (
+            self.dir.join(format!("{}.json", key)),
+            self.dir.join(format!("{}.body", key)),
+        )
+    }
+}
+
+/// Cache-Control中与缓存相关的指令
+#[derive(Debug, Default)]
+struct CacheControl {
+    /// 不允许保存
+    no_store: bool,
+    /// 每次使用前都需要验证
+    no_cache: bool,
+    /// 最长有效时间（秒）
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    /// 解析Cache-Control响应头
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut directives = Self::default();
+
+        for value in headers.get_all(CACHE_CONTROL) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for directive in value.split(',') {
+                let directive = directive.trim();
+                let (name, arg) = match directive.split_once('=') {
+                    Some((name, arg)) => (name.trim(), Some(arg.trim().trim_matches('"'))),
+                    None => (directive, None),
+                };
+
+                match name.to_ascii_lowercase().as_str() {
+                    "no-store" => directives.no_store = true,
+                    "no-cache" => directives.no_cache = true,
+                    "max-age" => directives.max_age = arg.and_then(|arg| arg.parse().ok()),
+                    _ => {}
+                }
+            }
+        }
+
+        directives
+    }
+
+    /// 实际使用的有效时间，`no-cache`视为立即过期
+    fn max_age(&self) -> Option<u64> {
+        if self.no_cache {
+            Some(0)
+        } else {
+            self.max_age
+        }
+    }
+}
+
+/// 将响应头转换为名称和值的列表，跳过无法以文本表示的值
+pub fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// 先写入临时文件再重命名，避免读到写了一半的缓存
+///
+/// 每次写入使用不同的临时文件，同时保存同一URL时不会互相覆盖临时文件。
+async fn write_atomic(path: &Path, content: &[u8]) -> Result<(), NetworkError> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!("{}.{}.tmp", file_name, Uuid::new_v4()));
+
+    let result = match tokio::fs::write(&temp_path, content).await {
+        Ok(()) => tokio::fs::rename(&temp_path, path).await,
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    Ok(result?)
+}
+
+/// 当前的UNIX时间戳（秒）
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[(&str, &str)]) -> FetchedResponse {
+        FetchedResponse {
+            status: StatusCode::OK,
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                        HeaderValue::from_str(value).unwrap(),
+                    )
+                })
+                .collect(),
+            url: "https://example.com/final".to_string(),
+            body: Bytes::from_static(b"body"),
+            from_cache: false,
+            failed_attempts: Vec::new(),
+        }
+    }
+
+    fn temp_cache() -> HttpCache {
+        HttpCache::new(std::env::temp_dir().join(format!("novacl-cache-{}", Uuid::new_v4())))
+    }
+
+    #[tokio::test]
+    async fn stores_and_loads_responses() {
+        let cache = temp_cache();
+        let url = "https://example.com/a";
+        cache
+            .store(url, &response(&[("cache-control", "max-age=60")]))
+            .await
+            .unwrap();
+
+        let entry = cache.load(url).await.unwrap();
+        assert!(entry.is_fresh());
+        let cached = entry.to_response();
+        assert!(cached.from_cache);
+        assert_eq!(cached.body, Bytes::from_static(b"body"));
+        assert_eq!(cached.url, "https://example.com/final");
+        assert!(cache.load("https://example.com/b").await.is_none());
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+
+    #[tokio::test]
+    async fn stores_same_url_concurrently() {
+        let cache = std::sync::Arc::new(temp_cache());
+        let url = "https://example.com/a";
+        let stores = (0..8).map(|i| {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                let mut response = response(&[("etag", &format!("\"{}\"", i))]);
+                response.body = Bytes::from(format!("body-{}", i));
+                cache.store(url, &response).await
+            })
+        });
+        for result in futures::future::join_all(stores).await {
+            result.unwrap().unwrap();
+        }
+
+        let entry = cache.load(url).await.unwrap();
+        let cached = entry.to_response();
+        let body = String::from_utf8(cached.body.to_vec()).unwrap();
+        let leftovers: Vec<_> = std::fs::read_dir(&cache.dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "tmp"))
+            .collect();
+        let _ = std::fs::remove_dir_all(&cache.dir);
+
+        assert!(body.starts_with("body-"));
+        assert_eq!(cached.url, "https://example.com/final");
+        assert!(cached.headers.contains_key("etag"));
+        assert!(leftovers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_uncacheable_responses() {
+        let cache = temp_cache();
+        let cases = [
+            ("https://example.com/vary", ("vary", "Authorization")),
+            (
+                "https://example.com/no-store",
+                ("cache-control", "no-store"),
+            ),
+        ];
+        for (url, header) in cases {
+            cache.store(url, &response(&[header])).await.unwrap();
+            assert!(cache.load(url).await.is_none(), "{}", url);
+        }
+
+        let mut not_found = response(&[]);
+        not_found.status = StatusCode::NOT_FOUND;
+        cache
+            .store("https://example.com/404", &not_found)
+            .await
+            .unwrap();
+        assert!(cache.load("https://example.com/404").await.is_none());
+        let _ = std::fs::remove_dir_all(&cache.dir);
+    }
+
+    #[test]
+    fn parses_cache_control() {
+        let headers = response(&[("cache-control", "public, max-age=\"120\"")]).headers;
+        assert_eq!(CacheControl::parse(&headers).max_age(), Some(120));
+        let headers = response(&[("cache-control", "no-cache, max-age=120")]).headers;
+        assert_eq!(CacheControl::parse(&headers).max_age(), Some(0));
+        assert_eq!(CacheControl::parse(&HeaderMap::new()).max_age(), None);
+    }
+}
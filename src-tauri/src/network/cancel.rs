@@ -0,0 +1,169 @@
+use futures::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::network::error::NetworkError;
+
+/// 进行中的可取消请求
+///
+/// 前端为请求生成ID并随命令传入，之后可以通过该ID取消请求；
+/// 请求结束或命令被丢弃时自动移除登记。
+#[derive(Debug, Clone, Default)]
+pub struct CancelRegistry {
+    /// 请求ID到登记序号和取消句柄的映射
+    requests: Arc<Mutex<HashMap<String, (u64, AbortHandle)>>>,
+    /// 下一个登记序号，用于区分先后使用同一ID的请求
+    next_generation: Arc<AtomicU64>,
+}
+
+impl CancelRegistry {
+    /// 执行请求，传入ID时可以被[`CancelRegistry::cancel`]取消
+    ///
+    /// 被取消时返回[`NetworkError::Cancelled`]；同一ID的请求仍在进行时返回错误。
+    /// 除HTTP请求外，也可以用于其他能够转换网络错误的长时间操作。
+    pub async fn run<T, E, F>(&self, request_id: Option<String>, future: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<NetworkError>,
+    {
+        let Some(request_id) = request_id else {
+            return future.await;
+        };
+
+        let (handle, registration) = AbortHandle::new_pair();
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut requests = self.lock();
+            if requests.contains_key(&request_id) {
+                return Err(
+                    NetworkError::InvalidArgument(format!("请求ID已存在: {}", request_id)).into(),
+                );
+            }
+            requests.insert(request_id.clone(), (generation, handle));
+        }
+
+        let _guard = Registration {
+            registry: self,
+            request_id,
+            generation,
+        };
+        Abortable::new(future, registration)
+            .await
+            .unwrap_or_else(|_| Err(NetworkError::Cancelled.into()))
+    }
+
+    /// 取消请求，请求不存在或已经结束时返回`false`
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.lock().remove(request_id) {
+            Some((_, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 锁定登记表，持有锁的线程崩溃后继续使用其中的数据
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (u64, AbortHandle)>> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 请求结束或被丢弃时移除登记
+struct Registration<'a> {
+    /// 所属的登记表
+    registry: &'a CancelRegistry,
+    /// 请求ID
+    request_id: String,
+    /// 登记序号
+    generation: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        // 被取消后同一ID可能已经登记了新的请求，只移除属于自己的登记
+        let mut requests = self.registry.lock();
+        if requests
+            .get(&self.request_id)
+            .is_some_and(|(generation, _)| *generation == self.generation)
+        {
+            requests.remove(&self.request_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn cancels_running_request() {
+        let registry = CancelRegistry::default();
+        let running = registry.run(
+            Some("a".to_string()),
+            std::future::pending::<Result<(), NetworkError>>(),
+        );
+        let cancel = async {
+            tokio::task::yield_now().await;
+            assert!(registry.cancel("a"));
+        };
+        let (result, _) = tokio::join!(running, cancel);
+        assert!(matches!(result, Err(NetworkError::Cancelled)));
+        assert!(!registry.cancel("a"));
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_ids() {
+        let registry = CancelRegistry::default();
+        let (tx, rx) = oneshot::channel::<()>();
+        let first = registry.run(Some("a".to_string()), async {
+            let _ = rx.await;
+            Ok::<_, NetworkError>(())
+        });
+        let second = async {
+            tokio::task::yield_now().await;
+            let result = registry
+                .run(Some("a".to_string()), async { Ok::<_, NetworkError>(()) })
+                .await;
+            let _ = tx.send(());
+            result
+        };
+        let (first, second) = tokio::join!(first, second);
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(NetworkError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn reused_id_survives_old_registration_drop() {
+        let registry = CancelRegistry::default();
+        let mut old = Box::pin(registry.run(
+            Some("a".to_string()),
+            std::future::pending::<Result<(), NetworkError>>(),
+        ));
+        // 先轮询一次完成登记
+        assert!(tokio::time::timeout(Duration::from_millis(10), &mut old)
+            .await
+            .is_err());
+        assert!(registry.cancel("a"));
+
+        // 旧请求还没有结束时立即以同一ID发起新请求
+        let new = registry.run(
+            Some("a".to_string()),
+            std::future::pending::<Result<(), NetworkError>>(),
+        );
+        let check = async {
+            tokio::task::yield_now().await;
+            assert!(matches!(old.await, Err(NetworkError::Cancelled)));
+            // 旧请求的登记被丢弃后，新请求仍然可以取消
+            assert!(registry.cancel("a"));
+        };
+        let (result, _) = tokio::join!(new, check);
+        assert!(matches!(result, Err(NetworkError::Cancelled)));
+    }
+}
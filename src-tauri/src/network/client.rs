@@ -1,133 +1,412 @@
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Method, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::time::Duration;
+use std::time::Instant;
 
+use crate::network::cache::{CachePolicy, FetchedResponse, HttpCache};
+use crate::network::diagnostics::{self, DiagnosticsConfig, DiagnosticsReport};
 use crate::network::error::NetworkError;
+use crate::network::multipart::{self, MultipartPart, UploadProgress};
+use crate::network::options::RequestOptions;
+use crate::network::request::{HttpRequest, HttpResponse, ResponseType};
+use crate::network::retry::is_idempotent;
+use crate::network::shared::{ClientSnapshot, SharedClient};
+use crate::network::stream::{self, AckReceiver, StreamEvent, StreamFormat};
 
 /// HTTP客户端，支持自定义UserAgent
+///
+/// 底层使用[`SharedClient`]，克隆不会创建新的连接池；
+/// UserAgent、请求头、缓存策略等通过[`RequestOptions`]按请求设置，不会修改共享客户端。
+#[derive(Debug, Clone)]
 pub struct HttpClient {
-    /// 内部reqwest客户端
-    client: reqwest::Client,
-    /// 默认超时时间（秒）
-    timeout: u64,
+    /// 共享的reqwest客户端
+    shared: SharedClient,
+    /// 磁盘缓存，未设置时忽略请求选项中的缓存策略
+    cache: Option<HttpCache>,
 }
 
 impl HttpClient {
-    /// 创建一个新的HTTP客户端，使用默认配置
-    pub fn new() -> Self {
-        Self::with_user_agent("NovaCL/1.0")
+    /// 创建一个新的HTTP客户端
+    pub fn new(shared: SharedClient) -> Self {
+        Self {
+            shared,
+            cache: None,
+        }
+    }
+
+    /// 设置磁盘缓存
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
-    /// 创建一个新的HTTP客户端，使用自定义UserAgent
-    pub fn with_user_agent(user_agent: &str) -> Self {
-        let client = reqwest::Client::builder()
-            .user_agent(user_agent)
-            .timeout(Duration::from_secs(30))
-            .build()
-            .unwrap_or_default();
+    /// 获取共享的客户端
+    pub fn shared(&self) -> &SharedClient {
+        &self.shared
+    }
 
-        Self {
-            client,
-            timeout: 30,
+    /// 创建请求，并应用请求选项
+    fn request_builder(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        options: &RequestOptions,
+    ) -> Result<reqwest::RequestBuilder, NetworkError> {
+        let snapshot = self.shared.snapshot();
+        options.apply(
+            snapshot.client.request(method, url),
+            Some(snapshot.config.request_timeout()),
+        )
+    }
+
+    /// 发送请求并读取完整响应，GET请求按缓存策略使用磁盘缓存
+    ///
+    /// 带有Authorization的请求不使用缓存，避免把一个凭据的响应返回给另一个凭据；
+    /// 无法使用缓存时，`CacheOnly`策略直接返回[`NetworkError::CacheMiss`]。
+    async fn fetch(
+        &self,
+        builder: reqwest::RequestBuilder,
+        options: &RequestOptions,
+    ) -> Result<FetchedResponse, NetworkError> {
+        let policy = options.cache;
+        let snapshot = self.shared.snapshot();
+        let mut request = builder.build()?;
+
+        let cacheable = policy != CachePolicy::Bypass
+            && request.method() == Method::GET
+            && !request.headers().contains_key(AUTHORIZATION);
+        let cache = match &self.cache {
+            Some(cache) if cacheable => cache,
+            _ if policy == CachePolicy::CacheOnly => {
+                return Err(NetworkError::CacheMiss(request.url().to_string()))
+            }
+            _ => return self.send_with_mirrors(&snapshot, request, options).await,
+        };
+
+        let url = request.url().to_string();
+        let entry = cache.load(&url).await;
+
+        match (policy, &entry) {
+            (CachePolicy::CacheOnly, _) => {
+                return entry
+                    .map(|entry| entry.to_response())
+                    .ok_or(NetworkError::CacheMiss(url));
+            }
+            (CachePolicy::CacheFirst, Some(entry)) if entry.is_fresh() => {
+                return Ok(entry.to_response());
+            }
+            _ => {}
+        }
+
+        // 携带条件请求头，内容未变化时服务器返回304
+        if let Some(entry) = &entry {
+            entry.add_conditional_headers(request.headers_mut());
+        }
+
+        let response = match self.send_with_mirrors(&snapshot, request, options).await {
+            Ok(response) => response,
+            // 网络不可用时使用缓存
+            Err(e) => return entry.map(|entry| entry.to_response()).ok_or(e),
+        };
+
+        match entry {
+            Some(mut entry) if response.status == StatusCode::NOT_MODIFIED => {
+                if let Err(e) = cache.revalidated(&mut entry, &response.headers).await {
+                    eprintln!("更新HTTP缓存失败: {}", e);
+                }
+                Ok(entry.to_response())
+            }
+            // 服务器错误时使用缓存
+            Some(entry) if response.status.is_server_error() => Ok(entry.to_response()),
+            _ => {
+                if let Err(e) = cache.store(&url, &response).await {
+                    eprintln!("保存HTTP缓存失败: {}", e);
+                }
+                Ok(response)
+            }
         }
     }
 
-    /// 创建一个新的HTTP客户端，使用自定义配置
-    pub fn with_config(user_agent: &str, timeout: u64) -> Self {
-        let client = reqwest::Client::builder()
-            .user_agent(user_agent)
-            .timeout(Duration::from_secs(timeout))
-            .build()
-            .unwrap_or_default();
+    /// 按镜像配置依次尝试各个候选地址，请求失败或返回错误状态码时换下一个地址
+    ///
+    /// 缓存仍以原始地址为键；请求体无法复制时只尝试第一个地址。
+    async fn send_with_mirrors(
+        &self,
+        snapshot: &ClientSnapshot,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<FetchedResponse, NetworkError> {
+        let mut urls = snapshot.mirrors.candidates(request.url());
+        let last = urls.pop().unwrap_or_else(|| request.url().clone());
+        let mut request = request;
+
+        for url in urls {
+            let next = request.try_clone();
+            *request.url_mut() = url;
+            let result = self.send_with_retry(snapshot, request, options).await;
+
+            match next {
+                Some(next) if needs_fallback(&result) => request = next,
+                _ => return result,
+            }
+        }
 
-        Self { client, timeout }
+        *request.url_mut() = last;
+        self.send_with_retry(snapshot, request, options).await
     }
 
-    /// 设置超时时间
-    pub fn set_timeout(&mut self, timeout: u64) {
-        self.timeout = timeout;
-        // 重新创建客户端以应用新的超时设置
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout))
-            .build()
-            .unwrap_or_default();
-        self.client = client;
+    /// 经过限流器发送请求并读取完整响应，遇到可重试的错误时按重试配置重试
+    ///
+    /// 所有尝试都失败时，返回的错误中包含每次尝试的结果；
+    /// 最后一次仍是5xx响应时返回该响应，之前失败的尝试记录在响应中。
+    async fn send_with_retry(
+        &self,
+        snapshot: &ClientSnapshot,
+        request: reqwest::Request,
+        options: &RequestOptions,
+    ) -> Result<FetchedResponse, NetworkError> {
+        let retry = &snapshot.config.retry;
+        let max_retries = if is_idempotent(request.method()) || options.retry_non_idempotent {
+            options.max_retries.unwrap_or(retry.max_retries)
+        } else {
+            0
+        };
+
+        if max_retries == 0 {
+            return FetchedResponse::read(snapshot.execute(request).await?).await;
+        }
+
+        let mut request = request;
+        let mut attempts = Vec::new();
+
+        loop {
+            // 请求体是流时无法复制，只能发送一次
+            let next = if (attempts.len() as u32) < max_retries {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            let started = Instant::now();
+            let result = match snapshot.execute(request).await {
+                Ok(response) => FetchedResponse::read(response).await,
+                Err(e) => Err(e.into()),
+            };
+            let failure = match &result {
+                Ok(response) if response.status.is_server_error() => {
+                    format!("HTTP错误: {}", response.status)
+                }
+                Err(e) if e.is_retryable() => e.to_string(),
+                _ => return finish_attempts(result, attempts),
+            };
+            attempts.push(format!(
+                "第{}次: {} ({}ms)",
+                attempts.len() + 1,
+                failure,
+                started.elapsed().as_millis()
+            ));
+
+            let Some(next) = next else {
+                return finish_attempts(result, attempts);
+            };
+            tokio::time::sleep(retry.backoff(attempts.len() as u32 - 1)).await;
+            request = next;
+        }
     }
 
-    /// 发送GET请求
-    pub async fn get<T>(&self, url: &str) -> Result<T, NetworkError>
-    where
-        T: DeserializeOwned,
-    {
-        let response = self.client.get(url).send().await?;
-        let status = response.status();
+    /// 发送请求并读取完整响应，非2xx状态码视为错误
+    async fn fetch_success(
+        &self,
+        builder: reqwest::RequestBuilder,
+        options: &RequestOptions,
+    ) -> Result<FetchedResponse, NetworkError> {
+        let response = self.fetch(builder, options).await?;
+
+        if !response.status.is_success() {
+            let error = NetworkError::http_status(response.status.as_u16(), &response.body);
+            return Err(match response.failed_attempts {
+                attempts if attempts.is_empty() => error,
+                attempts => NetworkError::RetriesExhausted {
+                    attempts,
+                    source: Box::new(error),
+                },
+            });
+        }
+
+        Ok(response)
+    }
 
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
+    /// 发送请求并返回尚未读取响应体的响应
+    ///
+    /// 不使用缓存和重试，按镜像配置依次尝试候选地址；
+    /// 请求选项中未设置超时时间时不限制总时长，由调用方控制读取超时。
+    pub async fn open(&self, request: &HttpRequest) -> Result<reqwest::Response, NetworkError> {
+        let snapshot = self.shared.snapshot();
+        let read_timeout = snapshot.config.read_timeout();
+        let mut builder = request.options.apply(
+            snapshot
+                .client
+                .request(request.method()?, request.url.as_str()),
+            None,
+        )?;
+        if let Some(body) = &request.body {
+            builder = body.apply(builder, &request.options)?;
+        }
+        let mut request = builder.build()?;
+
+        let mut urls = snapshot.mirrors.candidates(request.url());
+        let last = urls.pop().unwrap_or_else(|| request.url().clone());
+        let send = |request| async {
+            tokio::time::timeout(read_timeout, snapshot.execute(request))
+                .await
+                .map_err(|_| NetworkError::Timeout)?
+                .map_err(NetworkError::from)
+        };
+
+        for url in urls {
+            let next = request.try_clone();
+            *request.url_mut() = url;
+            let result = send(request).await;
+
+            let failed = result
+                .as_ref()
+                .map_or(true, |response| is_error_status(response.status()));
+            match next {
+                Some(next) if failed => request = next,
+                _ => return result,
+            }
         }
 
-        let result = response.json::<T>().await?;
-        Ok(result)
+        *request.url_mut() = last;
+        send(request).await
     }
 
-    /// 发送GET请求并返回文本
-    pub async fn get_text(&self, url: &str) -> Result<String, NetworkError> {
-        let response = self.client.get(url).send().await?;
-        let status = response.status();
+    /// 发送请求并将响应体分块交给`emit`，返回接收的总字节数
+    pub async fn stream(
+        &self,
+        request: &HttpRequest,
+        format: StreamFormat,
+        acks: AckReceiver,
+        emit: impl FnMut(StreamEvent) -> Result<(), NetworkError>,
+    ) -> Result<u64, NetworkError> {
+        let response = self.open(request).await?;
+        let read_timeout = self.shared.config().read_timeout();
+        stream::forward(response, format, read_timeout, acks, emit).await
+    }
 
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
-        }
+    /// 以multipart/form-data格式上传，文件部分边读边发送，通过`on_progress`报告上传进度
+    ///
+    /// 请求体是流，不会重试；请求选项中未设置超时时间时不限制总时长。
+    /// 响应体按JSON解析，无法解析时以文本返回。
+    pub async fn post_multipart(
+        &self,
+        url: &str,
+        parts: &[MultipartPart],
+        options: &RequestOptions,
+        on_progress: impl Fn(UploadProgress) + Send + Sync + 'static,
+    ) -> Result<HttpResponse, NetworkError> {
+        let form = multipart::build_form(parts, on_progress).await?;
+        let snapshot = self.shared.snapshot();
+        let builder = options
+            .apply(snapshot.client.post(url), None)?
+            .multipart(form);
+
+        let response = self.fetch_success(builder, options).await?;
+        Ok(HttpResponse::from_fetched(response, ResponseType::Json))
+    }
 
-        let result = response.text().await?;
-        Ok(result)
+    /// 诊断网络连接，依次测量各个地址的各阶段耗时并推荐下载源
+    pub async fn diagnose(
+        &self,
+        config: &DiagnosticsConfig,
+    ) -> Result<DiagnosticsReport, NetworkError> {
+        config.validate()?;
+        Ok(diagnostics::run(&self.shared.snapshot(), config).await)
     }
 
-    /// 发送GET请求并返回字节数据
-    pub async fn get_bytes(&self, url: &str) -> Result<bytes::Bytes, NetworkError> {
-        let response = self.client.get(url).send().await?;
-        let status = response.status();
+    /// 发送通用请求，非2xx状态码同样以响应返回，只有网络错误等才返回错误
+    pub async fn execute(&self, request: &HttpRequest) -> Result<HttpResponse, NetworkError> {
+        let mut builder =
+            self.request_builder(request.method()?, &request.url, &request.options)?;
 
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
+        if let Some(body) = &request.body {
+            builder = body.apply(builder, &request.options)?;
         }
 
-        let result = response.bytes().await?;
+        let response = self.fetch(builder, &request.options).await?;
+        Ok(HttpResponse::from_fetched(response, request.response_type))
+    }
+
+    /// 发送GET请求
+    pub async fn get<T>(&self, url: &str, options: &RequestOptions) -> Result<T, NetworkError>
+    where
+        T: DeserializeOwned,
+    {
+        let builder = self.request_builder(reqwest::Method::GET, url, options)?;
+        let response = self.fetch_success(builder, options).await?;
+        let result = serde_json::from_slice::<T>(&response.body)?;
+        Ok(result)
+    }
+
+    /// 发送GET请求并返回文本
+    pub async fn get_text(
+        &self,
+        url: &str,
+        options: &RequestOptions,
+    ) -> Result<String, NetworkError> {
+        let builder = self.request_builder(reqwest::Method::GET, url, options)?;
+        let response = self.fetch_success(builder, options).await?;
+        let result = String::from_utf8_lossy(&response.body).into_owned();
+        Ok(result)
+    }
+
+    /// 发送GET请求并返回字节数据
+    pub async fn get_bytes(
+        &self,
+        url: &str,
+        options: &RequestOptions,
+    ) -> Result<bytes::Bytes, NetworkError> {
+        let builder = self.request_builder(reqwest::Method::GET, url, options)?;
+        let result = self.fetch_success(builder, options).await?.body;
         Ok(result)
     }
 
     /// 发送POST请求，带JSON数据
-    pub async fn post_json<T, R>(&self, url: &str, data: &T) -> Result<R, NetworkError>
+    pub async fn post_json<T, R>(
+        &self,
+        url: &str,
+        data: &T,
+        options: &RequestOptions,
+    ) -> Result<R, NetworkError>
     where
         T: Serialize + ?Sized,
         R: DeserializeOwned,
     {
-        let response = self.client.post(url).json(data).send().await?;
-        let status = response.status();
-
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
-        }
-
-        let result = response.json::<R>().await?;
+        let builder = self
+            .request_builder(reqwest::Method::POST, url, options)?
+            .json(data);
+        let response = self.fetch_success(builder, options).await?;
+        let result = serde_json::from_slice::<R>(&response.body)?;
         Ok(result)
     }
 
     /// 发送POST请求，带表单数据
-    pub async fn post_form<T, R>(&self, url: &str, form: &T) -> Result<R, NetworkError>
+    pub async fn post_form<T, R>(
+        &self,
+        url: &str,
+        form: &T,
+        options: &RequestOptions,
+    ) -> Result<R, NetworkError>
     where
         T: Serialize + ?Sized,
         R: DeserializeOwned,
     {
-        let response = self.client.post(url).form(form).send().await?;
-        let status = response.status();
-
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
-        }
-
-        let result = response.json::<R>().await?;
+        let builder = self
+            .request_builder(reqwest::Method::POST, url, options)?
+            .form(form);
+        let response = self.fetch_success(builder, options).await?;
+        let result = serde_json::from_slice::<R>(&response.body)?;
         Ok(result)
     }
 
@@ -138,11 +417,12 @@ impl HttpClient {
         url: &str,
         headers: Option<HeaderMap>,
         body: Option<Vec<u8>>,
+        options: &RequestOptions,
     ) -> Result<R, NetworkError>
     where
         R: DeserializeOwned,
     {
-        let mut request_builder = self.client.request(method, url);
+        let mut request_builder = self.request_builder(method, url, options)?;
 
         if let Some(headers) = headers {
             request_builder = request_builder.headers(headers);
@@ -152,25 +432,178 @@ impl HttpClient {
             request_builder = request_builder.body(body);
         }
 
-        let response = request_builder.send().await?;
-        let status = response.status();
+        let response = self.fetch_success(request_builder, options).await?;
+        let result = serde_json::from_slice::<R>(&response.body)?;
+        Ok(result)
+    }
+}
+
+/// 是否需要换下一个候选地址，304等重定向类状态码视为成功
+fn needs_fallback(result: &Result<FetchedResponse, NetworkError>) -> bool {
+    result
+        .as_ref()
+        .map_or(true, |response| is_error_status(response.status))
+}
+
+/// 4xx和5xx状态码
+fn is_error_status(status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
 
-        if !status.is_success() {
-            return Err(NetworkError::HttpError(format!("HTTP错误: {}", status)));
+/// 将之前失败的尝试记录到最终结果中
+fn finish_attempts(
+    result: Result<FetchedResponse, NetworkError>,
+    attempts: Vec<String>,
+) -> Result<FetchedResponse, NetworkError> {
+    match result {
+        Ok(mut response) => {
+            response.failed_attempts = attempts;
+            Ok(response)
         }
+        Err(e) if attempts.is_empty() => Err(e),
+        Err(e) => Err(NetworkError::RetriesExhausted {
+            attempts,
+            source: Box::new(e),
+        }),
+    }
+}
 
-        let result = response.json::<R>().await?;
-        Ok(result)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::cache::HttpCache;
+    use crate::network::proxy::{ProxyConfig, ProxyMode};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 没有服务监听的地址，请求一旦发出就会失败
+    const UNREACHABLE_URL: &str = "http://127.0.0.1:9/resource";
+
+    fn cache_only() -> RequestOptions {
+        RequestOptions {
+            cache: CachePolicy::CacheOnly,
+            max_retries: Some(0),
+            ..RequestOptions::default()
+        }
     }
 
-    /// 创建一个HeaderMap，包含自定义的UserAgent
-    pub fn create_headers(&self, user_agent: Option<&str>) -> Result<HeaderMap, NetworkError> {
-        let mut headers = HeaderMap::new();
+    #[tokio::test]
+    async fn cache_only_without_cache_does_not_send() {
+        let client = HttpClient::new(SharedClient::new());
+        let result = client.get_bytes(UNREACHABLE_URL, &cache_only()).await;
+        assert!(matches!(result, Err(NetworkError::CacheMiss(_))));
+    }
 
-        if let Some(ua) = user_agent {
-            headers.insert(USER_AGENT, HeaderValue::from_str(ua)?);
-        }
+    #[tokio::test]
+    async fn cache_only_with_authorization_does_not_send() {
+        let dir = std::env::temp_dir().join(format!("novacl-cache-{}", uuid::Uuid::new_v4()));
+        let client = HttpClient::new(SharedClient::new()).with_cache(HttpCache::new(dir));
+        let mut options = cache_only();
+        options
+            .headers
+            .insert("Authorization".to_string(), "Bearer token".to_string());
+
+        let result = client.get_bytes(UNREACHABLE_URL, &options).await;
+        assert!(matches!(result, Err(NetworkError::CacheMiss(_))));
+
+        // 非GET请求同样不会发送
+        let builder = client
+            .request_builder(Method::POST, UNREACHABLE_URL, &cache_only())
+            .unwrap();
+        let result = client.fetch(builder, &cache_only()).await;
+        assert!(matches!(result, Err(NetworkError::CacheMiss(_))));
+    }
+
+    /// 不经过系统代理、重试等待很短的客户端
+    fn client(max_retries: u32) -> HttpClient {
+        let shared = SharedClient::new();
+        shared
+            .set_proxy(ProxyConfig {
+                mode: ProxyMode::Direct,
+                ..ProxyConfig::default()
+            })
+            .unwrap();
+        let mut config = shared.config();
+        config.retry.max_retries = max_retries;
+        config.retry.initial_backoff_ms = 1;
+        config.retry.max_backoff_ms = 1;
+        shared.set_config(config).unwrap();
+        HttpClient::new(shared)
+    }
+
+    /// 本地HTTP服务，每个连接只处理一个请求，返回地址和收到的请求数
+    async fn serve(status: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(len) if len > 0 => request.extend_from_slice(&buf[..len]),
+                        _ => break,
+                    }
+                }
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+            }
+        });
+        (format!("http://{}/resource", addr), requests)
+    }
+
+    /// 发送请求，返回响应状态码、失败的尝试次数和服务收到的请求数
+    async fn send(
+        method: Method,
+        max_retries: u32,
+        options: RequestOptions,
+    ) -> (StatusCode, usize, usize) {
+        let (url, requests) = serve("503 Service Unavailable").await;
+        let client = client(max_retries);
+        let builder = client.request_builder(method, &url, &options).unwrap();
+        let response = client.fetch(builder, &options).await.unwrap();
+        (
+            response.status,
+            response.failed_attempts.len(),
+            requests.load(Ordering::SeqCst),
+        )
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests_max_retries_times() {
+        let (status, failed, requests) = send(Method::GET, 2, RequestOptions::default()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(requests, 3);
+        assert_eq!(failed, 3);
+
+        let options = RequestOptions {
+            max_retries: Some(1),
+            ..RequestOptions::default()
+        };
+        let (_, _, requests) = send(Method::GET, 2, options).await;
+        assert_eq!(requests, 2);
+    }
 
-        Ok(headers)
+    #[tokio::test]
+    async fn does_not_retry_post_unless_allowed() {
+        let (status, failed, requests) = send(Method::POST, 2, RequestOptions::default()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(requests, 1);
+        assert_eq!(failed, 0);
+
+        let options = RequestOptions {
+            retry_non_idempotent: true,
+            ..RequestOptions::default()
+        };
+        let (_, _, requests) = send(Method::POST, 2, options).await;
+        assert_eq!(requests, 3);
     }
 }
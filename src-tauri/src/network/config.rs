@@ -0,0 +1,162 @@
+use reqwest::redirect::Policy;
+use reqwest::tls::Version;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::network::error::NetworkError;
+use crate::network::proxy::ProxyConfig;
+use crate::network::retry::RetryConfig;
+use crate::network::tls::TrustConfig;
+
+/// 默认UserAgent
+pub const DEFAULT_USER_AGENT: &str = "NovaCL/1.0";
+
+/// 最低TLS版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsVersion {
+    /// TLS 1.0
+    Tls1_0,
+    /// TLS 1.1
+    Tls1_1,
+    /// TLS 1.2
+    Tls1_2,
+    /// TLS 1.3
+    Tls1_3,
+}
+
+impl From<TlsVersion> for Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls1_0 => Version::TLS_1_0,
+            TlsVersion::Tls1_1 => Version::TLS_1_1,
+            TlsVersion::Tls1_2 => Version::TLS_1_2,
+            TlsVersion::Tls1_3 => Version::TLS_1_3,
+        }
+    }
+}
+
+/// HTTP客户端配置，HTTP客户端和下载管理器共用同一份配置和连接池
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpClientConfig {
+    /// UserAgent
+    pub user_agent: String,
+    /// 建立连接的超时时间（秒）
+    pub connect_timeout: u64,
+    /// 普通请求的总超时时间（秒），不作用于下载
+    pub request_timeout: u64,
+    /// 读取超时时间（秒）：等待响应或两次接收数据之间的最长间隔，用于下载
+    pub read_timeout: u64,
+    /// 每个主机保留的最大空闲连接数
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接的保留时间（秒）
+    pub pool_idle_timeout: u64,
+    /// 是否允许通过ALPN协商使用HTTP/2，关闭后只使用HTTP/1.1
+    pub http2: bool,
+    /// 最大重定向次数，为0时不跟随重定向
+    pub max_redirects: usize,
+    /// 最低TLS版本，为空时使用系统默认值
+    pub min_tls_version: Option<TlsVersion>,
+    /// 是否接受无效证书（不安全，仅用于调试）
+    pub accept_invalid_certs: bool,
+    /// 重试配置
+    pub retry: RetryConfig,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            connect_timeout: 10,
+            request_timeout: 30,
+            read_timeout: 30,
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout: 90,
+            http2: true,
+            max_redirects: 10,
+            min_tls_version: None,
+            accept_invalid_certs: false,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// 普通请求的总超时时间
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout)
+    }
+
+    /// 读取超时时间
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout)
+    }
+
+    /// 检查配置是否有效
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        if self.user_agent.trim().is_empty() {
+            return Err(NetworkError::InvalidArgument(
+                "UserAgent不能为空".to_string(),
+            ));
+        }
+        if self.connect_timeout == 0 || self.request_timeout == 0 || self.read_timeout == 0 {
+            return Err(NetworkError::InvalidArgument(
+                "超时时间必须大于0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 根据配置、代理和证书信任设置构建reqwest客户端
+    ///
+    /// 客户端本身不设置总超时，普通请求在发送时单独设置，避免大文件下载被中断。
+    pub fn build(
+        &self,
+        proxy: &ProxyConfig,
+        trust: &TrustConfig,
+    ) -> Result<reqwest::Client, NetworkError> {
+        self.build_with(proxy, trust, self.accept_invalid_certs)
+    }
+
+    /// 构建不校验证书的客户端，只用于[`TrustConfig::insecure_hosts`]中的主机
+    pub fn build_insecure(
+        &self,
+        proxy: &ProxyConfig,
+        trust: &TrustConfig,
+    ) -> Result<reqwest::Client, NetworkError> {
+        self.build_with(proxy, trust, true)
+    }
+
+    fn build_with(
+        &self,
+        proxy: &ProxyConfig,
+        trust: &TrustConfig,
+        accept_invalid_certs: bool,
+    ) -> Result<reqwest::Client, NetworkError> {
+        self.validate()?;
+
+        let redirect = if self.max_redirects == 0 {
+            Policy::none()
+        } else {
+            Policy::limited(self.max_redirects)
+        };
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(self.user_agent.as_str())
+            .connect_timeout(Duration::from_secs(self.connect_timeout))
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(self.pool_idle_timeout))
+            .redirect(redirect)
+            .danger_accept_invalid_certs(accept_invalid_certs);
+
+        if !self.http2 {
+            builder = builder.http1_only();
+        }
+        if let Some(version) = self.min_tls_version {
+            builder = builder.min_tls_version(version.into());
+        }
+
+        Ok(trust.apply(proxy.apply(builder)?)?.build()?)
+    }
+}
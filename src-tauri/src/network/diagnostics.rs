@@ -0,0 +1,543 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_native_tls::native_tls;
+use url::Url;
+
+use crate::network::config::HttpClientConfig;
+use crate::network::error::NetworkError;
+use crate::network::mirror::MirrorMode;
+use crate::network::proxy::ProxyMode;
+use crate::network::shared::ClientSnapshot;
+
+/// 官方下载源的名称
+const OFFICIAL_SOURCE: &str = "official";
+
+/// 诊断的目标地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticEndpoint {
+    /// 显示名称
+    pub name: String,
+    /// 请求地址
+    pub url: String,
+    /// 所属的下载源，如`official`、`bmclapi`，不参与下载源比较时为`None`
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl DiagnosticEndpoint {
+    /// 创建一个诊断地址
+    fn new(name: &str, url: &str, source: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            source: source.map(str::to_string),
+        }
+    }
+}
+
+/// 诊断配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagnosticsConfig {
+    /// 诊断的地址，依次检查
+    pub endpoints: Vec<DiagnosticEndpoint>,
+    /// 测速时最多读取的字节数
+    pub sample_bytes: u64,
+    /// 单个地址的最长诊断时间（秒）
+    pub timeout: u64,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![
+                DiagnosticEndpoint::new(
+                    "Mojang元数据",
+                    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json",
+                    Some(OFFICIAL_SOURCE),
+                ),
+                DiagnosticEndpoint::new(
+                    "Mojang资源文件",
+                    "https://resources.download.minecraft.net/",
+                    Some(OFFICIAL_SOURCE),
+                ),
+                DiagnosticEndpoint::new(
+                    "Mojang依赖库",
+                    "https://libraries.minecraft.net/",
+                    Some(OFFICIAL_SOURCE),
+                ),
+                DiagnosticEndpoint::new("Modrinth", "https://api.modrinth.com/v2", None),
+                DiagnosticEndpoint::new(
+                    "BMCLAPI元数据",
+                    "https://bmclapi2.bangbang93.com/mc/game/version_manifest_v2.json",
+                    Some("bmclapi"),
+                ),
+                DiagnosticEndpoint::new(
+                    "BMCLAPI依赖库",
+                    "https://bmclapi2.bangbang93.com/maven/",
+                    Some("bmclapi"),
+                ),
+            ],
+            sample_bytes: 256 * 1024,
+            timeout: 10,
+        }
+    }
+}
+
+impl DiagnosticsConfig {
+    /// 检查配置是否有效
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        if self.endpoints.is_empty() {
+            return Err(NetworkError::InvalidArgument(
+                "诊断地址不能为空".to_string(),
+            ));
+        }
+        if self.timeout == 0 {
+            return Err(NetworkError::InvalidArgument(
+                "超时时间必须大于0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 单个地址的诊断结果，未完成的阶段为`None`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EndpointReport {
+    /// 显示名称
+    pub name: String,
+    /// 请求地址
+    pub url: String,
+    /// 所属的下载源
+    pub source: Option<String>,
+    /// 使用手动代理时，DNS和连接时间测量的是代理服务器
+    pub via_proxy: bool,
+    /// 使用系统代理时，DNS、连接和TLS握手时间直接测量目标主机，
+    /// 首字节时间和吞吐量则按系统代理设置发送，两者不可直接比较
+    pub system_proxy: bool,
+    /// DNS解析时间（毫秒）
+    pub dns_ms: Option<u64>,
+    /// TCP连接时间（毫秒）
+    pub connect_ms: Option<u64>,
+    /// TLS握手时间（毫秒），非HTTPS或经过代理时为`None`
+    pub tls_ms: Option<u64>,
+    /// 从发送请求到收到响应头的时间（毫秒），使用新建的连接，包括连接和握手时间
+    pub ttfb_ms: Option<u64>,
+    /// 状态码
+    pub status: Option<u16>,
+    /// 测速读取的字节数
+    pub sampled_bytes: u64,
+    /// 测速得到的吞吐量（字节/秒）
+    pub throughput: Option<u64>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+impl EndpointReport {
+    /// 是否成功收到响应
+    fn is_reachable(&self) -> bool {
+        self.error.is_none() && self.ttfb_ms.is_some()
+    }
+}
+
+/// 诊断报告
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    /// 各地址的诊断结果
+    pub endpoints: Vec<EndpointReport>,
+    /// 最快的下载源，所有下载源都不可用时为`None`
+    pub recommended_source: Option<String>,
+    /// 建议的镜像使用方式
+    pub recommended_mode: Option<MirrorMode>,
+}
+
+/// 依次诊断各个地址，并根据结果推荐下载源
+pub async fn run(snapshot: &ClientSnapshot, config: &DiagnosticsConfig) -> DiagnosticsReport {
+    let timeout = Duration::from_secs(config.timeout);
+    let mut endpoints = Vec::with_capacity(config.endpoints.len());
+
+    for endpoint in &config.endpoints {
+        let mut report = EndpointReport {
+            name: endpoint.name.clone(),
+            url: endpoint.url.clone(),
+            source: endpoint.source.clone(),
+            ..EndpointReport::default()
+        };
+
+        let result = tokio::time::timeout(
+            timeout,
+            measure(snapshot, &endpoint.url, config.sample_bytes, &mut report),
+        )
+        .await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => report.error = Some(e.to_string()),
+            // 测速阶段超时时保留已读取的部分
+            Err(_) if report.ttfb_ms.is_some() => {}
+            Err(_) => report.error = Some(NetworkError::Timeout.to_string()),
+        }
+        endpoints.push(report);
+    }
+
+    let (recommended_source, recommended_mode) = recommend(&endpoints);
+    DiagnosticsReport {
+        endpoints,
+        recommended_source,
+        recommended_mode,
+    }
+}
+
+/// 依次测量DNS解析、TCP连接、TLS握手、首字节时间和吞吐量，结果写入`report`
+async fn measure(
+    snapshot: &ClientSnapshot,
+    url: &str,
+    sample_bytes: u64,
+    report: &mut EndpointReport,
+) -> Result<(), NetworkError> {
+    let url = Url::parse(url).map_err(|e| NetworkError::InvalidUrl(format!("{}: {}", url, e)))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| NetworkError::InvalidUrl(url.to_string()))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| NetworkError::InvalidUrl(url.to_string()))?;
+
+    // 使用手动代理时请求经过代理服务器，只能测量到代理服务器的连接
+    let proxy = &snapshot.proxy;
+    report.via_proxy = proxy.mode == ProxyMode::Manual;
+    report.system_proxy = proxy.mode == ProxyMode::System;
+    let (connect_host, connect_port) = if report.via_proxy {
+        (proxy.host.as_str(), proxy.port)
+    } else {
+        (host, port)
+    };
+
+    let started = Instant::now();
+    // 配置了静态映射的主机不经过DNS解析
+    let addr = match snapshot.trust.resolve(connect_host) {
+        Some(ip) => SocketAddr::new(ip, connect_port),
+        None => tokio::net::lookup_host((connect_host, connect_port))
+            .await?
+            .next()
+            .ok_or_else(|| {
+                NetworkError::ConnectionError(format!("域名没有解析到地址: {}", connect_host))
+            })?,
+    };
+    report.dns_ms = Some(elapsed_ms(started));
+
+    let started = Instant::now();
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| NetworkError::ConnectionError(e.to_string()))?;
+    report.connect_ms = Some(elapsed_ms(started));
+
+    if !report.via_proxy && url.scheme() == "https" {
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.danger_accept_invalid_certs(
+            snapshot.config.accept_invalid_certs || snapshot.trust.is_insecure(host),
+        );
+        for cert in snapshot.trust.certificates()? {
+            builder.add_root_certificate(cert);
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| NetworkError::Other(e.to_string()))?;
+        let started = Instant::now();
+        tokio_native_tls::TlsConnector::from(connector)
+            .connect(host, stream)
+            .await
+            .map_err(|e| NetworkError::ConnectionError(format!("TLS握手失败: {}", e)))?;
+        report.tls_ms = Some(elapsed_ms(started));
+    }
+
+    // 首字节时间和吞吐量使用与实际请求相同的代理、TLS配置、限流和流量记录，
+    // 但不复用共享客户端中已经建立的连接，避免结果受之前请求的影响
+    let client = probe_client(snapshot, host)?;
+    let request = client.get(url.clone()).build()?;
+    let started = Instant::now();
+    let response = snapshot.execute_with(&client, request).await?;
+    report.ttfb_ms = Some(elapsed_ms(started));
+    report.status = Some(response.status().as_u16());
+
+    let started = Instant::now();
+    let mut stream = response.bytes_stream();
+    while report.sampled_bytes < sample_bytes {
+        let Some(chunk) = stream.next().await else {
+            break;
+        };
+        report.sampled_bytes += chunk?.len() as u64;
+
+        let secs = started.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            report.throughput = Some((report.sampled_bytes as f64 / secs) as u64);
+        }
+    }
+
+    Ok(())
+}
+
+/// 测量首字节时间使用的客户端，不保留空闲连接
+fn probe_client(snapshot: &ClientSnapshot, host: &str) -> Result<reqwest::Client, NetworkError> {
+    let config = HttpClientConfig {
+        pool_max_idle_per_host: 0,
+        ..snapshot.config.clone()
+    };
+    if snapshot.trust.is_insecure(host) {
+        config.build_insecure(&snapshot.proxy, &snapshot.trust)
+    } else {
+        config.build(&snapshot.proxy, &snapshot.trust)
+    }
+}
+
+/// 比较各下载源的平均首字节时间，只考虑所有地址都可以访问的下载源
+fn recommend(endpoints: &[EndpointReport]) -> (Option<String>, Option<MirrorMode>) {
+    let mut sources: BTreeMap<&str, Option<Vec<u64>>> = BTreeMap::new();
+    for endpoint in endpoints {
+        let Some(source) = &endpoint.source else {
+            continue;
+        };
+        let latencies = sources.entry(source).or_insert_with(|| Some(Vec::new()));
+        match (latencies, endpoint.ttfb_ms) {
+            (Some(latencies), Some(ttfb)) if endpoint.is_reachable() => latencies.push(ttfb),
+            (latencies, _) => *latencies = None,
+        }
+    }
+
+    let available: Vec<(&str, u64)> = sources
+        .into_iter()
+        .filter_map(|(source, latencies)| {
+            let latencies = latencies?;
+            let average = latencies.iter().sum::<u64>() / latencies.len().max(1) as u64;
+            Some((source, average))
+        })
+        .collect();
+
+    let Some((best, _)) = available.iter().min_by_key(|(_, average)| *average) else {
+        return (None, None);
+    };
+
+    let mode = if *best != OFFICIAL_SOURCE {
+        MirrorMode::MirrorFirst
+    } else if available.len() > 1 {
+        MirrorMode::OfficialFirst
+    } else {
+        MirrorMode::OfficialOnly
+    };
+    (Some(best.to_string()), Some(mode))
+}
+
+/// 经过的毫秒数
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::proxy::ProxyConfig;
+    use crate::network::shared::SharedClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 不经过系统代理的客户端
+    fn direct_client() -> SharedClient {
+        let shared = SharedClient::new();
+        shared
+            .set_proxy(ProxyConfig {
+                mode: ProxyMode::Direct,
+                ..ProxyConfig::default()
+            })
+            .unwrap();
+        shared
+    }
+
+    fn config(url: &str, timeout: u64) -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            endpoints: vec![DiagnosticEndpoint::new("local", url, None)],
+            sample_bytes: 1024 * 1024,
+            timeout,
+        }
+    }
+
+    /// 本地HTTP服务，支持长连接，返回地址和已接受的连接数
+    async fn serve(status: &'static str, body: &'static [u8]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        let Ok(len) = socket.read(&mut buf).await else {
+                            return;
+                        };
+                        if len == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buf[..len]);
+                        if !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                            continue;
+                        }
+                        request.clear();
+                        let head = format!(
+                            "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n",
+                            status,
+                            body.len()
+                        );
+                        if socket.write_all(head.as_bytes()).await.is_err()
+                            || socket.write_all(body).await.is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{}/", addr), accepted)
+    }
+
+    #[tokio::test]
+    async fn reports_each_phase() {
+        let (url, _) = serve("200 OK", &[b'x'; 4096]).await;
+        let report = run(&direct_client().snapshot(), &config(&url, 5)).await;
+        let endpoint = &report.endpoints[0];
+
+        assert_eq!(endpoint.error, None);
+        assert!(!endpoint.via_proxy);
+        assert!(!endpoint.system_proxy);
+        assert!(endpoint.dns_ms.is_some());
+        assert!(endpoint.connect_ms.is_some());
+        assert_eq!(endpoint.tls_ms, None);
+        assert!(endpoint.ttfb_ms.is_some());
+        assert_eq!(endpoint.status, Some(200));
+        assert_eq!(endpoint.sampled_bytes, 4096);
+    }
+
+    #[tokio::test]
+    async fn labels_direct_phases_under_system_proxy() {
+        let (url, _) = serve("200 OK", b"ok").await;
+        let report = run(&SharedClient::new().snapshot(), &config(&url, 5)).await;
+        let endpoint = &report.endpoints[0];
+
+        assert!(endpoint.system_proxy);
+        assert!(!endpoint.via_proxy);
+        assert!(endpoint.connect_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_error_status_as_reachable() {
+        let (url, _) = serve("404 Not Found", b"").await;
+        let report = run(&direct_client().snapshot(), &config(&url, 5)).await;
+        let endpoint = &report.endpoints[0];
+        assert_eq!(endpoint.status, Some(404));
+        assert!(endpoint.is_reachable());
+    }
+
+    #[tokio::test]
+    async fn measures_ttfb_on_a_new_connection() {
+        let (url, accepted) = serve("200 OK", b"ok").await;
+        let shared = direct_client();
+        let snapshot = shared.snapshot();
+
+        // 共享客户端中已经有一个空闲连接
+        let response = snapshot.send(snapshot.client.get(&url)).await.unwrap();
+        response.bytes().await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        let report = run(&snapshot, &config(&url, 5)).await;
+        assert_eq!(report.endpoints[0].error, None);
+        // 测量TCP连接一次，测量首字节时间再新建一次
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn maps_connection_failures() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let report = run(&direct_client().snapshot(), &config(&url, 5)).await;
+        let endpoint = &report.endpoints[0];
+        assert!(endpoint.dns_ms.is_some());
+        assert_eq!(endpoint.connect_ms, None);
+        assert!(endpoint.error.as_deref().unwrap().starts_with("连接错误"));
+
+        let report = run(&direct_client().snapshot(), &config("not a url", 5)).await;
+        assert!(report.endpoints[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("无效的URL"));
+    }
+
+    #[tokio::test]
+    async fn maps_timeouts() {
+        // 接受连接但从不响应
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let report = run(&direct_client().snapshot(), &config(&url, 1)).await;
+        let endpoint = &report.endpoints[0];
+        assert!(endpoint.connect_ms.is_some());
+        assert_eq!(endpoint.ttfb_ms, None);
+        assert_eq!(
+            endpoint.error.as_deref(),
+            Some(NetworkError::Timeout.to_string().as_str())
+        );
+    }
+
+    fn reachable(source: &str, ttfb: u64) -> EndpointReport {
+        EndpointReport {
+            source: Some(source.to_string()),
+            ttfb_ms: Some(ttfb),
+            ..EndpointReport::default()
+        }
+    }
+
+    #[test]
+    fn recommends_fastest_fully_available_source() {
+        let endpoints = [reachable("official", 300), reachable("bmclapi", 100)];
+        assert_eq!(
+            recommend(&endpoints),
+            (Some("bmclapi".to_string()), Some(MirrorMode::MirrorFirst))
+        );
+
+        let endpoints = [reachable("official", 100), reachable("bmclapi", 300)];
+        assert_eq!(
+            recommend(&endpoints),
+            (
+                Some("official".to_string()),
+                Some(MirrorMode::OfficialFirst)
+            )
+        );
+
+        // 有任一地址不可用的下载源不参与比较
+        let failed = EndpointReport {
+            error: Some("连接错误".to_string()),
+            ..reachable("bmclapi", 10)
+        };
+        let endpoints = [reachable("official", 300), reachable("bmclapi", 10), failed];
+        assert_eq!(
+            recommend(&endpoints),
+            (Some("official".to_string()), Some(MirrorMode::OfficialOnly))
+        );
+        assert_eq!(recommend(&[]), (None, None));
+    }
+}
@@ -6,8 +6,13 @@ use std::io;
 pub enum NetworkError {
     /// IO错误
     IoError(io::Error),
-    /// HTTP请求错误
-    HttpError(String),
+    /// 服务器返回了非2xx状态码
+    HttpError {
+        /// 状态码
+        status: u16,
+        /// 响应体的开头部分，响应体为空时为`None`
+        body: Option<String>,
+    },
     /// 无效的URL
     InvalidUrl(String),
     /// 请求超时
@@ -16,6 +21,17 @@ pub enum NetworkError {
     ConnectionError(String),
     /// 响应解析错误
     ResponseParseError(String),
+    /// 缓存中没有该请求的响应
+    CacheMiss(String),
+    /// 重试后仍然失败，记录了每次尝试的结果
+    RetriesExhausted {
+        attempts: Vec<String>,
+        source: Box<NetworkError>,
+    },
+    /// 无效的参数或配置
+    InvalidArgument(String),
+    /// 请求已被取消
+    Cancelled,
     /// 其他错误
     Other(String),
 }
@@ -24,17 +40,59 @@ impl fmt::Display for NetworkError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::IoError(err) => write!(f, "IO错误: {}", err),
-            Self::HttpError(err) => write!(f, "HTTP请求错误: {}", err),
+            Self::HttpError { status, .. } => write!(f, "HTTP错误: {}", status),
             Self::InvalidUrl(url) => write!(f, "无效的URL: {}", url),
             Self::Timeout => write!(f, "请求超时"),
             Self::ConnectionError(err) => write!(f, "连接错误: {}", err),
             Self::ResponseParseError(err) => write!(f, "响应解析错误: {}", err),
+            Self::CacheMiss(url) => write!(f, "缓存中没有该请求的响应: {}", url),
+            Self::RetriesExhausted { attempts, source } => write!(
+                f,
+                "{}（共尝试{}次: {}）",
+                source,
+                attempts.len(),
+                attempts.join("; ")
+            ),
+            Self::InvalidArgument(err) => write!(f, "无效的参数: {}", err),
+            Self::Cancelled => write!(f, "请求已取消"),
             Self::Other(err) => write!(f, "其他错误: {}", err),
         }
     }
 }
 
-impl std::error::Error for NetworkError {}
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            Self::RetriesExhausted { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// 错误信息中保留的响应体最大字符数
+const BODY_EXCERPT_CHARS: usize = 1024;
+
+impl NetworkError {
+    /// 是否为可以重试的错误（连接错误和超时）
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Timeout | Self::ConnectionError(_))
+    }
+
+    /// 根据状态码和响应体生成错误，响应体只保留开头部分
+    pub fn http_status(status: u16, body: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(body);
+        let text = text.trim();
+        let body = (!text.is_empty()).then(|| {
+            let mut excerpt: String = text.chars().take(BODY_EXCERPT_CHARS).collect();
+            if text.chars().nth(BODY_EXCERPT_CHARS).is_some() {
+                excerpt.push('…');
+            }
+            excerpt
+        });
+        Self::HttpError { status, body }
+    }
+}
 
 impl From<io::Error> for NetworkError {
     fn from(err: io::Error) -> Self {
@@ -46,12 +104,15 @@ impl From<reqwest::Error> for NetworkError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
             Self::Timeout
-        } else if err.is_connect() {
+        } else if err.is_connect() || err.is_request() || err.is_body() {
             Self::ConnectionError(err.to_string())
         } else if err.is_decode() {
             Self::ResponseParseError(err.to_string())
-        } else if err.is_status() {
-            Self::HttpError(format!("状态码错误: {}", err))
+        } else if let Some(status) = err.status() {
+            Self::HttpError {
+                status: status.as_u16(),
+                body: None,
+            }
         } else {
             Self::Other(err.to_string())
         }
@@ -60,7 +121,13 @@ impl From<reqwest::Error> for NetworkError {
 
 impl From<reqwest::header::InvalidHeaderValue> for NetworkError {
     fn from(err: reqwest::header::InvalidHeaderValue) -> Self {
-        Self::Other(format!("无效的请求头: {}", err))
+        Self::InvalidArgument(format!("无效的请求头: {}", err))
+    }
+}
+
+impl From<reqwest::header::InvalidHeaderName> for NetworkError {
+    fn from(err: reqwest::header::InvalidHeaderName) -> Self {
+        Self::InvalidArgument(format!("无效的请求头名称: {}", err))
     }
 }
 
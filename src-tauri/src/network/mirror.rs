@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::network::error::NetworkError;
+
+/// BMCLAPI镜像地址
+const BMCLAPI: &str = "https://bmclapi2.bangbang93.com";
+
+/// 镜像的使用方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorMode {
+    /// 只使用官方地址
+    #[default]
+    OfficialOnly,
+    /// 优先使用镜像，镜像不可用时使用官方地址
+    MirrorFirst,
+    /// 优先使用官方地址，官方地址不可用时使用镜像
+    OfficialFirst,
+}
+
+/// 镜像改写规则
+///
+/// `from`为不带协议的主机和路径前缀，如`libraries.minecraft.net`；
+/// 匹配的地址将前缀替换为`to`，如`https://bmclapi2.bangbang93.com/maven`，其余路径和查询参数保持不变。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorRule {
+    /// 官方地址的主机和路径前缀
+    pub from: String,
+    /// 镜像地址前缀
+    pub to: String,
+}
+
+impl MirrorRule {
+    /// 创建一条改写规则
+    fn new(from: &str, to: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    /// 规则的前缀，忽略末尾的斜杠
+    fn prefix(&self) -> &str {
+        self.from.trim_end_matches('/')
+    }
+
+    /// 改写地址，不匹配时返回`None`
+    fn rewrite(&self, url: &Url) -> Option<Url> {
+        let host = url.host_str()?;
+        let location = match url.port() {
+            Some(port) => format!("{}:{}{}", host, port, url.path()),
+            None => format!("{}{}", host, url.path()),
+        };
+
+        // 前缀只在路径分隔处匹配，避免`example.com`匹配到`example.com.cn`
+        let rest = location.strip_prefix(self.prefix())?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+
+        let mut rewritten = format!("{}{}", self.to.trim_end_matches('/'), rest);
+        if let Some(query) = url.query() {
+            rewritten.push('?');
+            rewritten.push_str(query);
+        }
+        Url::parse(&rewritten).ok()
+    }
+}
+
+/// 镜像配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MirrorConfig {
+    /// 镜像的使用方式
+    pub mode: MirrorMode,
+    /// 改写规则，多条规则匹配时使用前缀最长的一条
+    pub rules: Vec<MirrorRule>,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            mode: MirrorMode::default(),
+            rules: bmclapi_rules(),
+        }
+    }
+}
+
+impl MirrorConfig {
+    /// 检查改写规则是否有效
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        for rule in &self.rules {
+            if rule.prefix().is_empty() || rule.from.contains("://") {
+                return Err(NetworkError::InvalidArgument(format!(
+                    "镜像规则的前缀不能为空且不能包含协议: {}",
+                    rule.from
+                )));
+            }
+            match Url::parse(&rule.to) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => {
+                    return Err(NetworkError::InvalidUrl(format!(
+                        "镜像地址必须是HTTP或HTTPS地址: {}",
+                        rule.to
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 地址对应的镜像地址，没有匹配的规则时返回`None`
+    pub fn rewrite(&self, url: &Url) -> Option<Url> {
+        self.rules
+            .iter()
+            .filter_map(|rule| Some((rule.prefix().len(), rule.rewrite(url)?)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, url)| url)
+    }
+
+    /// 按使用方式排列的候选地址，依次尝试，至少包含一个地址
+    pub fn candidates(&self, url: &Url) -> Vec<Url> {
+        let mirror = match self.mode {
+            MirrorMode::OfficialOnly => None,
+            _ => self.rewrite(url),
+        };
+
+        match (self.mode, mirror) {
+            (MirrorMode::MirrorFirst, Some(mirror)) => vec![mirror, url.clone()],
+            (MirrorMode::OfficialFirst, Some(mirror)) => vec![url.clone(), mirror],
+            _ => vec![url.clone()],
+        }
+    }
+}
+
+/// BMCLAPI的默认改写规则
+fn bmclapi_rules() -> Vec<MirrorRule> {
+    let maven = format!("{}/maven", BMCLAPI);
+    vec![
+        MirrorRule::new("piston-meta.mojang.com", BMCLAPI),
+        MirrorRule::new("piston-data.mojang.com", BMCLAPI),
+        MirrorRule::new("launchermeta.mojang.com", BMCLAPI),
+        MirrorRule::new("launcher.mojang.com", BMCLAPI),
+        MirrorRule::new(
+            "resources.download.minecraft.net",
+            &format!("{}/assets", BMCLAPI),
+        ),
+        MirrorRule::new("libraries.minecraft.net", &maven),
+        MirrorRule::new("maven.minecraftforge.net", &maven),
+        MirrorRule::new("files.minecraftforge.net/maven", &maven),
+        MirrorRule::new("maven.neoforged.net/releases", &maven),
+        MirrorRule::new("maven.fabricmc.net", &maven),
+        MirrorRule::new("meta.fabricmc.net", &format!("{}/fabric-meta", BMCLAPI)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn config(mode: MirrorMode, rules: &[(&str, &str)]) -> MirrorConfig {
+        MirrorConfig {
+            mode,
+            rules: rules
+                .iter()
+                .map(|(from, to)| MirrorRule::new(from, to))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rewrites_only_at_path_boundaries() {
+        let rule = MirrorRule::new("a.com/foo", "https://mirror.com/bar");
+
+        assert_eq!(
+            rule.rewrite(&url("https://a.com/foo/x.jar?v=1")),
+            Some(url("https://mirror.com/bar/x.jar?v=1"))
+        );
+        assert_eq!(
+            rule.rewrite(&url("https://a.com/foo")),
+            Some(url("https://mirror.com/bar"))
+        );
+        assert_eq!(rule.rewrite(&url("https://a.com/foobar")), None);
+        assert_eq!(rule.rewrite(&url("https://a.com/other/foo")), None);
+
+        let host = MirrorRule::new("example.com", "https://mirror.com");
+        assert_eq!(host.rewrite(&url("https://example.com.cn/x")), None);
+        assert_eq!(host.rewrite(&url("https://example.com:8080/x")), None);
+    }
+
+    #[test]
+    fn prefers_longest_matching_prefix() {
+        let config = config(
+            MirrorMode::MirrorFirst,
+            &[
+                ("a.com", "https://mirror.com"),
+                ("a.com/maven", "https://mirror.com/maven-mirror"),
+            ],
+        );
+
+        assert_eq!(
+            config.rewrite(&url("https://a.com/maven/lib.jar")),
+            Some(url("https://mirror.com/maven-mirror/lib.jar"))
+        );
+        assert_eq!(
+            config.rewrite(&url("https://a.com/meta.json")),
+            Some(url("https://mirror.com/meta.json"))
+        );
+        assert_eq!(config.rewrite(&url("https://b.com/meta.json")), None);
+    }
+
+    #[test]
+    fn orders_candidates_by_mode() {
+        let official = url("https://a.com/file");
+        let mirror = url("https://mirror.com/file");
+        let rules = [("a.com", "https://mirror.com")];
+
+        let candidates = |mode| config(mode, &rules).candidates(&official);
+        assert_eq!(candidates(MirrorMode::OfficialOnly), vec![official.clone()]);
+        assert_eq!(
+            candidates(MirrorMode::MirrorFirst),
+            vec![mirror.clone(), official.clone()]
+        );
+        assert_eq!(
+            candidates(MirrorMode::OfficialFirst),
+            vec![official.clone(), mirror]
+        );
+
+        // 没有匹配的规则时只有官方地址
+        let other = url("https://b.com/file");
+        assert_eq!(
+            config(MirrorMode::MirrorFirst, &rules).candidates(&other),
+            vec![other]
+        );
+    }
+}
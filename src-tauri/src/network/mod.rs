@@ -1,9 +1,41 @@
 //! 网络模块
 //!
-//! 这个模块提供了HTTP客户端功能，支持自定义UserAgent和常见的HTTP请求方法。
+//! 这个模块提供了HTTP客户端功能，支持按请求设置UserAgent、请求头等选项、代理和常见的HTTP请求方法。
+//! HTTP客户端和下载管理器通过[`SharedClient`]共用同一份配置和连接池，
+//! 元数据等GET请求可以通过[`HttpCache`]缓存到磁盘，
+//! 官方地址可以按[`MirrorConfig`]改写为BMCLAPI等镜像地址，
+//! 额外的CA证书和主机映射通过[`TrustConfig`]设置。
 
+mod cache;
+mod cancel;
 mod client;
+mod config;
+mod diagnostics;
 mod error;
+mod mirror;
+mod multipart;
+mod options;
+mod proxy;
+mod rate_limit;
+mod recorder;
+mod request;
+mod retry;
+mod shared;
+mod stream;
+mod tls;
 
+pub use cache::{CachePolicy, HttpCache};
+pub use cancel::CancelRegistry;
 pub use client::HttpClient;
+pub use config::HttpClientConfig;
+pub use diagnostics::{DiagnosticsConfig, DiagnosticsReport};
 pub use error::NetworkError;
+pub use mirror::MirrorConfig;
+pub use multipart::{MultipartPart, UploadProgress};
+pub use options::RequestOptions;
+pub use proxy::ProxyConfig;
+pub use recorder::RecorderConfig;
+pub use request::{HttpRequest, HttpResponse};
+pub use shared::{ClientSnapshot, SharedClient};
+pub use stream::{StreamAcks, StreamEvent, StreamFormat};
+pub use tls::TrustConfig;
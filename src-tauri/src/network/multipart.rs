@@ -0,0 +1,246 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use bytes::Bytes;
+use futures::{stream, Stream, TryStreamExt};
+use reqwest::multipart::{Form, Part};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+use crate::network::error::NetworkError;
+
+/// 从磁盘读取文件时每次读取的大小
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 表单中的一个部分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MultipartPart {
+    /// 文本字段
+    Text {
+        /// 字段名
+        name: String,
+        /// 字段值
+        value: String,
+    },
+    /// 磁盘上的文件，上传时边读边发送
+    File {
+        /// 字段名
+        name: String,
+        /// 文件路径
+        path: String,
+        /// 文件名，未设置时使用路径中的文件名
+        #[serde(default)]
+        filename: Option<String>,
+        /// MIME类型，未设置时为`application/octet-stream`
+        #[serde(default)]
+        mime: Option<String>,
+    },
+    /// Base64编码的二进制数据
+    Bytes {
+        /// 字段名
+        name: String,
+        /// Base64编码的数据
+        data: String,
+        /// 文件名
+        #[serde(default)]
+        filename: Option<String>,
+        /// MIME类型，未设置时为`application/octet-stream`
+        #[serde(default)]
+        mime: Option<String>,
+    },
+}
+
+/// 上传进度，只统计文件和二进制部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct UploadProgress {
+    /// 已发送的字节数
+    pub sent: u64,
+    /// 需要发送的总字节数
+    pub total: u64,
+}
+
+/// 上传进度的统计
+struct ProgressTracker {
+    /// 已发送的字节数
+    sent: AtomicU64,
+    /// 需要发送的总字节数
+    total: u64,
+    /// 进度回调
+    on_progress: Box<dyn Fn(UploadProgress) + Send + Sync>,
+}
+
+impl ProgressTracker {
+    /// 记录已交给底层连接的数据
+    fn advance(&self, len: usize) {
+        let sent = self.sent.fetch_add(len as u64, Ordering::Relaxed) + len as u64;
+        (self.on_progress)(UploadProgress {
+            sent,
+            total: self.total,
+        });
+    }
+}
+
+/// 构建multipart表单
+///
+/// 文件部分以流的形式读取，不会整体读入内存；发送过程中通过`on_progress`报告进度。
+pub async fn build_form(
+    parts: &[MultipartPart],
+    on_progress: impl Fn(UploadProgress) + Send + Sync + 'static,
+) -> Result<Form, NetworkError> {
+    // 先打开所有文件，确定总大小后再开始统计进度
+    let mut prepared = Vec::with_capacity(parts.len());
+    for part in parts {
+        prepared.push(PreparedPart::prepare(part).await?);
+    }
+
+    let tracker = Arc::new(ProgressTracker {
+        sent: AtomicU64::new(0),
+        total: prepared.iter().map(PreparedPart::len).sum(),
+        on_progress: Box::new(on_progress),
+    });
+
+    let mut form = Form::new();
+    for part in prepared {
+        form = match part {
+            PreparedPart::Text { name, value } => form.text(name, value),
+            PreparedPart::Data {
+                name,
+                filename,
+                mime,
+                source,
+                len,
+            } => {
+                let body = match source {
+                    DataSource::File(file) => Body::wrap_stream(track(file_stream(file), &tracker)),
+                    DataSource::Bytes(data) => Body::wrap_stream(track(
+                        stream::once(async move { Ok::<_, io::Error>(data) }),
+                        &tracker,
+                    )),
+                };
+                let part = Part::stream_with_length(body, len);
+                let part = match filename {
+                    Some(filename) => part.file_name(filename),
+                    None => part,
+                };
+                let part = part
+                    .mime_str(mime.as_deref().unwrap_or("application/octet-stream"))
+                    .map_err(|e| NetworkError::InvalidArgument(format!("无效的MIME类型: {}", e)))?;
+                form.part(name, part)
+            }
+        };
+    }
+
+    Ok(form)
+}
+
+/// 已经打开文件或解码数据的表单部分
+enum PreparedPart {
+    /// 文本字段
+    Text { name: String, value: String },
+    /// 文件或二进制数据
+    Data {
+        name: String,
+        filename: Option<String>,
+        mime: Option<String>,
+        source: DataSource,
+        len: u64,
+    },
+}
+
+/// 文件或二进制数据的来源
+enum DataSource {
+    /// 已打开的文件
+    File(tokio::fs::File),
+    /// 解码后的二进制数据
+    Bytes(Bytes),
+}
+
+impl PreparedPart {
+    /// 打开文件或解码Base64数据
+    async fn prepare(part: &MultipartPart) -> Result<Self, NetworkError> {
+        Ok(match part {
+            MultipartPart::Text { name, value } => Self::Text {
+                name: name.clone(),
+                value: value.clone(),
+            },
+            MultipartPart::File {
+                name,
+                path,
+                filename,
+                mime,
+            } => {
+                let file = tokio::fs::File::open(path).await.map_err(|e| {
+                    NetworkError::IoError(io::Error::new(
+                        e.kind(),
+                        format!("无法打开文件 {}: {}", path, e),
+                    ))
+                })?;
+                let len = file.metadata().await?.len();
+                let filename = filename.clone().or_else(|| {
+                    Path::new(path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                });
+                Self::Data {
+                    name: name.clone(),
+                    filename,
+                    mime: mime.clone(),
+                    source: DataSource::File(file),
+                    len,
+                }
+            }
+            MultipartPart::Bytes {
+                name,
+                data,
+                filename,
+                mime,
+            } => {
+                let data = BASE64.decode(data).map_err(|e| {
+                    NetworkError::InvalidArgument(format!("无效的Base64数据: {}", e))
+                })?;
+                Self::Data {
+                    name: name.clone(),
+                    filename: filename.clone(),
+                    mime: mime.clone(),
+                    len: data.len() as u64,
+                    source: DataSource::Bytes(Bytes::from(data)),
+                }
+            }
+        })
+    }
+
+    /// 计入上传进度的字节数
+    fn len(&self) -> u64 {
+        match self {
+            Self::Text { .. } => 0,
+            Self::Data { len, .. } => *len,
+        }
+    }
+}
+
+/// 按块读取文件
+fn file_stream(file: tokio::fs::File) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    stream::try_unfold(file, |mut file| async move {
+        let mut buf = vec![0; READ_CHUNK_SIZE];
+        let len = file.read(&mut buf).await?;
+        if len == 0 {
+            return Ok(None);
+        }
+        buf.truncate(len);
+        Ok(Some((Bytes::from(buf), file)))
+    })
+}
+
+/// 统计流经的数据量
+fn track(
+    stream: impl Stream<Item = Result<Bytes, io::Error>>,
+    tracker: &Arc<ProgressTracker>,
+) -> impl Stream<Item = Result<Bytes, io::Error>> {
+    let tracker = tracker.clone();
+    stream.inspect_ok(move |chunk| tracker.advance(chunk.len()))
+}
@@ -0,0 +1,71 @@
+use reqwest::header::{HeaderName, HeaderValue, ACCEPT, USER_AGENT};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::network::cache::CachePolicy;
+use crate::network::error::NetworkError;
+
+/// 单个请求的选项，只作用于当前请求，不会修改共享客户端
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestOptions {
+    /// 附加的请求头
+    pub headers: BTreeMap<String, String>,
+    /// 附加的查询参数，按顺序追加到URL后
+    pub query: Vec<(String, String)>,
+    /// 覆盖配置中的UserAgent
+    pub user_agent: Option<String>,
+    /// 覆盖配置中的请求超时时间（秒）
+    pub timeout: Option<u64>,
+    /// Accept请求头
+    pub accept: Option<String>,
+    /// 缓存策略，只作用于GET请求
+    pub cache: CachePolicy,
+    /// 覆盖配置中的最大重试次数
+    pub max_retries: Option<u32>,
+    /// 是否允许重试POST等非幂等请求
+    pub retry_non_idempotent: bool,
+}
+
+impl RequestOptions {
+    /// 将选项应用到请求，未设置超时时间时使用`default_timeout`，两者都没有时不限制总时长
+    pub fn apply(
+        &self,
+        mut builder: RequestBuilder,
+        default_timeout: Option<Duration>,
+    ) -> Result<RequestBuilder, NetworkError> {
+        if !self.query.is_empty() {
+            builder = builder.query(&self.query);
+        }
+
+        for (name, value) in &self.headers {
+            builder = builder.header(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        if let Some(ua) = &self.user_agent {
+            builder = builder.header(USER_AGENT, HeaderValue::from_str(ua)?);
+        }
+        if let Some(accept) = &self.accept {
+            builder = builder.header(ACCEPT, HeaderValue::from_str(accept)?);
+        }
+
+        let timeout = match self.timeout {
+            Some(0) => {
+                return Err(NetworkError::InvalidArgument(
+                    "超时时间必须大于0".to_string(),
+                ))
+            }
+            Some(secs) => Some(Duration::from_secs(secs)),
+            None => default_timeout,
+        };
+
+        Ok(match timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        })
+    }
+}
@@ -0,0 +1,185 @@
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+
+use crate::network::error::NetworkError;
+
+/// 代理模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// 使用系统环境变量（HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY）
+    #[default]
+    System,
+    /// 不使用任何代理
+    Direct,
+    /// 使用手动配置的代理
+    Manual,
+}
+
+/// 代理协议
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyScheme {
+    /// HTTP代理
+    #[default]
+    Http,
+    /// HTTPS代理
+    Https,
+    /// SOCKS5代理（由代理服务器解析域名）
+    Socks5,
+}
+
+impl ProxyScheme {
+    /// 对应的URL协议名
+    fn as_url_scheme(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+            Self::Socks5 => "socks5h",
+        }
+    }
+}
+
+/// 代理配置，同时作用于HTTP客户端和下载管理器
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// 代理模式
+    pub mode: ProxyMode,
+    /// 代理协议（仅手动模式）
+    pub scheme: ProxyScheme,
+    /// 代理服务器地址（仅手动模式）
+    pub host: String,
+    /// 代理服务器端口（仅手动模式）
+    pub port: u16,
+    /// 认证用户名
+    pub username: Option<String>,
+    /// 认证密码，以明文保存在设置文件中，不会返回给前端
+    pub password: Option<String>,
+    /// 不走代理的主机列表，格式同NO_PROXY环境变量（如 `localhost`、`.example.com`、`10.0.0.0/8`）
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// 去掉密码后的配置，用于返回给前端
+    pub fn without_password(&self) -> Self {
+        Self {
+            password: None,
+            ..self.clone()
+        }
+    }
+
+    /// 前端没有传入密码时沿用之前保存的密码，传入空字符串表示清除密码
+    pub fn keep_password(mut self, previous: &ProxyConfig) -> Self {
+        match self.password.as_deref() {
+            None => self.password = previous.password.clone(),
+            Some("") => self.password = None,
+            Some(_) => {}
+        }
+        self
+    }
+
+    /// 将代理配置应用到reqwest客户端构建器
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, NetworkError> {
+        match self.mode {
+            // reqwest默认会读取系统环境变量中的代理设置
+            ProxyMode::System => Ok(builder),
+            ProxyMode::Direct => Ok(builder.no_proxy()),
+            ProxyMode::Manual => Ok(builder.proxy(self.build_proxy()?)),
+        }
+    }
+
+    /// 构建手动模式下的代理
+    fn build_proxy(&self) -> Result<Proxy, NetworkError> {
+        let host = self.host.trim();
+        if host.is_empty() || self.port == 0 {
+            return Err(NetworkError::InvalidUrl(format!(
+                "代理地址无效: {}:{}",
+                host, self.port
+            )));
+        }
+
+        let mut proxy = Proxy::all(self.proxy_url(host))
+            .map_err(|e| NetworkError::InvalidUrl(e.to_string()))?;
+
+        if let Some(username) = self.username.as_deref().filter(|u| !u.is_empty()) {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or(""));
+        }
+
+        Ok(proxy.no_proxy(NoProxy::from_string(&self.no_proxy.join(","))))
+    }
+
+    /// 代理URL，IPv6地址需要加上方括号
+    fn proxy_url(&self, host: &str) -> String {
+        let scheme = self.scheme.as_url_scheme();
+        if host.contains(':') && !host.starts_with('[') {
+            format!("{}://[{}]:{}", scheme, host, self.port)
+        } else {
+            format!("{}://{}:{}", scheme, host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(host: &str) -> ProxyConfig {
+        ProxyConfig {
+            mode: ProxyMode::Manual,
+            host: host.to_string(),
+            port: 8080,
+            ..ProxyConfig::default()
+        }
+    }
+
+    #[test]
+    fn brackets_ipv6_hosts() {
+        assert_eq!(manual("::1").proxy_url("::1"), "http://[::1]:8080");
+        assert_eq!(manual("[::1]").proxy_url("[::1]"), "http://[::1]:8080");
+        assert_eq!(
+            manual("127.0.0.1").proxy_url("127.0.0.1"),
+            "http://127.0.0.1:8080"
+        );
+        assert!(manual("::1").build_proxy().is_ok());
+        assert!(manual("fe80::1").build_proxy().is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_host_or_port() {
+        assert!(manual(" ").build_proxy().is_err());
+        let config = ProxyConfig {
+            port: 0,
+            ..manual("127.0.0.1")
+        };
+        assert!(config.build_proxy().is_err());
+    }
+
+    #[test]
+    fn keeps_saved_password() {
+        let saved = ProxyConfig {
+            username: Some("user".to_string()),
+            password: Some("secret".to_string()),
+            ..manual("127.0.0.1")
+        };
+        assert_eq!(saved.without_password().password, None);
+
+        let updated = saved.without_password().keep_password(&saved);
+        assert_eq!(updated.password.as_deref(), Some("secret"));
+
+        let changed = ProxyConfig {
+            password: Some("new".to_string()),
+            ..saved.clone()
+        };
+        assert_eq!(
+            changed.keep_password(&saved).password.as_deref(),
+            Some("new")
+        );
+
+        let cleared = ProxyConfig {
+            password: Some(String::new()),
+            ..saved.clone()
+        };
+        assert_eq!(cleared.keep_password(&saved).password, None);
+    }
+}
@@ -0,0 +1,329 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use url::Url;
+
+/// 剩余请求次数的响应头
+const REMAINING_HEADERS: [&str; 2] = ["x-ratelimit-remaining", "ratelimit-remaining"];
+/// 限额重置时间的响应头
+const RESET_HEADERS: [&str; 2] = ["x-ratelimit-reset", "ratelimit-reset"];
+/// 超过该值的重置时间视为UNIX时间戳，否则视为剩余秒数
+const EPOCH_THRESHOLD: u64 = 1_000_000_000;
+/// 429响应没有Retry-After时的等待时间
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+/// 单次等待的最长时间，避免异常的响应头让请求长时间挂起
+const MAX_WAIT: Duration = Duration::from_secs(60);
+/// 收到429/503后自动重发的最大次数
+const MAX_RATE_LIMIT_RETRIES: usize = 3;
+
+/// 单个主机的限流状态
+#[derive(Debug, Default)]
+struct HostState {
+    /// 当前窗口剩余的请求次数，未知时为`None`
+    remaining: Option<u64>,
+    /// 当前窗口的重置时间
+    reset_at: Option<Instant>,
+    /// 收到429/503后暂停发送的截止时间
+    blocked_until: Option<Instant>,
+}
+
+impl HostState {
+    /// 尝试占用一次请求额度，额度不足时返回需要等待的时间
+    fn try_acquire(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.blocked_until = None;
+        }
+
+        if let Some(reset_at) = self.reset_at {
+            if reset_at <= now {
+                // 窗口已重置，额度未知，等待下一个响应更新
+                self.remaining = None;
+                self.reset_at = None;
+            } else if self.remaining == Some(0) {
+                return Some(reset_at - now);
+            }
+        }
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(1);
+        }
+        None
+    }
+}
+
+/// 按主机限流
+///
+/// 从响应头（`X-Ratelimit-Remaining`/`X-Ratelimit-Reset`）中学习各主机的请求额度，
+/// 额度用完时让后续请求排队等待窗口重置；收到429/503时按`Retry-After`暂停该主机的所有请求。
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    /// 各主机的限流状态
+    hosts: Arc<Mutex<HashMap<String, HostState>>>,
+}
+
+impl RateLimiter {
+    /// 等待主机有可用额度后发送请求
+    ///
+    /// 收到429/503时等待`Retry-After`后自动重发，请求体无法复制时直接返回该响应。
+    pub async fn execute(
+        &self,
+        client: &reqwest::Client,
+        request: Request,
+    ) -> Result<Response, reqwest::Error> {
+        let url = request.url().clone();
+        let mut request = request;
+        let mut retries = 0;
+
+        loop {
+            let retry = request.try_clone();
+
+            self.acquire(&url).await;
+            let response = client.execute(request).await?;
+            let retry_after = self
+                .observe(&url, response.status(), response.headers())
+                .await;
+
+            match (retry_after, retry) {
+                (Some(_), Some(retry)) if retries < MAX_RATE_LIMIT_RETRIES => {
+                    // 等待由下一次acquire完成
+                    retries += 1;
+                    request = retry;
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// 等待主机有可用的请求额度
+    async fn acquire(&self, url: &Url) {
+        let Some(host) = host_key(url) else {
+            return;
+        };
+
+        loop {
+            let wait = {
+                let mut hosts = self.hosts.lock().await;
+                hosts
+                    .entry(host.clone())
+                    .or_default()
+                    .try_acquire(Instant::now())
+            };
+
+            match wait {
+                Some(wait) => tokio::time::sleep(wait.min(MAX_WAIT)).await,
+                None => return,
+            }
+        }
+    }
+
+    /// 根据响应更新主机的限流状态，需要等待后重试时返回等待时间
+    async fn observe(
+        &self,
+        url: &Url,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        let host = host_key(url)?;
+        let now = Instant::now();
+        let system_now = SystemTime::now();
+
+        let mut hosts = self.hosts.lock().await;
+        let state = hosts.entry(host).or_default();
+
+        if let Some(remaining) = header_u64(headers, &REMAINING_HEADERS) {
+            state.remaining = Some(remaining);
+        }
+        if let Some(reset) = header_u64(headers, &RESET_HEADERS) {
+            state.reset_at = Some(now + reset_delay(reset, system_now).min(MAX_WAIT));
+        }
+
+        let retry_after = match status {
+            StatusCode::TOO_MANY_REQUESTS => {
+                Some(retry_after(headers, system_now).unwrap_or(DEFAULT_RETRY_AFTER))
+            }
+            StatusCode::SERVICE_UNAVAILABLE => retry_after(headers, system_now),
+            _ => None,
+        }?
+        .min(MAX_WAIT);
+
+        let until = now + retry_after;
+        if state.blocked_until.is_none_or(|blocked| blocked < until) {
+            state.blocked_until = Some(until);
+        }
+        Some(retry_after)
+    }
+}
+
+/// 限流状态按主机和端口区分
+fn host_key(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(match url.port_or_known_default() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// 读取第一个存在的数字响应头
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names.iter().find_map(|name| {
+        headers
+            .get(*name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| *value >= 0.0)
+            .map(|value| value.ceil() as u64)
+    })
+}
+
+/// 将重置时间转换为等待时间，兼容剩余秒数和UNIX时间戳两种格式
+fn reset_delay(reset: u64, now: SystemTime) -> Duration {
+    if reset < EPOCH_THRESHOLD {
+        return Duration::from_secs(reset);
+    }
+
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Duration::from_secs(reset.saturating_sub(now))
+}
+
+/// 解析Retry-After响应头，支持秒数和HTTP日期两种格式
+fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn waits_for_window_reset_when_quota_is_used_up() {
+        let now = Instant::now();
+        let mut state = HostState {
+            remaining: Some(2),
+            reset_at: Some(now + Duration::from_secs(10)),
+            blocked_until: None,
+        };
+
+        assert_eq!(state.try_acquire(now), None);
+        assert_eq!(state.try_acquire(now), None);
+        assert_eq!(state.remaining, Some(0));
+        assert_eq!(
+            state.try_acquire(now + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+
+        // 窗口重置后额度未知，不再限制
+        assert_eq!(state.try_acquire(now + Duration::from_secs(10)), None);
+        assert_eq!(state.remaining, None);
+        assert_eq!(state.reset_at, None);
+    }
+
+    #[test]
+    fn waits_until_block_expires() {
+        let now = Instant::now();
+        let mut state = HostState {
+            blocked_until: Some(now + Duration::from_secs(5)),
+            ..HostState::default()
+        };
+
+        assert_eq!(
+            state.try_acquire(now + Duration::from_secs(2)),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(state.try_acquire(now + Duration::from_secs(5)), None);
+        assert_eq!(state.blocked_until, None);
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = httpdate::fmt_http_date(now + Duration::from_secs(30));
+        let earlier = httpdate::fmt_http_date(now - Duration::from_secs(30));
+
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", "120")]), now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", &later)]), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after(&headers(&[("retry-after", &earlier)]), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers(&[("retry-after", "soon")]), now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn converts_reset_to_delay() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(reset_delay(30, now), Duration::from_secs(30));
+        assert_eq!(reset_delay(1_700_000_045, now), Duration::from_secs(45));
+        assert_eq!(reset_delay(1_600_000_000, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn reads_first_numeric_header() {
+        let names = &REMAINING_HEADERS;
+
+        assert_eq!(
+            header_u64(&headers(&[("x-ratelimit-remaining", "5")]), names),
+            Some(5)
+        );
+        assert_eq!(
+            header_u64(&headers(&[("ratelimit-remaining", "1.2")]), names),
+            Some(2)
+        );
+        assert_eq!(
+            header_u64(
+                &headers(&[("x-ratelimit-remaining", "3"), ("ratelimit-remaining", "7")]),
+                names
+            ),
+            Some(3)
+        );
+        assert_eq!(
+            header_u64(&headers(&[("x-ratelimit-remaining", "-1")]), names),
+            None
+        );
+        assert_eq!(
+            header_u64(&headers(&[("x-ratelimit-remaining", "many")]), names),
+            None
+        );
+        assert_eq!(header_u64(&HeaderMap::new(), names), None);
+    }
+}
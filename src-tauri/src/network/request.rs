@@ -0,0 +1,166 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::network::cache::{header_pairs, FetchedResponse};
+use crate::network::error::NetworkError;
+use crate::network::options::RequestOptions;
+
+/// 请求体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RequestBody {
+    /// JSON数据
+    Json(serde_json::Value),
+    /// 表单数据（application/x-www-form-urlencoded）
+    Form(Vec<(String, String)>),
+    /// 纯文本
+    Text(String),
+    /// Base64编码的二进制数据
+    Base64(String),
+}
+
+impl RequestBody {
+    /// 将请求体应用到请求
+    ///
+    /// JSON和表单由reqwest设置Content-Type；文本和二进制数据在请求选项中未指定时使用默认值。
+    pub fn apply(
+        &self,
+        builder: RequestBuilder,
+        options: &RequestOptions,
+    ) -> Result<RequestBuilder, NetworkError> {
+        let (builder, default_content_type) = match self {
+            Self::Json(value) => (builder.json(value), None),
+            Self::Form(fields) => (builder.form(fields), None),
+            Self::Text(text) => (
+                builder.body(text.clone()),
+                Some("text/plain; charset=utf-8"),
+            ),
+            Self::Base64(data) => {
+                let data = BASE64.decode(data).map_err(|e| {
+                    NetworkError::InvalidArgument(format!("无效的Base64数据: {}", e))
+                })?;
+                (builder.body(data), Some("application/octet-stream"))
+            }
+        };
+
+        let has_content_type = options
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+
+        Ok(match default_content_type {
+            Some(content_type) if !has_content_type => {
+                builder.header(CONTENT_TYPE, HeaderValue::from_static(content_type))
+            }
+            _ => builder,
+        })
+    }
+}
+
+/// 响应体的读取方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseType {
+    /// 按文本读取
+    #[default]
+    Text,
+    /// 按JSON解析，无法解析时以文本返回
+    Json,
+    /// 按二进制读取，以Base64编码返回
+    Bytes,
+}
+
+/// 通用HTTP请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequest {
+    /// 请求方法，默认为GET
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 请求URL
+    pub url: String,
+    /// 请求选项
+    #[serde(default)]
+    pub options: RequestOptions,
+    /// 请求体
+    #[serde(default)]
+    pub body: Option<RequestBody>,
+    /// 响应体的读取方式
+    #[serde(default)]
+    pub response_type: ResponseType,
+}
+
+/// 默认请求方法
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl HttpRequest {
+    /// 解析请求方法
+    pub fn method(&self) -> Result<Method, NetworkError> {
+        Method::from_bytes(self.method.trim().to_ascii_uppercase().as_bytes())
+            .map_err(|_| NetworkError::InvalidArgument(format!("无效的请求方法: {}", self.method)))
+    }
+}
+
+/// 响应体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ResponseBody {
+    /// 文本
+    Text(String),
+    /// JSON数据
+    Json(serde_json::Value),
+    /// Base64编码的二进制数据
+    Bytes(String),
+}
+
+/// 通用HTTP响应，非2xx状态码同样以响应返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    /// 状态码
+    pub status: u16,
+    /// 状态码是否为2xx
+    pub ok: bool,
+    /// 响应头，同名的头会出现多次
+    pub headers: Vec<(String, String)>,
+    /// 跟随重定向后的最终URL
+    pub url: String,
+    /// 响应体
+    pub body: ResponseBody,
+    /// 是否来自缓存
+    pub from_cache: bool,
+}
+
+impl HttpResponse {
+    /// 根据已读取的响应生成通用HTTP响应
+    pub fn from_fetched(response: FetchedResponse, response_type: ResponseType) -> Self {
+        let text = || String::from_utf8_lossy(&response.body).into_owned();
+
+        let body = match response_type {
+            ResponseType::Text => ResponseBody::Text(text()),
+            ResponseType::Json => {
+                if response.body.iter().all(u8::is_ascii_whitespace) {
+                    ResponseBody::Json(serde_json::Value::Null)
+                } else {
+                    match serde_json::from_slice(&response.body) {
+                        Ok(value) => ResponseBody::Json(value),
+                        Err(_) => ResponseBody::Text(text()),
+                    }
+                }
+            }
+            ResponseType::Bytes => ResponseBody::Bytes(BASE64.encode(&response.body)),
+        };
+
+        Self {
+            status: response.status.as_u16(),
+            ok: response.status.is_success(),
+            headers: header_pairs(&response.headers),
+            url: response.url,
+            body,
+            from_cache: response.from_cache,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 重试配置
+///
+/// 只重试幂等请求，遇到连接错误、超时和5xx响应时按指数退避重试；
+/// POST等非幂等请求只有在请求选项中明确允许时才会重试。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// 最大重试次数，为0时不重试
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间（毫秒）
+    pub initial_backoff_ms: u64,
+    /// 重试等待时间的上限（毫秒）
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// 第`retry`次重试（从0开始）前的等待时间，每次翻倍，不超过上限
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64.checked_shl(retry).unwrap_or(u64::MAX);
+        let millis = self
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms);
+        Duration::from_millis(millis)
+    }
+}
+
+/// 请求方法是否幂等，幂等请求可以安全地重复发送
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(initial_backoff_ms: u64, max_backoff_ms: u64) -> RetryConfig {
+        RetryConfig {
+            max_retries: 2,
+            initial_backoff_ms,
+            max_backoff_ms,
+        }
+    }
+
+    #[test]
+    fn doubles_backoff_up_to_cap() {
+        let config = config(500, 3000);
+        assert_eq!(config.backoff(0), Duration::from_millis(500));
+        assert_eq!(config.backoff(1), Duration::from_millis(1000));
+        assert_eq!(config.backoff(2), Duration::from_millis(2000));
+        assert_eq!(config.backoff(3), Duration::from_millis(3000));
+        assert_eq!(config.backoff(10), Duration::from_millis(3000));
+    }
+
+    #[test]
+    fn caps_backoff_when_multiplier_overflows() {
+        let config = config(500, 8000);
+        assert_eq!(config.backoff(64), Duration::from_millis(8000));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_millis(8000));
+
+        let config = RetryConfig {
+            initial_backoff_ms: u64::MAX / 2,
+            max_backoff_ms: u64::MAX,
+            ..RetryConfig::default()
+        };
+        assert_eq!(config.backoff(4), Duration::from_millis(u64::MAX));
+    }
+
+    #[test]
+    fn classifies_idempotent_methods() {
+        for method in [
+            Method::GET,
+            Method::HEAD,
+            Method::OPTIONS,
+            Method::TRACE,
+            Method::PUT,
+            Method::DELETE,
+        ] {
+            assert!(is_idempotent(&method), "{}", method);
+        }
+        for method in [Method::POST, Method::PATCH, Method::CONNECT] {
+            assert!(!is_idempotent(&method), "{}", method);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+use url::Url;
+
+use crate::network::config::HttpClientConfig;
+use crate::network::error::NetworkError;
+use crate::network::mirror::MirrorConfig;
+use crate::network::proxy::ProxyConfig;
+use crate::network::rate_limit::RateLimiter;
+use crate::network::recorder::TrafficRecorder;
+use crate::network::tls::TrustConfig;
+
+/// 某一时刻的客户端及其配置
+#[derive(Debug, Clone)]
+pub struct ClientSnapshot {
+    /// reqwest客户端，克隆后共享同一个连接池
+    pub client: reqwest::Client,
+    /// 不校验证书的客户端，只用于[`TrustConfig::insecure_hosts`]中的主机
+    pub insecure_client: Option<reqwest::Client>,
+    /// 客户端配置
+    pub config: HttpClientConfig,
+    /// 代理配置
+    pub proxy: ProxyConfig,
+    /// 证书信任和主机解析配置
+    pub trust: TrustConfig,
+    /// 镜像配置
+    pub mirrors: MirrorConfig,
+    /// 按主机限流，修改配置后仍沿用同一个限流状态
+    pub limiter: RateLimiter,
+    /// 流量记录器，修改配置后仍沿用同一份记录
+    pub recorder: TrafficRecorder,
+}
+
+impl ClientSnapshot {
+    /// 发送到该地址的请求使用的客户端
+    pub fn client_for(&self, url: &Url) -> &reqwest::Client {
+        match (&self.insecure_client, url.host_str()) {
+            (Some(client), Some(host)) if self.trust.is_insecure(host) => client,
+            _ => &self.client,
+        }
+    }
+
+    /// 经过限流器发送请求
+    pub async fn send(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.execute(builder.build()?).await
+    }
+
+    /// 经过限流器发送请求，启用流量记录时记录请求和响应
+    pub async fn execute(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let client = self.client_for(request.url());
+        self.execute_with(client, request).await
+    }
+
+    /// 使用指定的客户端发送请求，同样经过限流器和流量记录
+    pub async fn execute_with(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let pending = self.recorder.begin(&request);
+        let mut result = self.limiter.execute(client, request).await;
+        if let Some(pending) = pending {
+            self.recorder.finish(pending, &mut result);
+        }
+        result
+    }
+}
+
+/// 共享的HTTP客户端
+///
+/// HTTP客户端和下载管理器持有同一个实例，使用同一个连接池和限流器。
+/// 修改配置时先构建新客户端再整体替换，配置无效时保持原客户端不变；
+/// 已经发出的请求继续使用取出时的客户端，不受替换影响。
+#[derive(Debug, Clone)]
+pub struct SharedClient {
+    /// 当前的客户端快照
+    current: Arc<watch::Sender<Arc<ClientSnapshot>>>,
+}
+
+impl SharedClient {
+    /// 使用默认配置创建共享客户端
+    pub fn new() -> Self {
+        let config = HttpClientConfig::default();
+        let proxy = ProxyConfig::default();
+        let trust = TrustConfig::default();
+        // 默认配置一般不会构建失败，失败时记录原因并退回reqwest的默认客户端
+        let client = config.build(&proxy, &trust).unwrap_or_else(|e| {
+            eprintln!("使用默认配置创建HTTP客户端失败: {}", e);
+            reqwest::Client::new()
+        });
+
+        Self {
+            current: Arc::new(watch::Sender::new(Arc::new(ClientSnapshot {
+                client,
+                insecure_client: None,
+                config,
+                proxy,
+                trust,
+                mirrors: MirrorConfig::default(),
+                limiter: RateLimiter::default(),
+                recorder: TrafficRecorder::default(),
+            }))),
+        }
+    }
+
+    /// 获取当前的客户端快照
+    pub fn snapshot(&self) -> Arc<ClientSnapshot> {
+        self.current.borrow().clone()
+    }
+
+    /// 获取流量记录器
+    pub fn recorder(&self) -> TrafficRecorder {
+        self.current.borrow().recorder.clone()
+    }
+
+    /// 获取当前的客户端配置
+    pub fn config(&self) -> HttpClientConfig {
+        self.current.borrow().config.clone()
+    }
+
+    /// 获取当前的代理配置
+    pub fn proxy(&self) -> ProxyConfig {
+        self.current.borrow().proxy.clone()
+    }
+
+    /// 获取当前的证书信任和主机解析配置
+    pub fn trust(&self) -> TrustConfig {
+        self.current.borrow().trust.clone()
+    }
+
+    /// 获取当前的镜像配置
+    pub fn mirrors(&self) -> MirrorConfig {
+        self.current.borrow().mirrors.clone()
+    }
+
+    /// 修改客户端配置
+    pub fn set_config(&self, config: HttpClientConfig) -> Result<(), NetworkError> {
+        self.update(|snapshot| snapshot.config = config)
+    }
+
+    /// 修改代理配置
+    pub fn set_proxy(&self, proxy: ProxyConfig) -> Result<(), NetworkError> {
+        self.update(|snapshot| snapshot.proxy = proxy)
+    }
+
+    /// 修改证书信任和主机解析配置
+    pub fn set_trust(&self, trust: TrustConfig) -> Result<(), NetworkError> {
+        trust.validate()?;
+        if !trust.insecure_hosts.is_empty() {
+            eprintln!(
+                "警告: 已关闭以下主机的证书校验，连接可能被窃听或篡改: {}",
+                trust.insecure_hosts.join(", ")
+            );
+        }
+        self.update(|snapshot| snapshot.trust = trust)
+    }
+
+    /// 修改镜像配置，只影响之后发出的请求和开始的下载
+    pub fn set_mirrors(&self, mirrors: MirrorConfig) -> Result<(), NetworkError> {
+        mirrors.validate()?;
+        self.current.send_modify(|current| {
+            let mut snapshot = ClientSnapshot::clone(current);
+            snapshot.mirrors = mirrors;
+            *current = Arc::new(snapshot);
+        });
+        Ok(())
+    }
+
+    /// 在当前配置的基础上修改并重建客户端，构建失败时保持原客户端不变
+    fn update(&self, modify: impl FnOnce(&mut ClientSnapshot)) -> Result<(), NetworkError> {
+        let mut result = Ok(());
+        self.current.send_if_modified(|current| {
+            let mut snapshot = ClientSnapshot::clone(current);
+            modify(&mut snapshot);
+
+            match build_clients(&snapshot.config, &snapshot.proxy, &snapshot.trust) {
+                Ok((client, insecure_client)) => {
+                    snapshot.client = client;
+                    snapshot.insecure_client = insecure_client;
+                    *current = Arc::new(snapshot);
+                    true
+                }
+                Err(e) => {
+                    result = Err(e);
+                    false
+                }
+            }
+        });
+        result
+    }
+}
+
+/// 构建客户端，需要跳过证书校验的主机单独使用一个客户端
+fn build_clients(
+    config: &HttpClientConfig,
+    proxy: &ProxyConfig,
+    trust: &TrustConfig,
+) -> Result<(reqwest::Client, Option<reqwest::Client>), NetworkError> {
+    let client = config.build(proxy, trust)?;
+    // 已经全局关闭证书校验时不需要单独的客户端
+    let insecure_client = if trust.insecure_hosts.is_empty() || config.accept_invalid_certs {
+        None
+    } else {
+        Some(config.build_insecure(proxy, trust)?)
+    };
+    Ok((client, insecure_client))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 内容无效的PEM证书
+    const INVALID_PEM: &str = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----";
+
+    #[test]
+    fn keeps_snapshot_when_trust_is_invalid() {
+        let shared = SharedClient::new();
+        let before = shared.snapshot();
+
+        let result = shared.set_trust(TrustConfig {
+            extra_ca_certs: vec![INVALID_PEM.to_string()],
+            ..TrustConfig::default()
+        });
+
+        assert!(matches!(result, Err(NetworkError::InvalidArgument(_))));
+        assert!(Arc::ptr_eq(&before, &shared.snapshot()));
+        assert_eq!(shared.trust(), TrustConfig::default());
+    }
+
+    #[test]
+    fn keeps_snapshot_when_config_fails_to_build() {
+        let shared = SharedClient::new();
+        let before = shared.snapshot();
+
+        let mut config = shared.config();
+        config.user_agent = " ".to_string();
+        let result = shared.set_config(config);
+
+        assert!(matches!(result, Err(NetworkError::InvalidArgument(_))));
+        assert!(Arc::ptr_eq(&before, &shared.snapshot()));
+        assert_eq!(shared.config(), HttpClientConfig::default());
+    }
+
+    #[test]
+    fn uses_insecure_client_only_for_listed_hosts() {
+        let shared = SharedClient::new();
+        shared
+            .set_trust(TrustConfig {
+                insecure_hosts: vec!["self-signed.local".to_string()],
+                ..TrustConfig::default()
+            })
+            .unwrap();
+        let snapshot = shared.snapshot();
+        let insecure = snapshot.insecure_client.as_ref().unwrap();
+
+        let url = Url::parse("https://SELF-SIGNED.local/file").unwrap();
+        assert!(std::ptr::eq(snapshot.client_for(&url), insecure));
+        let url = Url::parse("https://example.com/file").unwrap();
+        assert!(std::ptr::eq(snapshot.client_for(&url), &snapshot.client));
+    }
+}
@@ -0,0 +1,323 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::network::cache::header_pairs;
+use crate::network::error::NetworkError;
+
+/// 攒够该大小的数据后再发送一次，避免过多的小消息
+const CHUNK_SIZE: usize = 64 * 1024;
+/// 前端未确认的数据块达到该数量时暂停读取
+const ACK_WINDOW: u64 = 8;
+/// 等待前端确认的最长时间，页面刷新等情况下前端不再确认时结束读取并释放连接
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 流式响应的发送格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamFormat {
+    /// 二进制数据，以Base64编码发送
+    #[default]
+    Bytes,
+    /// 按UTF-8解码的文本，多字节字符不会被拆开
+    Text,
+    /// 每行一个JSON值（NDJSON），按行解析后发送
+    Ndjson,
+}
+
+/// 一个数据块的内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StreamData {
+    /// Base64编码的二进制数据
+    Bytes(String),
+    /// 文本
+    Text(String),
+    /// 解析后的JSON记录
+    Records(Vec<serde_json::Value>),
+}
+
+/// 通过通道发送给前端的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// 收到响应头，非2xx状态码同样继续发送响应体
+    Started {
+        /// 状态码
+        status: u16,
+        /// 响应头
+        headers: Vec<(String, String)>,
+        /// 跟随重定向后的最终URL
+        url: String,
+        /// 响应体大小，服务器未提供时为`None`
+        total: Option<u64>,
+    },
+    /// 一个数据块，前端处理后需要按序号确认
+    Chunk {
+        /// 序号，从1开始
+        seq: u64,
+        /// 目前已接收的字节数
+        received: u64,
+        /// 响应体大小
+        total: Option<u64>,
+        /// 数据
+        data: StreamData,
+    },
+}
+
+/// 各个流的确认进度
+///
+/// 前端处理完一个数据块后确认其序号，发送方在未确认的数据块过多时暂停读取响应，
+/// 从而将前端的处理速度反馈到网络读取上。
+#[derive(Debug, Clone, Default)]
+pub struct StreamAcks {
+    /// 流ID到已确认序号的映射
+    streams: Arc<Mutex<HashMap<String, watch::Sender<u64>>>>,
+}
+
+impl StreamAcks {
+    /// 登记一个流，返回的接收端被丢弃时自动移除登记
+    pub fn register(&self, stream_id: &str) -> Result<AckReceiver, NetworkError> {
+        let mut streams = self.lock();
+        if streams.contains_key(stream_id) {
+            return Err(NetworkError::InvalidArgument(format!(
+                "流ID已存在: {}",
+                stream_id
+            )));
+        }
+
+        let (tx, rx) = watch::channel(0);
+        streams.insert(stream_id.to_string(), tx);
+        Ok(AckReceiver {
+            acks: self.clone(),
+            stream_id: stream_id.to_string(),
+            rx,
+        })
+    }
+
+    /// 确认序号及之前的数据块，流不存在时返回`false`
+    pub fn ack(&self, stream_id: &str, seq: u64) -> bool {
+        match self.lock().get(stream_id) {
+            Some(tx) => {
+                tx.send_if_modified(|acked| {
+                    let advanced = seq > *acked;
+                    *acked = (*acked).max(seq);
+                    advanced
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 锁定登记表，持有锁的线程崩溃后继续使用其中的数据
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, watch::Sender<u64>>> {
+        self.streams
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 一个流的确认进度
+#[derive(Debug)]
+pub struct AckReceiver {
+    /// 所属的登记表
+    acks: StreamAcks,
+    /// 流ID
+    stream_id: String,
+    /// 已确认的序号
+    rx: watch::Receiver<u64>,
+}
+
+impl AckReceiver {
+    /// 等待未确认的数据块少于窗口大小，超过`timeout`仍未确认时返回错误
+    async fn wait_window(&mut self, seq: u64, timeout: Duration) -> Result<(), NetworkError> {
+        tokio::time::timeout(timeout, self.rx.wait_for(|acked| seq <= acked + ACK_WINDOW))
+            .await
+            .map_err(|_| {
+                NetworkError::Other(format!(
+                    "等待前端确认数据块超时: {}",
+                    seq.saturating_sub(ACK_WINDOW)
+                ))
+            })?
+            .map(|_| ())
+            .map_err(|_| NetworkError::Cancelled)
+    }
+}
+
+impl Drop for AckReceiver {
+    fn drop(&mut self) {
+        self.acks.lock().remove(&self.stream_id);
+    }
+}
+
+/// 读取响应体并分块发送
+///
+/// 每个数据块最多等待`read_timeout`，超时返回[`NetworkError::Timeout`]；
+/// 前端超过[`ACK_TIMEOUT`]没有确认时同样结束读取。返回接收的总字节数。
+pub async fn forward(
+    response: reqwest::Response,
+    format: StreamFormat,
+    read_timeout: Duration,
+    mut acks: AckReceiver,
+    mut emit: impl FnMut(StreamEvent) -> Result<(), NetworkError>,
+) -> Result<u64, NetworkError> {
+    let total = response.content_length();
+    emit(StreamEvent::Started {
+        status: response.status().as_u16(),
+        headers: header_pairs(response.headers()),
+        url: response.url().to_string(),
+        total,
+    })?;
+
+    let mut stream = response.bytes_stream();
+    let mut pending = Vec::new();
+    let mut received = 0u64;
+    let mut seq = 0u64;
+
+    loop {
+        let chunk = tokio::time::timeout(read_timeout, stream.next())
+            .await
+            .map_err(|_| NetworkError::Timeout)?;
+        let finished = chunk.is_none();
+        if let Some(chunk) = chunk {
+            let chunk = chunk?;
+            received += chunk.len() as u64;
+            pending.extend_from_slice(&chunk);
+        }
+
+        if pending.len() >= CHUNK_SIZE || finished {
+            if let Some(data) = take_data(format, &mut pending, finished)? {
+                seq += 1;
+                emit(StreamEvent::Chunk {
+                    seq,
+                    received,
+                    total,
+                    data,
+                })?;
+                acks.wait_window(seq, ACK_TIMEOUT).await?;
+            }
+        }
+
+        if finished {
+            return Ok(received);
+        }
+    }
+}
+
+/// 从缓冲区中取出可以发送的数据，留下不完整的字符或行，没有可发送的数据时返回`None`
+fn take_data(
+    format: StreamFormat,
+    pending: &mut Vec<u8>,
+    finished: bool,
+) -> Result<Option<StreamData>, NetworkError> {
+    if pending.is_empty() {
+        return Ok(None);
+    }
+
+    let data = match format {
+        StreamFormat::Bytes => StreamData::Bytes(BASE64.encode(std::mem::take(pending))),
+        StreamFormat::Text => {
+            let split = match std::str::from_utf8(pending) {
+                Ok(_) => pending.len(),
+                // 末尾是不完整的多字节字符，留到下一块
+                Err(e) if e.error_len().is_none() && !finished => e.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            let rest = pending.split_off(split);
+            let text = String::from_utf8_lossy(&std::mem::replace(pending, rest)).into_owned();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            StreamData::Text(text)
+        }
+        StreamFormat::Ndjson => {
+            let split = match pending.iter().rposition(|b| *b == b'\n') {
+                Some(index) => index + 1,
+                None if finished => pending.len(),
+                None => return Ok(None),
+            };
+            let rest = pending.split_off(split);
+            let lines = std::mem::replace(pending, rest);
+
+            let records = lines
+                .split(|b| *b == b'\n')
+                .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+                .map(serde_json::from_slice)
+                .collect::<Result<Vec<_>, _>>()?;
+            if records.is_empty() {
+                return Ok(None);
+            }
+            StreamData::Records(records)
+        }
+    };
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORT_TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn waits_only_when_window_is_full() {
+        let acks = StreamAcks::default();
+        let mut rx = acks.register("a").unwrap();
+        assert!(rx.wait_window(ACK_WINDOW, SHORT_TIMEOUT).await.is_ok());
+
+        let waiting = rx.wait_window(ACK_WINDOW + 2, Duration::from_secs(5));
+        let ack = async {
+            tokio::task::yield_now().await;
+            assert!(acks.ack("a", 2));
+        };
+        let (result, _) = tokio::join!(waiting, ack);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn times_out_without_acks() {
+        let acks = StreamAcks::default();
+        let mut rx = acks.register("a").unwrap();
+        let result = rx.wait_window(ACK_WINDOW + 1, SHORT_TIMEOUT).await;
+        assert!(matches!(result, Err(NetworkError::Other(_))));
+    }
+
+    #[tokio::test]
+    async fn unregisters_on_drop() {
+        let acks = StreamAcks::default();
+        let rx = acks.register("a").unwrap();
+        assert!(acks.register("a").is_err());
+        drop(rx);
+        assert!(!acks.ack("a", 1));
+        assert!(acks.register("a").is_ok());
+    }
+
+    #[test]
+    fn keeps_incomplete_text_and_lines() {
+        let mut pending = "中".as_bytes()[..2].to_vec();
+        assert_eq!(
+            take_data(StreamFormat::Text, &mut pending, false).unwrap(),
+            None
+        );
+        pending.push("中".as_bytes()[2]);
+        assert_eq!(
+            take_data(StreamFormat::Text, &mut pending, false).unwrap(),
+            Some(StreamData::Text("中".to_string()))
+        );
+
+        let mut pending = b"{\"a\":1}\n{\"b\"".to_vec();
+        assert_eq!(
+            take_data(StreamFormat::Ndjson, &mut pending, false).unwrap(),
+            Some(StreamData::Records(vec![serde_json::json!({"a": 1})]))
+        );
+        assert_eq!(pending, b"{\"b\"");
+        assert!(take_data(StreamFormat::Ndjson, &mut pending, true).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use reqwest::{Certificate, ClientBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use tokio_native_tls::native_tls;
+
+use crate::network::error::NetworkError;
+
+/// PEM证书的结束标记
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// 证书信任和主机解析配置，同时作用于HTTP客户端和下载管理器
+///
+/// 用于企业或校园网中解密TLS流量的代理，以及使用私有CA的自建镜像。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustConfig {
+    /// 额外信任的CA证书（PEM格式），每一项可以包含多个证书
+    pub extra_ca_certs: Vec<String>,
+    /// 不校验证书的主机（不安全，连接可能被窃听或篡改）
+    pub insecure_hosts: Vec<String>,
+    /// 主机到IP地址的静态映射，优先于DNS解析
+    pub host_overrides: BTreeMap<String, String>,
+}
+
+impl TrustConfig {
+    /// 检查配置是否有效
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        self.certificates()?;
+        self.resolved_hosts()?;
+        if self
+            .insecure_hosts
+            .iter()
+            .any(|host| host.trim().is_empty())
+        {
+            return Err(NetworkError::InvalidArgument("主机名不能为空".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 将额外的CA证书和主机映射应用到reqwest客户端构建器
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, NetworkError> {
+        for pem in self.pem_blocks() {
+            builder = builder.add_root_certificate(Certificate::from_pem(pem.as_bytes())?);
+        }
+        // DNS不包含端口信息，映射中的端口会被忽略，实际使用URL中的端口
+        for (host, ip) in self.resolved_hosts()? {
+            builder = builder.resolve(&host.to_ascii_lowercase(), SocketAddr::new(ip, 0));
+        }
+        Ok(builder)
+    }
+
+    /// 是否跳过该主机的证书校验
+    pub fn is_insecure(&self, host: &str) -> bool {
+        self.insecure_hosts
+            .iter()
+            .any(|insecure| insecure.trim().eq_ignore_ascii_case(host))
+    }
+
+    /// 主机的静态映射地址
+    pub fn resolve(&self, host: &str) -> Option<IpAddr> {
+        self.host_overrides
+            .iter()
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(host))
+            .and_then(|(_, ip)| ip.trim().parse().ok())
+    }
+
+    /// 解析所有CA证书，用于直接建立TLS连接的场景
+    pub fn certificates(&self) -> Result<Vec<native_tls::Certificate>, NetworkError> {
+        let mut certs = Vec::new();
+        for pem in &self.extra_ca_certs {
+            let blocks = split_pem(pem);
+            if blocks.is_empty() {
+                return Err(NetworkError::InvalidArgument(
+                    "没有找到PEM格式的证书".to_string(),
+                ));
+            }
+            for block in blocks {
+                let cert = native_tls::Certificate::from_pem(block.as_bytes())
+                    .map_err(|e| NetworkError::InvalidArgument(format!("无效的CA证书: {}", e)))?;
+                certs.push(cert);
+            }
+        }
+        Ok(certs)
+    }
+
+    /// 所有证书的PEM文本
+    fn pem_blocks(&self) -> impl Iterator<Item = &str> {
+        self.extra_ca_certs.iter().flat_map(|pem| split_pem(pem))
+    }
+
+    /// 解析主机映射中的IP地址
+    fn resolved_hosts(&self) -> Result<Vec<(&str, IpAddr)>, NetworkError> {
+        self.host_overrides
+            .iter()
+            .map(|(host, ip)| {
+                let host = host.trim();
+                if host.is_empty() {
+                    return Err(NetworkError::InvalidArgument("主机名不能为空".to_string()));
+                }
+                let ip = ip.trim().parse().map_err(|_| {
+                    NetworkError::InvalidArgument(format!("无效的IP地址: {} -> {}", host, ip))
+                })?;
+                Ok((host, ip))
+            })
+            .collect()
+    }
+}
+
+/// 将包含多个证书的PEM文本拆分为单个证书
+fn split_pem(pem: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = pem;
+    while let Some(end) = rest.find(PEM_END) {
+        let (block, next) = rest.split_at(end + PEM_END.len());
+        if let Some(start) = block.find("-----BEGIN CERTIFICATE-----") {
+            blocks.push(&block[start..]);
+        }
+        rest = next;
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trust_with_certs(pem: &str) -> TrustConfig {
+        TrustConfig {
+            extra_ca_certs: vec![pem.to_string()],
+            ..TrustConfig::default()
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_pem() {
+        let missing = trust_with_certs("not a certificate");
+        assert!(matches!(
+            missing.certificates(),
+            Err(NetworkError::InvalidArgument(message)) if message.contains("没有找到")
+        ));
+
+        let invalid =
+            trust_with_certs("-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----");
+        assert!(matches!(
+            invalid.certificates(),
+            Err(NetworkError::InvalidArgument(message)) if message.contains("无效的CA证书")
+        ));
+        assert!(invalid.validate().is_err());
+        assert!(TrustConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn splits_multiple_pem_blocks() {
+        let pem = "comment\n-----BEGIN CERTIFICATE-----\nA\n-----END CERTIFICATE-----\n\
+                   -----BEGIN CERTIFICATE-----\nB\n-----END CERTIFICATE-----\ntrailing";
+        let blocks = split_pem(pem);
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].starts_with("-----BEGIN CERTIFICATE-----\nA"));
+        assert!(blocks[1].ends_with(PEM_END));
+    }
+
+    #[test]
+    fn matches_insecure_hosts_exactly() {
+        let trust = TrustConfig {
+            insecure_hosts: vec![" Mirror.Local ".to_string()],
+            ..TrustConfig::default()
+        };
+        assert!(trust.is_insecure("mirror.local"));
+        assert!(trust.is_insecure("MIRROR.LOCAL"));
+        assert!(!trust.is_insecure("sub.mirror.local"));
+        assert!(!trust.is_insecure("mirror.local.evil.com"));
+
+        let blank = TrustConfig {
+            insecure_hosts: vec![" ".to_string()],
+            ..TrustConfig::default()
+        };
+        assert!(blank.validate().is_err());
+    }
+
+    #[test]
+    fn resolves_host_overrides() {
+        let trust = TrustConfig {
+            host_overrides: BTreeMap::from([
+                ("Mirror.Local".to_string(), " 10.0.0.2 ".to_string()),
+                ("v6.local".to_string(), "::1".to_string()),
+            ]),
+            ..TrustConfig::default()
+        };
+        assert!(trust.validate().is_ok());
+        assert_eq!(
+            trust.resolve("mirror.local"),
+            Some(IpAddr::from([10, 0, 0, 2]))
+        );
+        assert_eq!(trust.resolve("v6.local"), "::1".parse().ok());
+        assert_eq!(trust.resolve("other.local"), None);
+
+        let invalid = TrustConfig {
+            host_overrides: BTreeMap::from([("mirror.local".to_string(), "10.0.0".to_string())]),
+            ..TrustConfig::default()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(NetworkError::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+/** 后端命令失败时返回的错误码 */
+export type AppErrorCode =
+    | "io"
+    | "invalid_url"
+    | "invalid_argument"
+    | "timeout"
+    | "connection"
+    | "http_status"
+    | "response_parse"
+    | "cache_miss"
+    | "content_length"
+    | "range_not_supported"
+    | "task_already_exists"
+    | "task_not_found"
+    | "invalid_state"
+    | "shutting_down"
+    | "settings"
+    | "cancelled"
+    | "checksum_mismatch"
+    | "batch_failed"
+    | "version_not_found"
+    | "invalid_version"
+    | "other";
+
+/** 后端命令失败时返回的错误，invoke会以该对象reject */
+export interface IAppError {
+    code: AppErrorCode;
+    /** 中文错误信息 */
+    message: string;
+    /** HTTP状态码，只有服务器返回了响应时才有 */
+    status: number | null;
+    /** 响应体的开头部分 */
+    body: string | null;
+    /** 稍后重试是否可能成功 */
+    retryable: boolean;
+    /** 底层错误，由外到内排列 */
+    sources: string[];
+}
+
+export function isAppError(error: unknown): error is IAppError {
+    return (
+        typeof error === "object" &&
+        error !== null &&
+        "code" in error &&
+        "message" in error
+    );
+}
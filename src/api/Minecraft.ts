@@ -0,0 +1,248 @@
+import { Channel, invoke } from "@tauri-apps/api/core";
+import type { IClientVersionItemRaw } from "@/types/Client";
+
+export interface IVersionListRaw {
+    latest: { release: string; snapshot: string };
+    releases: IClientVersionItemRaw[];
+    snapshots: IClientVersionItemRaw[];
+    old_beta: IClientVersionItemRaw[];
+    old_alpha: IClientVersionItemRaw[];
+    /** 4月1日发布的版本，21w13a除外 */
+    april_fools: IClientVersionItemRaw[];
+}
+
+/** 获取按分类整理的版本列表，`refresh`为true时重新从网络获取 */
+export async function getMinecraftVersions(
+    refresh = false
+): Promise<IVersionListRaw> {
+    return await invoke("get_minecraft_versions", { refresh });
+}
+
+/** 按ID查找版本，不存在时以`version_not_found`错误reject */
+export async function getMinecraftVersion(
+    id: string
+): Promise<IClientVersionItemRaw> {
+    return await invoke("get_minecraft_version", { id });
+}
+
+export interface IRule {
+    action: "allow" | "disallow";
+    os: { name: string | null; version: string | null; arch: string | null } | null;
+    features: Record<string, boolean> | null;
+}
+
+export type IArgument =
+    | string
+    | { rules: IRule[]; value: string | string[] };
+
+export interface ILibraryArtifact {
+    path: string | null;
+    url: string;
+    sha1: string | null;
+    size: number | null;
+}
+
+export interface ILibrary {
+    name: string;
+    downloads: {
+        artifact: ILibraryArtifact | null;
+        classifiers: Record<string, ILibraryArtifact>;
+    } | null;
+    url: string | null;
+    sha1: string | null;
+    size: number | null;
+    rules: IRule[] | null;
+    natives: Record<string, string> | null;
+    extract: { exclude: string[] } | null;
+}
+
+/** 合并继承链后的版本JSON */
+export interface IVersionProfile {
+    id: string;
+    inheritsFrom: null;
+    /** 使用的游戏本体所属的版本ID，为空时使用id */
+    jar: string | null;
+    type: string | null;
+    time: string | null;
+    releaseTime: string | null;
+    mainClass: string | null;
+    arguments: { game: IArgument[]; jvm: IArgument[] } | null;
+    minecraftArguments: string | null;
+    libraries: ILibrary[];
+    assetIndex: {
+        id: string;
+        url: string;
+        sha1: string;
+        size: number;
+        totalSize: number;
+    } | null;
+    assets: string | null;
+    downloads: Record<string, { url: string; sha1: string | null; size: number | null }>;
+    javaVersion: { component: string; majorVersion: number } | null;
+    logging: {
+        client: {
+            argument: string;
+            file: { id: string; url: string; sha1: string; size: number };
+            type: string;
+        } | null;
+    } | null;
+}
+
+/** 读取游戏目录中的版本JSON并合并inheritsFrom继承链 */
+export async function getVersionProfile(
+    gameDir: string,
+    versionId: string
+): Promise<IVersionProfile> {
+    return await invoke("get_version_profile", { gameDir, versionId });
+}
+
+export interface ILibraryFile {
+    /** Maven坐标 */
+    name: string;
+    /** 相对于libraries目录的路径 */
+    path: string;
+    /** 加载器安装时本地生成的依赖库没有下载地址 */
+    url: string | null;
+    size: number | null;
+    sha1: string | null;
+    /** 是否为需要解压的本地库 */
+    native: boolean;
+    /** 是否加入classpath，旧版本的本地库jar不加入 */
+    classpath: boolean;
+    extract: { exclude: string[] } | null;
+}
+
+/** 获取版本在当前平台上需要的依赖库文件，已去重并保持加载顺序 */
+export async function getVersionLibraries(
+    gameDir: string,
+    versionId: string
+): Promise<ILibraryFile[]> {
+    return await invoke("get_version_libraries", { gameDir, versionId });
+}
+
+/** 批量下载的进度，completed包括已存在而跳过的文件 */
+export interface IBatchProgress {
+    total: number;
+    completed: number;
+    skipped: number;
+    failed: number;
+    total_bytes: number;
+    completed_bytes: number;
+}
+
+/**
+ * 为实例下载版本的资源索引和资源文件，已存在且校验通过的文件会被跳过。
+ * 可以通过 cancelInstall(installId) 取消下载。
+ */
+export async function installVersionAssets(
+    gameDir: string,
+    versionId: string,
+    instance: ILaunchInstance,
+    onProgress: (progress: IBatchProgress) => void,
+    installId: string = crypto.randomUUID()
+): Promise<IBatchProgress> {
+    const channel = new Channel<IBatchProgress>();
+    channel.onmessage = onProgress;
+    return await invoke("install_version_assets", {
+        gameDir,
+        versionId,
+        instance,
+        installId,
+        onProgress: channel,
+    });
+}
+
+/** 将版本的本地库解压到本次启动使用的新目录，返回该目录；超过一天没有修改的旧目录会被删除 */
+export async function extractVersionNatives(
+    gameDir: string,
+    versionId: string
+): Promise<string> {
+    return await invoke("extract_version_natives", { gameDir, versionId });
+}
+
+/** 安装版本时的阶段和进度 */
+export type IInstallProgress =
+    | { stage: "resolving" }
+    | { stage: "libraries"; progress: IBatchProgress }
+    | { stage: "assets"; progress: IBatchProgress }
+    | { stage: "finalizing" };
+
+/**
+ * 为实例安装版本运行所需的全部文件，已存在且校验通过的文件会被跳过，可以重复执行。
+ * 可以通过 cancelInstall(installId) 取消安装，退出应用时安装同样会被取消。
+ */
+export async function installVersion(
+    gameDir: string,
+    versionId: string,
+    instance: ILaunchInstance,
+    onProgress: (progress: IInstallProgress) => void,
+    installId: string = crypto.randomUUID()
+): Promise<void> {
+    const channel = new Channel<IInstallProgress>();
+    channel.onmessage = onProgress;
+    return await invoke("install_minecraft_version", {
+        gameDir,
+        versionId,
+        instance,
+        installId,
+        onProgress: channel,
+    });
+}
+
+/** 取消正在进行的版本安装，安装不存在或已经结束时返回false */
+export async function cancelInstall(installId: string): Promise<boolean> {
+    return await invoke("cancel_install", { installId });
+}
+
+/** 启动使用的账户 */
+export interface ILaunchAccount {
+    name: string;
+    /** 不含连字符的UUID */
+    uuid: string;
+    access_token: string;
+    /** msa、mojang或legacy */
+    user_type: string;
+    xuid?: string;
+    client_id?: string;
+}
+
+export interface ILaunchInstance {
+    /** 游戏运行目录，开启版本隔离时为版本目录 */
+    game_directory: string;
+}
+
+export interface ILaunchSettings {
+    /** 内存大小（MB），最小内存不能大于最大内存 */
+    /** 内存大小（MB） */
+    min_memory?: number;
+    max_memory?: number;
+    /** 额外的JVM参数，每一项为一个参数 */
+    jvm_args?: string[];
+    window_width?: number;
+    window_height?: number;
+}
+
+export interface ILaunchCommand {
+    program: string;
+    args: string[];
+    working_dir: string;
+}
+
+/** 生成启动游戏的完整命令，nativesDir 为 extractVersionNatives 返回的目录 */
+export async function getLaunchCommand(
+    gameDir: string,
+    versionId: string,
+    nativesDir: string,
+    account: ILaunchAccount,
+    instance: ILaunchInstance,
+    settings: ILaunchSettings
+): Promise<ILaunchCommand> {
+    return await invoke("get_launch_command", {
+        gameDir,
+        versionId,
+        nativesDir,
+        account,
+        instance,
+        settings,
+    });
+}
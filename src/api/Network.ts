@@ -1,16 +1,359 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
+
+export interface IRequestOptions {
+    headers?: Record<string, string>;
+    query?: [string, string][];
+    user_agent?: string;
+    /** 超时时间（秒） */
+    timeout?: number;
+    accept?: string;
+    /** 缓存策略，只作用于GET请求 */
+    cache?: "bypass" | "network_first" | "cache_first" | "cache_only";
+    /** 覆盖配置中的最大重试次数 */
+    max_retries?: number;
+    /** 是否允许重试POST等非幂等请求 */
+    retry_non_idempotent?: boolean;
+}
+
+/** 生成请求ID，传给请求函数后可以通过 cancelRequest 取消该请求 */
+export function createRequestId(): string {
+    return crypto.randomUUID();
+}
+
+/** 取消带有请求ID的请求，被取消的请求以 code 为 "cancelled" 的错误 reject */
+export async function cancelRequest(requestId: string): Promise<boolean> {
+    return await invoke("cancel_request", { requestId });
+}
 
 export async function httpGet(
     url: string,
-    userAgent?: string
+    userAgent?: string,
+    options?: IRequestOptions,
+    requestId?: string
 ): Promise<string> {
-    return await invoke("http_get", { url, user_agent: userAgent });
+    return await invoke("http_get", { url, userAgent, options, requestId });
 }
 
 export async function httpPostJson<T = any>(
     url: string,
     data: T,
-    userAgent?: string
+    userAgent?: string,
+    options?: IRequestOptions,
+    requestId?: string
 ): Promise<string> {
-    return await invoke("http_post_json", { url, data, user_agent: userAgent });
+    return await invoke("http_post_json", {
+        url,
+        data,
+        userAgent,
+        options,
+        requestId,
+    });
+}
+
+export type IRequestBody =
+    | { type: "json"; data: any }
+    | { type: "form"; data: [string, string][] }
+    | { type: "text"; data: string }
+    | { type: "base64"; data: string };
+
+export interface IHttpRequest {
+    method?: string;
+    url: string;
+    options?: IRequestOptions;
+    body?: IRequestBody;
+    response_type?: "text" | "json" | "bytes";
+}
+
+export type IResponseBody =
+    | { type: "text"; data: string }
+    | { type: "json"; data: any }
+    | { type: "bytes"; data: string };
+
+export interface IHttpResponse {
+    status: number;
+    ok: boolean;
+    headers: [string, string][];
+    url: string;
+    body: IResponseBody;
+    from_cache: boolean;
+}
+
+/** 发送通用HTTP请求，非2xx状态码同样以响应返回 */
+export async function httpRequest(
+    request: IHttpRequest,
+    requestId?: string
+): Promise<IHttpResponse> {
+    return await invoke("http_request", { request, requestId });
+}
+
+export type IMultipartPart =
+    | { type: "text"; name: string; value: string }
+    | {
+          type: "file";
+          name: string;
+          /** 磁盘上的文件路径，上传时边读边发送 */
+          path: string;
+          filename?: string;
+          mime?: string;
+      }
+    | {
+          type: "bytes";
+          name: string;
+          /** Base64编码的数据 */
+          data: string;
+          filename?: string;
+          mime?: string;
+      };
+
+export interface IUploadProgress {
+    /** 已发送的字节数，只统计文件和二进制部分 */
+    sent: number;
+    total: number;
+}
+
+/** 以multipart/form-data格式上传，非2xx状态码以错误 reject，响应体按JSON解析 */
+export async function httpPostMultipart(
+    url: string,
+    parts: IMultipartPart[],
+    onProgress?: (progress: IUploadProgress) => void,
+    options?: IRequestOptions,
+    requestId?: string
+): Promise<IHttpResponse> {
+    const channel = new Channel<IUploadProgress>();
+    if (onProgress) channel.onmessage = onProgress;
+
+    return await invoke("http_post_multipart", {
+        url,
+        parts,
+        options,
+        requestId,
+        onProgress: channel,
+    });
+}
+
+export type IStreamData =
+    | { type: "bytes"; data: string }
+    | { type: "text"; data: string }
+    | { type: "records"; data: any[] };
+
+export type IStreamEvent =
+    | {
+          event: "started";
+          data: {
+              status: number;
+              headers: [string, string][];
+              url: string;
+              total: number | null;
+          };
+      }
+    | {
+          event: "chunk";
+          data: {
+              seq: number;
+              received: number;
+              total: number | null;
+              data: IStreamData;
+          };
+      };
+
+/**
+ * 流式读取响应体，返回接收的总字节数
+ *
+ * 事件按顺序交给 onEvent 处理，处理完成后才确认数据块，处理过慢时后端会暂停读取，
+ * 单个数据块处理超过30秒时流以错误结束；
+ * onEvent 抛出错误时取消请求。streamId 同时作为请求ID，可以通过 cancelRequest 取消。
+ */
+export async function httpStream(
+    request: IHttpRequest,
+    onEvent: (event: IStreamEvent) => void | Promise<void>,
+    format: "bytes" | "text" | "ndjson" = "bytes",
+    streamId: string = createRequestId()
+): Promise<number> {
+    const channel = new Channel<IStreamEvent>();
+    let queue = Promise.resolve();
+    let handlerError: unknown = undefined;
+
+    channel.onmessage = (event) => {
+        queue = queue.then(async () => {
+            if (handlerError !== undefined) return;
+            try {
+                await onEvent(event);
+                if (event.event === "chunk") {
+                    await invoke("ack_stream", { streamId, seq: event.data.seq });
+                }
+            } catch (error) {
+                handlerError = error;
+                await cancelRequest(streamId);
+            }
+        });
+    };
+
+    try {
+        return await invoke("http_stream", {
+            request,
+            format,
+            streamId,
+            onEvent: channel,
+        });
+    } finally {
+        await queue;
+        if (handlerError !== undefined) throw handlerError;
+    }
+}
+
+export interface IProxyConfig {
+    mode: "system" | "direct" | "manual";
+    scheme: "http" | "https" | "socks5";
+    host: string;
+    port: number;
+    username?: string;
+    /**
+     * 读取时总是为空；保存时不传表示沿用之前的密码，空字符串表示清除密码。
+     * 密码以明文保存在设置文件中。
+     */
+    password?: string | null;
+    no_proxy: string[];
+}
+
+export async function getProxyConfig(): Promise<IProxyConfig> {
+    return await invoke("get_proxy_config");
+}
+
+export async function setProxyConfig(config: IProxyConfig): Promise<void> {
+    return await invoke("set_proxy_config", { config });
+}
+
+export interface IHttpClientConfig {
+    user_agent: string;
+    connect_timeout: number;
+    request_timeout: number;
+    read_timeout: number;
+    pool_max_idle_per_host: number;
+    pool_idle_timeout: number;
+    http2: boolean;
+    max_redirects: number;
+    min_tls_version?: "tls1_0" | "tls1_1" | "tls1_2" | "tls1_3" | null;
+    accept_invalid_certs: boolean;
+    retry: {
+        max_retries: number;
+        initial_backoff_ms: number;
+        max_backoff_ms: number;
+    };
+}
+
+export async function getHttpConfig(): Promise<IHttpClientConfig> {
+    return await invoke("get_http_config");
+}
+
+export async function setHttpConfig(config: IHttpClientConfig): Promise<void> {
+    return await invoke("set_http_config", { config });
+}
+
+export interface ITrustConfig {
+    /** 额外信任的CA证书（PEM格式），每一项可以包含多个证书 */
+    extra_ca_certs: string[];
+    /** 不校验证书的主机，连接可能被窃听或篡改，只应在确认网络环境可信时使用 */
+    insecure_hosts: string[];
+    /** 主机到IP地址的静态映射，优先于DNS解析 */
+    host_overrides: Record<string, string>;
+}
+
+export async function getTrustConfig(): Promise<ITrustConfig> {
+    return await invoke("get_trust_config");
+}
+
+export async function setTrustConfig(config: ITrustConfig): Promise<void> {
+    return await invoke("set_trust_config", { config });
+}
+
+export interface IMirrorRule {
+    /** 不带协议的主机和路径前缀，如 libraries.minecraft.net */
+    from: string;
+    /** 镜像地址前缀，如 https://bmclapi2.bangbang93.com/maven */
+    to: string;
+}
+
+export interface IMirrorConfig {
+    mode: "official_only" | "mirror_first" | "official_first";
+    rules: IMirrorRule[];
+}
+
+export async function getMirrorConfig(): Promise<IMirrorConfig> {
+    return await invoke("get_mirror_config");
+}
+
+export async function setMirrorConfig(config: IMirrorConfig): Promise<void> {
+    return await invoke("set_mirror_config", { config });
+}
+
+export interface IDiagnosticEndpoint {
+    name: string;
+    url: string;
+    /** 所属的下载源，如 official、bmclapi，不参与比较时省略 */
+    source?: string | null;
+}
+
+export interface IDiagnosticsConfig {
+    endpoints: IDiagnosticEndpoint[];
+    /** 测速时最多读取的字节数 */
+    sample_bytes: number;
+    /** 单个地址的最长诊断时间（秒） */
+    timeout: number;
+}
+
+export interface IEndpointReport {
+    name: string;
+    url: string;
+    source: string | null;
+    /** 使用手动代理时，DNS和连接时间测量的是代理服务器 */
+    via_proxy: boolean;
+    /** 使用系统代理时，DNS、连接和TLS握手直接测量目标主机，首字节时间和吞吐量按系统代理发送 */
+    system_proxy: boolean;
+    dns_ms: number | null;
+    connect_ms: number | null;
+    tls_ms: number | null;
+    ttfb_ms: number | null;
+    status: number | null;
+    sampled_bytes: number;
+    /** 字节/秒 */
+    throughput: number | null;
+    error: string | null;
+}
+
+export interface IDiagnosticsReport {
+    endpoints: IEndpointReport[];
+    recommended_source: string | null;
+    recommended_mode: IMirrorConfig["mode"] | null;
+}
+
+/** 诊断网络连接，未传入配置时检查默认的官方地址和镜像 */
+export async function runNetworkDiagnostics(
+    config?: IDiagnosticsConfig
+): Promise<IDiagnosticsReport> {
+    return await invoke("run_network_diagnostics", { config });
+}
+
+export interface IRecorderConfig {
+    enabled: boolean;
+    /** 最多保留的记录数，超出后丢弃最早的记录 */
+    capacity: number;
+    /** 每个请求和响应体最多记录的字节数 */
+    max_body_bytes: number;
+}
+
+export async function getRecorderConfig(): Promise<IRecorderConfig> {
+    return await invoke("get_recorder_config");
+}
+
+export async function setRecorderConfig(config: IRecorderConfig): Promise<void> {
+    return await invoke("set_recorder_config", { config });
+}
+
+export async function clearTrafficRecords(): Promise<void> {
+    return await invoke("clear_traffic_records");
+}
+
+/** 将记录的流量导出为HAR文件，敏感的请求头已被隐藏 */
+export async function exportHar(path: string): Promise<void> {
+    return await invoke("export_har", { path });
 }
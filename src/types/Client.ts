@@ -17,6 +17,7 @@ export interface IClientVersionItem extends IClientVersionItemBase {
 }
 
 export interface IClientVersionList {
+    latest: { release: string; snapshot: string };
     releases: Array<IClientVersionItem>;
     snapshots: Array<IClientVersionItem>;
     old_beta: Array<IClientVersionItem>;